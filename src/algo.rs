@@ -1,12 +1,40 @@
 // src/algorithms.rs
 
-use crate::logger::Logger;
-use egui::Pos2;
+use crate::logger::{Logger, NoOpLogger};
+use egui::{Pos2, Rect, Vec2};
 
 // Тип для представления пикселя со значением интенсивности (для сглаживания)
 pub type AntialiasedPixel = (i32, i32, f32);
 
+// Расширенная запись о пикселе для наложений, которым нужны метаданные:
+// step_index - номер шага алгоритма, на котором пиксель получен, tag - произвольная метка (например, октант),
+// value - значение внутренней переменной алгоритма в этот момент (например, ошибки Брезенхема)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelRecord {
+    pub x: i32,
+    pub y: i32,
+    pub intensity: f32,
+    pub step_index: usize,
+    pub tag: u8,
+    pub value: f32,
+}
+
+impl PixelRecord {
+    pub fn from_pos(step_index: usize, p: Pos2) -> Self {
+        Self { x: p.x.round() as i32, y: p.y.round() as i32, intensity: 1.0, step_index, tag: 0, value: 0.0 }
+    }
+
+    pub fn from_antialiased(step_index: usize, (x, y, intensity): AntialiasedPixel) -> Self {
+        Self { x, y, intensity, step_index, tag: 0, value: 0.0 }
+    }
+
+    pub fn pos(&self) -> Pos2 {
+        Pos2::new(self.x as f32, self.y as f32)
+    }
+}
+
 // Enum для выбора алгоритма в интерфейсе
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Algorithm {
     StepByStep,
@@ -18,6 +46,88 @@ pub enum Algorithm {
     StepByStepAA,
     DdaAA,
     BresenhamAA,
+    BrushLine,
+    PolygonFillAA,
+    SsaaLine,
+    CustomParametric,
+    EllipseAA,
+    MidpointParabola,
+    Hyperbola,
+    BezierSubdivision,
+    BSpline,
+    CatmullRom,
+    Hermite,
+    RationalBezier,
+    FloodFill,
+    BoundaryFill,
+    TriangleFillAA,
+    GuptaSproull,
+    WuCircle,
+    ThreePointCircle,
+    MidpointLine,
+    PolarCircle,
+    SqrtCircle,
+    RotationCircle,
+    Annulus,
+    RoundedRect,
+    PieSlice,
+    ThickPolyline,
+    FunctionPlot,
+    Line3D,
+    DiamondExit,
+    SymmetricBresenham,
+    RunSliceLine,
+    DoubleStepLine,
+}
+
+impl Algorithm {
+    // Алгоритмы, строящие отрезок по двум точкам P1, P2
+    pub fn is_line(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::StepByStep | Algorithm::DDA | Algorithm::BresenhamLine | Algorithm::WuLine |
+            Algorithm::StepByStepAA | Algorithm::DdaAA | Algorithm::BresenhamAA | Algorithm::BrushLine |
+            Algorithm::SsaaLine | Algorithm::GuptaSproull | Algorithm::MidpointLine | Algorithm::DiamondExit |
+            Algorithm::SymmetricBresenham | Algorithm::RunSliceLine | Algorithm::DoubleStepLine
+        )
+    }
+
+    // Алгоритмы, округляющие координаты согласно RoundingMode
+    pub fn uses_rounding(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::StepByStep | Algorithm::DDA | Algorithm::StepByStepAA | Algorithm::DdaAA |
+            Algorithm::WuLine | Algorithm::CastlePitway
+        )
+    }
+
+    // Кривые по общему списку опорных точек castle_points
+    pub fn is_control_curve(&self) -> bool {
+        matches!(self, Algorithm::CastlePitway | Algorithm::BezierSubdivision | Algorithm::BSpline | Algorithm::CatmullRom | Algorithm::RationalBezier)
+    }
+
+    // Алгоритмы, закрашивающие площадь, а не контур
+    pub fn is_area_fill(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::PolygonFillAA | Algorithm::TriangleFillAA | Algorithm::FloodFill | Algorithm::BoundaryFill |
+            Algorithm::Annulus | Algorithm::PieSlice | Algorithm::ThickPolyline
+        )
+    }
+
+    // Алгоритмы растеризации окружности, участвующие в сравнении
+    pub const CIRCLES: &'static [Algorithm] = &[Algorithm::BresenhamCircle, Algorithm::WuCircle, Algorithm::PolarCircle, Algorithm::SqrtCircle, Algorithm::RotationCircle];
+
+    // Алгоритмы отрезка толщиной в один пиксель без сглаживания и со сглаживанием
+    pub const LINES: &'static [Algorithm] = &[Algorithm::StepByStep, Algorithm::DDA, Algorithm::BresenhamLine, Algorithm::MidpointLine, Algorithm::DiamondExit, Algorithm::SymmetricBresenham, Algorithm::RunSliceLine, Algorithm::DoubleStepLine];
+    pub const AA_LINES: &'static [Algorithm] = &[
+        Algorithm::StepByStepAA,
+        Algorithm::DdaAA,
+        Algorithm::BresenhamAA,
+        Algorithm::GuptaSproull,
+        Algorithm::WuLine,
+        Algorithm::SsaaLine,
+    ];
 }
 
 impl std::fmt::Display for Algorithm {
@@ -26,8 +136,86 @@ impl std::fmt::Display for Algorithm {
     }
 }
 
+// Способ выделения целой и дробной части координаты в алгоритмах сглаживания.
+// Соглашение: базовый пиксель - floor(v), соседний - floor(v) + 1, дробная часть лежит в [0, 1).
+// Truncate оставлен только для демонстрации: при v < 0 trunc(v) != floor(v),
+// дробная часть становится отрицательной, и интенсивности выходят за [0, 1].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FractionMode {
+    Floor,
+    Truncate,
+}
+
+impl std::fmt::Display for FractionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Режим округления координат в алгоритмах с плавающей точкой
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RoundingMode {
+    HalfUp,     // 0.5 округляется от нуля (как f32::round)
+    HalfToEven, // 0.5 округляется к ближайшему чётному
+    Floor,      // отбрасывание дробной части вниз
+}
+
+impl std::fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Округление координаты в выбранном режиме
+pub fn round_with(mode: RoundingMode, v: f32) -> f32 {
+    match mode {
+        RoundingMode::HalfUp => v.round(),
+        RoundingMode::HalfToEven => v.round_ties_even(),
+        RoundingMode::Floor => v.floor(),
+    }
+}
+
+// Рисунок пера вдоль кривой: длины участков "рисуем" и "пропускаем" в пикселях
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PenPattern {
+    Solid,
+    Dashed, // штрих 6, пропуск 4
+    Dotted, // точка 1, пропуск 2
+}
+
+impl PenPattern {
+    fn runs(self) -> Option<(usize, usize)> {
+        match self {
+            PenPattern::Solid => None,
+            PenPattern::Dashed => Some((6, 4)),
+            PenPattern::Dotted => Some((1, 2)),
+        }
+    }
+}
+
+/// Применение рисунка пера к последовательности пикселей кривой: пиксели идут в порядке обхода,
+/// счётчик переключается между участками "рисуем" и "пропускаем"
+pub fn apply_pen_pattern(pixels: Vec<Pos2>, pattern: PenPattern) -> Vec<Pos2> {
+    let Some((on, off)) = pattern.runs() else { return pixels };
+    pixels
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % (on + off) < on)
+        .map(|(_, p)| p)
+        .collect()
+}
+
+/// Разбиение координаты на базовый пиксель и дробную часть
+pub fn split_fraction(v: f32, mode: FractionMode) -> (i32, f32) {
+    let base = match mode {
+        FractionMode::Floor => v.floor(),
+        FractionMode::Truncate => v.trunc(),
+    };
+    (base as i32, v - base)
+}
+
 /// Пошаговый алгоритм
-pub fn step_by_step<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2> {
+pub fn step_by_step<L: Logger>(p1: Pos2, p2: Pos2, rounding: RoundingMode, logger: &mut L) -> Vec<Pos2> {
     let mut pixels = Vec::new();
     let dx = p2.x - p1.x;
     let dy = p2.y - p1.y;
@@ -38,10 +226,12 @@ pub fn step_by_step<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2>
         
         let (start_x, end_x) = if p1.x < p2.x { (p1.x, p2.x) } else { (p2.x, p1.x) };
 
-        for x in (start_x.round() as i32)..=(end_x.round() as i32) {
-            let y = (k * x as f32 + b).round() as i32;
+        let (from, to) = (round_with(rounding, start_x) as i32, round_with(rounding, end_x) as i32);
+        for x in from..=to {
+            logger.progress((x - from) as f32 / (to - from).max(1) as f32);
+            let y = round_with(rounding, k * x as f32 + b) as i32;
             if logger.can_write() {
-                logger.log(format!("x = {}, y = {:.2} * {} + {:.2} = {:.2} -> округляем до {}", x, k, x, b, (k * x as f32 + b), y));
+                logger.log_pixel(x, y, format!("x = {}, y = {:.2} * {} + {:.2} = {:.2} -> округляем до {}", x, k, x, b, (k * x as f32 + b), y));
             }
             pixels.push(Pos2::new(x as f32, y as f32));
         }
@@ -51,10 +241,12 @@ pub fn step_by_step<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2>
         
         let (start_y, end_y) = if p1.y < p2.y { (p1.y, p2.y) } else { (p2.y, p1.y) };
 
-        for y in (start_y.round() as i32)..=(end_y.round() as i32) {
-            let x = (k * y as f32 + b).round() as i32;
+        let (from, to) = (round_with(rounding, start_y) as i32, round_with(rounding, end_y) as i32);
+        for y in from..=to {
+            logger.progress((y - from) as f32 / (to - from).max(1) as f32);
+            let x = round_with(rounding, k * y as f32 + b) as i32;
             if logger.can_write() {
-                logger.log(format!("y = {}, x = {:.2} * {} + {:.2} = {:.2} -> округляем до {}", y, k, y, b, (k * y as f32 + b), x));
+                logger.log_pixel(x, y, format!("y = {}, x = {:.2} * {} + {:.2} = {:.2} -> округляем до {}", y, k, y, b, (k * y as f32 + b), x));
             }
             pixels.push(Pos2::new(x as f32, y as f32));
         }
@@ -63,7 +255,7 @@ pub fn step_by_step<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2>
 }
 
 /// Алгоритм ЦДА
-pub fn dda<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2> {
+pub fn dda<L: Logger>(p1: Pos2, p2: Pos2, rounding: RoundingMode, logger: &mut L) -> Vec<Pos2> {
     let mut pixels = Vec::new();
     let dx = p2.x - p1.x;
     let dy = p2.y - p1.y;
@@ -75,11 +267,18 @@ pub fn dda<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2> {
     let mut x = p1.x;
     let mut y = p1.y;
 
-    for i in 0..=steps.round() as u32 {
-        let ix = x.round() as i32;
-        let iy = y.round() as i32;
+    let count = steps.round() as u32;
+    for i in 0..=count {
+        logger.progress(i as f32 / count.max(1) as f32);
+        let ix = round_with(rounding, x) as i32;
+        let iy = round_with(rounding, y) as i32;
         if logger.can_write() {
-            logger.log(format!("Шаг {}: x = {:.2}, y = {:.2} -> Пиксель ({}, {})", i, x, y, ix, iy));
+            logger.log_step(ix, iy, format!("Шаг {}: x = {:.2}, y = {:.2} -> Пиксель ({}, {})", i, x, y, ix, iy), &[
+                ("шаг", i.to_string()),
+                ("x", format!("{:.2}", x)),
+                ("y", format!("{:.2}", y)),
+                ("пиксель", format!("({}, {})", ix, iy)),
+            ]);
         }
         pixels.push(Pos2::new(ix as f32, iy as f32));
         x += x_inc;
@@ -90,6 +289,11 @@ pub fn dda<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2> {
 
 /// Алгоритм Брезенхема для отрезка
 pub fn bresenham_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2> {
+    bresenham_line_rich(p1, p2, logger).iter().map(PixelRecord::pos).collect()
+}
+
+/// Алгоритм Брезенхема для отрезка с подробным результатом: value - значение err при выводе пикселя
+pub fn bresenham_line_rich<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
     let mut pixels = Vec::new();
     let mut x1 = p1.x.round() as i32;
     let mut y1 = p1.y.round() as i32;
@@ -103,9 +307,16 @@ pub fn bresenham_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2
     let mut err = dx + dy;
 
     loop {
-        pixels.push(Pos2::new(x1 as f32, y1 as f32));
+        let step_index = pixels.len();
+        logger.progress(step_index as f32 / dx.max(-dy).max(1) as f32);
+        pixels.push(PixelRecord { x: x1, y: y1, intensity: 1.0, step_index, tag: 0, value: err as f32 });
         if logger.can_write() {
-            logger.log(format!("Пиксель: ({}, {}), Ошибка: {}", x1, y1, err));
+            logger.log_step(x1, y1, format!("Пиксель: ({}, {}), Ошибка: {}", x1, y1, err), &[
+                ("шаг", step_index.to_string()),
+                ("x", x1.to_string()),
+                ("y", y1.to_string()),
+                ("ошибка", err.to_string()),
+            ]);
         }
         if x1 == x2 && y1 == y2 { break; }
         let e2 = 2 * err;
@@ -121,289 +332,2863 @@ pub fn bresenham_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<Pos2
     pixels
 }
 
-/// Алгоритм Брезенхема для окружности
-pub fn bresenham_circle<L: Logger>(center: Pos2, radius: f32, logger: &mut L) -> Vec<Pos2> {
-    let mut pixels = Vec::new();
-    let cx = center.x.round() as i32;
-    let cy = center.y.round() as i32;
-    let r = radius.round() as i32;
+// Проекция трёхмерных точек на плоскость холста
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Projection {
+    Orthographic,
+    Isometric,
+}
 
-    let mut x = 0;
-    let mut y = r;
-    let mut d = 3 - 2 * r;
+impl Projection {
+    // Точка на холсте и глубина (чем больше, тем дальше от наблюдателя)
+    pub fn project(self, p: [f32; 3]) -> (Pos2, f32) {
+        let [x, y, z] = p;
+        match self {
+            Projection::Orthographic => (Pos2::new(x, y), z),
+            // Оси X и Z уходят под 30° к горизонтали, ось Y остаётся вертикальной
+            Projection::Isometric => {
+                let (sin, cos) = 30.0_f32.to_radians().sin_cos();
+                (Pos2::new((x - z) * cos, y + (x + z) * sin), x + y + z)
+            }
+        }
+    }
+}
 
-    while y >= x {
+impl std::fmt::Display for Projection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Projection::Orthographic => write!(f, "Ортогональная"),
+            Projection::Isometric => write!(f, "Изометрическая"),
+        }
+    }
+}
+
+/// Трёхмерный алгоритм Брезенхема: по ведущей оси (с наибольшим приращением) шаг на каждой
+/// итерации, по двум другим - отдельные ошибки, как у плоского алгоритма с той же ведущей осью.
+/// Каждый шаг меняет каждую координату не больше чем на 1, поэтому вокселы 26-связны
+pub fn bresenham_3d<L: Logger>(p1: [f32; 3], p2: [f32; 3], logger: &mut L) -> Vec<[i32; 3]> {
+    let mut voxels = Vec::new();
+    let mut p = p1.map(|v| v.round() as i32);
+    let end = p2.map(|v| v.round() as i32);
+    let delta = [0, 1, 2].map(|i| (end[i] - p[i]).abs());
+    let step = [0, 1, 2].map(|i| if end[i] >= p[i] { 1 } else { -1 });
+    let major = (0..3).max_by_key(|&i| delta[i]).unwrap_or(0);
+    let minors = [(major + 1) % 3, (major + 2) % 3];
+    let mut err = minors.map(|i| 2 * delta[i] - delta[major]);
+    let axes = ["x", "y", "z"];
+    let err_names = minors.map(|i| ["ошибка x", "ошибка y", "ошибка z"][i]);
+    if logger.can_write() {
+        logger.log(format!("Ведущая ось: {}, шагов: {}", axes[major], delta[major]));
+    }
+    for i in 0..=delta[major] {
+        logger.iteration();
+        logger.progress(i as f32 / delta[major].max(1) as f32);
+        voxels.push(p);
         if logger.can_write() {
-            logger.log(format!("x={}, y={}, d={}", x, y, d));
+            logger.log_fields(p[0], p[1], &[
+                ("шаг", i.to_string()),
+                ("x", p[0].to_string()),
+                ("y", p[1].to_string()),
+                ("z", p[2].to_string()),
+                (err_names[0], err[0].to_string()),
+                (err_names[1], err[1].to_string()),
+            ]);
+        }
+        for (k, &axis) in minors.iter().enumerate() {
+            if err[k] > 0 {
+                p[axis] += step[axis];
+                err[k] -= 2 * delta[major];
+            }
+            err[k] += 2 * delta[axis];
         }
-        // Отрисовка для всех 8 октантов
-        pixels.push(Pos2::new((cx+x) as f32, (cy+y) as f32));
-        pixels.push(Pos2::new((cx-x) as f32, (cy+y) as f32));
-        pixels.push(Pos2::new((cx+x) as f32, (cy-y) as f32));
-        pixels.push(Pos2::new((cx-x) as f32, (cy-y) as f32));
-        pixels.push(Pos2::new((cx+y) as f32, (cy+x) as f32));
-        pixels.push(Pos2::new((cx-y) as f32, (cy+x) as f32));
-        pixels.push(Pos2::new((cx+y) as f32, (cy-x) as f32));
-        pixels.push(Pos2::new((cx-y) as f32, (cy-x) as f32));
+        p[major] += step[major];
+    }
+    voxels
+}
 
-        x += 1;
-        if d > 0 {
-            y -= 1;
-            d = d + 4 * (x - y) + 10;
-        } else {
-            d = d + 4 * x + 6;
+/// Проекция вокселов на холст: интенсивность растёт к наблюдателю от 0.25 у самого дальнего
+/// вокселя до 1 у ближайшего. Если несколько вокселов попадают в один пиксель, виден ближайший
+pub fn project_voxels(voxels: &[[i32; 3]], projection: Projection) -> Vec<AntialiasedPixel> {
+    let projected: Vec<(Pos2, f32)> = voxels.iter().map(|v| projection.project(v.map(|c| c as f32))).collect();
+    let near = projected.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let far = projected.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    let mut pixels: Vec<AntialiasedPixel> = Vec::new();
+    let mut index = std::collections::HashMap::new();
+    for (p, depth) in projected {
+        let intensity = if far > near { 1.0 - 0.75 * (depth - near) / (far - near) } else { 1.0 };
+        let key = (p.x.round() as i32, p.y.round() as i32);
+        match index.get(&key) {
+            Some(&i) => {
+                let pixel: &mut AntialiasedPixel = &mut pixels[i];
+                pixel.2 = pixel.2.max(intensity);
+            }
+            None => {
+                index.insert(key, pixels.len());
+                pixels.push((key.0, key.1, intensity));
+            }
         }
     }
     pixels
 }
 
-/// Алгоритм Кастла-Питвея (Де Кастельжо)
-pub fn castle_pitway<L: Logger>(points: &[Pos2], logger: &mut L) -> Vec<Pos2> {
-    let mut curve_pixels = Vec::new();
-    if points.len() < 2 { return curve_pixels; }
+/// Отрезок методом средней точки: F(x, y) = dy x - dx y + c равна нулю на прямой, и знак
+/// d = 2 F(M) в средней точке M между двумя кандидатами решает, какой из них ближе.
+/// Для единообразия всех октантов работаем в координатах (основная ось, неосновная ось).
+/// value - d на момент вывода пикселя, tag - 1, если шаг был диагональным
+pub fn midpoint_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let (x1, y1) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
 
-    let steps = 1000; // Количество шагов для построения кривой
-    for i in 0..=steps {
-        let t = i as f32 / steps as f32;
-        let mut temp_points = points.to_vec();
+    let steep = (y2 - y1).abs() > (x2 - x1).abs();
+    let (major_start, minor_start, major_end, minor_end) = if steep { (y1, x1, y2, x2) } else { (x1, y1, x2, y2) };
+    let dx = (major_end - major_start).abs();
+    let dy = (minor_end - minor_start).abs();
+    let s_major = if major_end >= major_start { 1 } else { -1 };
+    let s_minor = if minor_end >= minor_start { 1 } else { -1 };
 
-        if logger.can_write() && i % 100 == 0 { // Логируем каждый 100-й шаг
-            logger.log(format!("t = {:.2}", t));
+    // Начальное значение в средней точке (1, 1/2), умноженное на 2, чтобы остаться в целых
+    let mut d = 2 * dy - dx;
+    let (mut major, mut minor) = (major_start, minor_start);
+    let mut diagonal = false;
+    for step_index in 0..=dx as usize {
+        let (x, y) = if steep { (minor, major) } else { (major, minor) };
+        logger.iteration();
+        logger.progress(step_index as f32 / dx.max(1) as f32);
+        pixels.push(PixelRecord { x, y, intensity: 1.0, step_index, tag: diagonal as u8, value: d as f32 });
+        if logger.can_write() {
+            logger.log_fields(x, y, &[
+                ("шаг", step_index.to_string()),
+                ("d", d.to_string()),
+                ("следующий шаг", if d < 0 { "прямо" } else { "по диагонали" }.to_string()),
+            ]);
         }
+        // d < 0: средняя точка выше прямой - остаёмся в строке, иначе переходим на следующую
+        diagonal = d >= 0;
+        if diagonal {
+            d += 2 * (dy - dx);
+            minor += s_minor;
+        } else {
+            d += 2 * dy;
+        }
+        major += s_major;
+    }
+    pixels
+}
 
-        while temp_points.len() > 1 {
-            temp_points = temp_points
-                .windows(2)
-                .map(|p| p[0].lerp(p[1], t))
-                .collect();
+/// Двусторонний (симметричный) алгоритм Брезенхема: отрезок симметричен относительно своей
+/// середины, поэтому второй курсор идёт от P2 навстречу первому, повторяя его решения зеркально.
+/// Итераций вдвое меньше, чем у обычного алгоритма. tag - 0 для курсора от P1, 1 для курсора от P2,
+/// value - ошибка на момент вывода
+pub fn symmetric_bresenham<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let (x1, y1) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
+
+    let steep = (y2 - y1).abs() > (x2 - x1).abs();
+    let (major_start, minor_start, major_end, minor_end) = if steep { (y1, x1, y2, x2) } else { (x1, y1, x2, y2) };
+    let dx = (major_end - major_start).abs();
+    let dy = (minor_end - minor_start).abs();
+    let s_major = if major_end >= major_start { 1 } else { -1 };
+    let s_minor = if minor_end >= minor_start { 1 } else { -1 };
+    let to_xy = |major: i32, minor: i32| if steep { (minor, major) } else { (major, minor) };
+
+    let mut err = 2 * dy - dx;
+    let (mut major, mut minor) = (major_start, minor_start);
+    // Курсоры встречаются в середине: при чётном dx средний пиксель общий и выводится один раз
+    let half = dx / 2;
+    for i in 0..=half {
+        logger.iteration();
+        logger.progress(i as f32 / half.max(1) as f32);
+        let front = to_xy(major, minor);
+        let back = to_xy(major_start + major_end - major, minor_start + minor_end - minor);
+        if logger.can_write() {
+            logger.log_fields(front.0, front.1, &[
+                ("шаг", i.to_string()),
+                ("от P1", format!("({}, {})", front.0, front.1)),
+                ("от P2", format!("({}, {})", back.0, back.1)),
+                ("ошибка", err.to_string()),
+            ]);
         }
-        if let Some(p) = temp_points.first() {
-            let pixel = Pos2::new(p.x.round(), p.y.round());
-            if !curve_pixels.contains(&pixel) {
-                curve_pixels.push(pixel);
-            }
+        pixels.push(PixelRecord { x: front.0, y: front.1, intensity: 1.0, step_index: pixels.len(), tag: 0, value: err as f32 });
+        if 2 * i != dx {
+            pixels.push(PixelRecord { x: back.0, y: back.1, intensity: 1.0, step_index: pixels.len(), tag: 1, value: err as f32 });
         }
+        if err > 0 {
+            minor += s_minor;
+            err -= 2 * dx;
+        }
+        err += 2 * dy;
+        major += s_major;
     }
-    curve_pixels
+    pixels
 }
 
-/// Алгоритм сглаживания Ву
-pub fn wu_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
+/// Алгоритм Брезенхема по сериям (run-slice): у пологого отрезка пиксели идут горизонтальными
+/// сериями, и длина каждой серии - либо floor(dx / dy), либо на единицу больше. Решение
+/// принимается один раз на серию, а не на каждый пиксель: ошибка растёт на 2 (dx mod dy) и при
+/// переполнении удлиняет серию. Первая и последняя серии - половинные. Итераций столько, сколько серий
+/// (dy + 1), поэтому для пологих отрезков выигрыш тем больше, чем меньше наклон.
+/// tag - чётность номера серии, value - длина серии
+pub fn run_slice_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
     let mut pixels = Vec::new();
-    let mut x0 = p1.x;
-    let mut y0 = p1.y;
-    let mut x1 = p2.x;
-    let mut y1 = p2.y;
+    let (x1, y1) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
 
-    let steep = (y1 - y0).abs() > (x1 - x0).abs();
-    if steep {
-        std::mem::swap(&mut x0, &mut y0);
-        std::mem::swap(&mut x1, &mut y1);
+    let steep = (y2 - y1).abs() > (x2 - x1).abs();
+    let (major_start, minor_start, major_end, minor_end) = if steep { (y1, x1, y2, x2) } else { (x1, y1, x2, y2) };
+    let dx = (major_end - major_start).abs();
+    let dy = (minor_end - minor_start).abs();
+    let s_major = if major_end >= major_start { 1 } else { -1 };
+    let s_minor = if minor_end >= minor_start { 1 } else { -1 };
+    let (mut major, mut minor) = (major_start, minor_start);
+
+    let emit_run = |pixels: &mut Vec<PixelRecord>, run: usize, length: i32, major: &mut i32, minor: i32, logger: &mut L| {
+        logger.iteration();
+        logger.progress(run as f32 / dy.max(1) as f32);
+        let (x, y) = if steep { (minor, *major) } else { (*major, minor) };
+        if logger.can_write() {
+            logger.log_fields(x, y, &[
+                ("серия", run.to_string()),
+                ("начало", format!("({}, {})", x, y)),
+                ("длина", length.to_string()),
+            ]);
+        }
+        for _ in 0..length {
+            let (x, y) = if steep { (minor, *major) } else { (*major, minor) };
+            pixels.push(PixelRecord { x, y, intensity: 1.0, step_index: pixels.len(), tag: (run % 2) as u8, value: length as f32 });
+            *major += s_major;
+        }
+    };
+
+    if dy == 0 {
+        emit_run(&mut pixels, 0, dx + 1, &mut major, minor, logger);
+        return pixels;
     }
-    if x0 > x1 {
-        std::mem::swap(&mut x0, &mut x1);
-        std::mem::swap(&mut y0, &mut y1);
+    // Минимальная длина серии и дробная часть dx / dy в виде целочисленной ошибки
+    let whole = dx / dy;
+    let adj_up = (dx % dy) * 2;
+    let adj_down = dy * 2;
+    let mut error = (dx % dy) - dy * 2;
+    // Первая и последняя серии делят одну полную пополам
+    let mut initial = whole / 2 + 1;
+    let last = whole / 2 + 1;
+    if adj_up == 0 && whole % 2 == 0 {
+        initial -= 1;
+    }
+    if whole % 2 == 1 {
+        error += dy;
+    }
+    if logger.can_write() {
+        logger.log(format!("dx = {}, dy = {}: серии по {} или {} пикс., первая {}, последняя {}", dx, dy, whole, whole + 1, initial, last));
     }
 
-    let dx = x1 - x0;
-    let dy = y1 - y0;
-    let gradient = if dx == 0.0 { 1.0 } else { dy / dx };
+    emit_run(&mut pixels, 0, initial, &mut major, minor, logger);
+    for run in 1..dy as usize {
+        minor += s_minor;
+        let mut length = whole;
+        error += adj_up;
+        if error > 0 {
+            length += 1;
+            error -= adj_down;
+        }
+        emit_run(&mut pixels, run, length, &mut major, minor, logger);
+    }
+    minor += s_minor;
+    emit_run(&mut pixels, dy as usize, last, &mut major, minor, logger);
+    pixels
+}
 
-    let mut y = y0;
-    for x in (x0.round() as i32)..=(x1.round() as i32) {
-        let y_floor = y.floor();
-        let fractional_part = y - y_floor;
+/// Двухшаговый алгоритм Ву (double-step): за итерацию выбираются сразу два следующих пикселя.
+/// Для двух шагов по основной оси возможны четыре образца: 1 - оба прямо, 2 - прямо и вверх,
+/// 3 - вверх и прямо, 4 - оба вверх. При наклоне меньше 1/2 образец 4 невозможен, при наклоне
+/// не меньше 1/2 - образец 1, поэтому одна переменная d решает "1 или 2/3" (соответственно
+/// "2/3 или 4"), а второе сравнение с 2dy (2dy - 2dx) различает образцы 2 и 3.
+/// tag - номер образца, value - d перед решением
+pub fn double_step_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let (x1, y1) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
 
-        let intensity1 = 1.0 - fractional_part;
-        let intensity2 = fractional_part;
+    let steep = (y2 - y1).abs() > (x2 - x1).abs();
+    let (major_start, minor_start, major_end, minor_end) = if steep { (y1, x1, y2, x2) } else { (x1, y1, x2, y2) };
+    let dx = (major_end - major_start).abs();
+    let dy = (minor_end - minor_start).abs();
+    let s_major = if major_end >= major_start { 1 } else { -1 };
+    let s_minor = if minor_end >= minor_start { 1 } else { -1 };
+    let push = |pixels: &mut Vec<PixelRecord>, major: i32, minor: i32, pattern: u8, d: i32| {
+        let (x, y) = if steep { (minor, major) } else { (major, minor) };
+        pixels.push(PixelRecord { x, y, intensity: 1.0, step_index: pixels.len(), tag: pattern, value: d as f32 });
+    };
 
-        if steep {
-            pixels.push((y.floor() as i32, x, intensity1));
-            pixels.push((y.floor() as i32 + 1, x, intensity2));
+    let (mut major, mut minor) = (major_start, minor_start);
+    push(&mut pixels, major, minor, 0, 0);
+    // d = 2dx e + 4dy - dx (пологий) или 2dx e + 4dy - 3dx (крутой), где e - ошибка текущего пикселя
+    let shallow = 2 * dy < dx;
+    let mut d = if shallow { 4 * dy - dx } else { 4 * dy - 3 * dx };
+    for step in 0..dx / 2 {
+        logger.iteration();
+        logger.progress(step as f32 / (dx / 2).max(1) as f32);
+        let pattern = if shallow {
+            if d < 0 { 1 } else if d < 2 * dy { 2 } else { 3 }
+        } else if d >= 0 {
+            4
+        } else if d < 2 * dy - 2 * dx {
+            2
         } else {
-            pixels.push((x, y.floor() as i32, intensity1));
-            pixels.push((x, y.floor() as i32 + 1, intensity2));
+            3
+        };
+        let (first, second) = match pattern {
+            1 => (0, 0),
+            2 => (0, 1),
+            3 => (1, 1),
+            _ => (1, 2),
+        };
+        if logger.can_write() {
+            let (x, y) = if steep { (minor, major) } else { (major, minor) };
+            logger.log_fields(x, y, &[
+                ("шаг", step.to_string()),
+                ("d", d.to_string()),
+                ("образец", pattern.to_string()),
+            ]);
+        }
+        push(&mut pixels, major + s_major, minor + first * s_minor, pattern, d);
+        push(&mut pixels, major + 2 * s_major, minor + second * s_minor, pattern, d);
+        major += 2 * s_major;
+        minor += second * s_minor;
+        d += 4 * dy - 2 * dx * second;
+    }
+    // При нечётном dx остаётся один шаг - он приходит точно в конечную точку
+    if dx % 2 == 1 {
+        push(&mut pixels, major_end, minor_end, 0, d);
+    }
+    logger.progress(1.0);
+    pixels
+}
+
+// Сдвиг ромбов для однозначного выбора, когда отрезок проходит через вершину ромба
+const DIAMOND_BIAS: f32 = 1e-3;
+
+/// Правило выхода из ромба (diamond-exit), по которому отрезки растеризует графический
+/// процессор: вокруг центра каждого пикселя - ромб |dx| + |dy| < 1/2, и пиксель закрашивается,
+/// если отрезок проходит через его ромб и выходит из него. Ромб, внутри которого отрезок
+/// заканчивается, не закрашивается - у стыкующихся отрезков общий конец выводится один раз.
+/// Концы не округляются заранее, поэтому на дробных концах выбор пикселей отличается от Брезенхема.
+/// Если отрезок проходит ровно через вершину между двумя ромбами, выбор решает малый сдвиг ромбов
+/// на (√2 DIAMOND_BIAS, DIAMOND_BIAS): верхняя и правая вершины считаются принадлежащими ромбу
+/// (отношение сдвигов иррационально, поэтому сдвиг не параллелен ни одному отрезку с целыми концами).
+/// value - параметр t выхода из ромба, пиксели упорядочены по нему
+pub fn diamond_exit_line<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<PixelRecord> {
+    let d = p2 - p1;
+    if d == Vec2::ZERO {
+        return Vec::new();
+    }
+    // Кандидаты - по три пикселя поперёк отрезка на каждом шаге по основной оси
+    let steep = d.y.abs() > d.x.abs();
+    let (major1, minor1, major_d, minor_d) = if steep { (p1.y, p1.x, d.y, d.x) } else { (p1.x, p1.y, d.x, d.y) };
+    let (from, to) = (major1.min(major1 + major_d).floor() as i32, major1.max(major1 + major_d).ceil() as i32);
+    let mut candidates = Vec::new();
+    for major in from..=to {
+        let t = ((major as f32 - major1) / major_d).clamp(0.0, 1.0);
+        let minor = (minor1 + t * minor_d).round() as i32;
+        for m in (minor - 1)..=(minor + 1) {
+            candidates.push(if steep { (m, major) } else { (major, m) });
+        }
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut lit = Vec::new();
+    let total = candidates.len();
+    for (i, (x, y)) in candidates.into_iter().enumerate() {
+        logger.iteration();
+        logger.progress(i as f32 / total.saturating_sub(1).max(1) as f32);
+        // Отсечение P(t) = P1 + t D ромбом: четыре полуплоскости a u + b v < 1/2
+        let (u, v) = (p1.x - x as f32 - std::f32::consts::SQRT_2 * DIAMOND_BIAS, p1.y - y as f32 - DIAMOND_BIAS);
+        let (mut t_enter, mut t_leave) = (0.0_f32, 1.0_f32);
+        for (a, b) in [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)] {
+            let f0 = a * u + b * v - 0.5;
+            let fd = a * d.x + b * d.y;
+            if fd == 0.0 {
+                if f0 >= 0.0 {
+                    t_leave = -1.0;
+                }
+            } else if fd > 0.0 {
+                t_leave = t_leave.min(-f0 / fd);
+            } else {
+                t_enter = t_enter.max(-f0 / fd);
+            }
+        }
+        if t_enter >= t_leave {
+            continue;
+        }
+        if t_leave >= 1.0 {
+            if logger.can_write() {
+                logger.log_pixel(x, y, format!("({}, {}): отрезок заканчивается внутри ромба - пиксель не закрашивается", x, y));
+            }
+            continue;
         }
+        lit.push((t_leave, x, y, t_enter));
+    }
+    lit.sort_by(|a, b| a.0.total_cmp(&b.0));
 
+    let mut pixels = Vec::new();
+    for (step_index, (t_leave, x, y, t_enter)) in lit.into_iter().enumerate() {
         if logger.can_write() {
-            logger.log(format!(
-                "x: {}, y: {:.2}, пиксели: ({}, {}, {:.2}), ({}, {}, {:.2})",
-                x, y,
-                if steep {y.floor() as i32} else {x}, if steep {x} else {y.floor() as i32}, intensity1,
-                if steep {y.floor() as i32 + 1} else {x}, if steep {x} else {y.floor() as i32 + 1}, intensity2
-            ));
+            logger.log_fields(x, y, &[
+                ("шаг", step_index.to_string()),
+                ("x", x.to_string()),
+                ("y", y.to_string()),
+                ("вход t", format!("{:.3}", t_enter)),
+                ("выход t", format!("{:.3}", t_leave)),
+            ]);
         }
-        y += gradient;
+        pixels.push(PixelRecord { x, y, intensity: 1.0, step_index, tag: 0, value: t_leave });
     }
     pixels
 }
 
-pub fn step_by_step_aa<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
-    let mut pixels = Vec::new();
-    let dx = p2.x - p1.x;
-    let dy = p2.y - p1.y;
+// Маска октантов окружности: бит k включает k-й октант (нумерация против часовой стрелки от оси X)
+pub const ALL_OCTANTS: u8 = 0xFF;
 
-    if dx.abs() > dy.abs() {
-        let k = dy / dx;
-        let b = p1.y - k * p1.x;
-        let (start_x, end_x) = if p1.x < p2.x { (p1.x, p2.x) } else { (p2.x, p1.x) };
+// Порядок октантов в цикле окружности Брезенхема: первым вычисляется октант 1, остальные - его отражения
+const OCTANT_ORDER: [u8; 8] = [1, 2, 6, 5, 0, 3, 7, 4];
 
-        for x_int in (start_x.round() as i32)..=(end_x.round() as i32) {
-            let x = x_int as f32;
-            let y_ideal = k * x + b;
-            let y_floor = y_ideal.floor();
-            let fractional = y_ideal - y_floor;
+/// Пиксели окружности Брезенхема, сгруппированные по октантам (tag): сначала весь вычисляемый октант 1
+/// в порядке шагов, затем каждое отражение. При пошаговом просмотре видно, как строится один октант,
+/// а потом появляются его зеркальные копии
+pub fn group_by_octant(mut records: Vec<PixelRecord>) -> Vec<PixelRecord> {
+    records.sort_by_key(|r| (OCTANT_ORDER.iter().position(|&o| o == r.tag), r.step_index));
+    records
+}
 
-            let y1 = y_floor as i32;
-            let y2 = y1 + 1;
-            let intensity1 = 1.0 - fractional;
-            let intensity2 = fractional;
+// Кисть: квадратная двоичная маска size x size, центр маски совмещается с пикселем линии
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stamp {
+    pub size: usize,
+    pub cells: Vec<bool>,
+}
 
-            pixels.push((x_int, y1, intensity1));
-            pixels.push((x_int, y2, intensity2));
+impl Stamp {
+    pub fn empty(size: usize) -> Self {
+        Self { size, cells: vec![false; size * size] }
+    }
 
-            if logger.can_write() {
-                logger.log(format!("x: {}, y_ideal: {:.2} -> ({}, {:.2}), ({}, {:.2})", x_int, y_ideal, y1, intensity1, y2, intensity2));
+    // Крест 3x3
+    pub fn cross3() -> Self {
+        let mut stamp = Self::empty(3);
+        for i in 0..3 {
+            stamp.set(1, i, true);
+            stamp.set(i, 1, true);
+        }
+        stamp
+    }
+
+    // Диск 5x5
+    pub fn disc5() -> Self {
+        let mut stamp = Self::empty(5);
+        for row in 0..5 {
+            for col in 0..5 {
+                let dx = col as i32 - 2;
+                let dy = row as i32 - 2;
+                stamp.set(col, row, dx * dx + dy * dy <= 5);
             }
         }
-    } else {
-        // Аналогичная логика для dy > dx
-        let k = dx / dy;
-        let b = p1.x - k * p1.y;
-        let (start_y, end_y) = if p1.y < p2.y { (p1.y, p2.y) } else { (p2.y, p1.y) };
+        stamp
+    }
 
-        for y_int in (start_y.round() as i32)..=(end_y.round() as i32) {
-            let y = y_int as f32;
-            let x_ideal = k * y + b;
-            let x_floor = x_ideal.floor();
-            let fractional = x_ideal - x_floor;
+    pub fn get(&self, col: usize, row: usize) -> bool {
+        self.cells[row * self.size + col]
+    }
 
-            let x1 = x_floor as i32;
-            let x2 = x1 + 1;
-            let intensity1 = 1.0 - fractional;
-            let intensity2 = fractional;
+    pub fn set(&mut self, col: usize, row: usize, value: bool) {
+        self.cells[row * self.size + col] = value;
+    }
 
-            pixels.push((x1, y_int, intensity1));
-            pixels.push((x2, y_int, intensity2));
+    // Изменение размера с сохранением левого верхнего угла маски
+    pub fn resize(&mut self, size: usize) {
+        let mut resized = Self::empty(size);
+        for row in 0..size.min(self.size) {
+            for col in 0..size.min(self.size) {
+                resized.set(col, row, self.get(col, row));
+            }
+        }
+        *self = resized;
+    }
 
-            if logger.can_write() {
-                logger.log(format!("y: {}, x_ideal: {:.2} -> ({}, {:.2}), ({}, {:.2})", y_int, x_ideal, x1, intensity1, x2, intensity2));
+    // Смещения включённых ячеек относительно центра маски
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        let half = (self.size / 2) as i32;
+        let mut offsets = Vec::new();
+        for row in 0..self.size {
+            for col in 0..self.size {
+                if self.get(col, row) {
+                    offsets.push((col as i32 - half, row as i32 - half));
+                }
             }
         }
+        offsets
     }
-    pixels
 }
 
-/// Алгоритм ЦДА со сглаживанием
-pub fn dda_aa<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
-    let mut pixels = Vec::new();
-    let dx = p2.x - p1.x;
-    let dy = p2.y - p1.y;
+// Узор заливки: маска 8x8, повторяющаяся по всей плоскости; бит x строки y включает пиксель
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FillPattern {
+    pub rows: [u8; 8],
+}
 
-    let steps = if dx.abs() > dy.abs() { dx.abs() } else { dy.abs() };
-    let x_inc = dx / steps;
-    let y_inc = dy / steps;
+impl FillPattern {
+    pub fn solid() -> Self {
+        Self { rows: [0xFF; 8] }
+    }
 
-    let mut x_ideal = p1.x;
-    let mut y_ideal = p1.y;
+    // Диагональная штриховка
+    pub fn hatch() -> Self {
+        let mut rows = [0; 8];
+        for (y, row) in rows.iter_mut().enumerate() {
+            *row = 1 << y;
+        }
+        Self { rows }
+    }
 
-    let is_steep = dy.abs() > dx.abs();
+    // Штриховка в обе диагонали
+    pub fn crosshatch() -> Self {
+        let mut rows = [0; 8];
+        for (y, row) in rows.iter_mut().enumerate() {
+            *row = (1 << y) | (1 << (7 - y));
+        }
+        Self { rows }
+    }
 
-    for _ in 0..=steps.round() as u32 {
-        if is_steep {
-            let x_floor = x_ideal.floor();
-            let fractional = x_ideal - x_floor;
-            let x1 = x_floor as i32;
-            let x2 = x1 + 1;
-            pixels.push((x1, y_ideal.round() as i32, 1.0 - fractional));
-            pixels.push((x2, y_ideal.round() as i32, fractional));
+    // Шахматная доска из клеток 2x2
+    pub fn checker() -> Self {
+        Self { rows: [0x33, 0x33, 0xCC, 0xCC, 0x33, 0x33, 0xCC, 0xCC] }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.rows == [0xFF; 8]
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> bool {
+        self.rows[row] & (1 << col) != 0
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, value: bool) {
+        if value {
+            self.rows[row] |= 1 << col;
         } else {
-            let y_floor = y_ideal.floor();
-            let fractional = y_ideal - y_floor;
-            let y1 = y_floor as i32;
-            let y2 = y1 + 1;
-            pixels.push((x_ideal.round() as i32, y1, 1.0 - fractional));
-            pixels.push((x_ideal.round() as i32, y2, fractional));
+            self.rows[row] &= !(1 << col);
         }
+    }
+
+    // Включён ли пиксель растра: маска привязана к началу координат, а не к фигуре
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.get(x.rem_euclid(8) as usize, y.rem_euclid(8) as usize)
+    }
+}
 
+/// Линия кистью: штамп накладывается на каждый spacing-й пиксель линии Брезенхема
+pub fn brush_line<L: Logger>(p1: Pos2, p2: Pos2, stamp: &Stamp, spacing: u32, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    let offsets = stamp.offsets();
+    let spacing = spacing.max(1) as usize;
+
+    let centers = bresenham_line(p1, p2, &mut NoOpLogger);
+    for (i, center) in centers.iter().enumerate().step_by(spacing) {
+        logger.progress(i as f32 / (centers.len() - 1).max(1) as f32);
+        let cx = center.x as i32;
+        let cy = center.y as i32;
+        let mut added = 0;
+        for &(dx, dy) in &offsets {
+            if seen.insert((cx + dx, cy + dy)) {
+                pixels.push(Pos2::new((cx + dx) as f32, (cy + dy) as f32));
+                added += 1;
+            }
+        }
         if logger.can_write() {
-            logger.log(format!("x: {:.2}, y: {:.2}", x_ideal, y_ideal));
+            logger.log_pixel(cx, cy, format!("Штамп {}: центр ({}, {}), новых пикселей: {}", i / spacing, cx, cy, added));
         }
-
-        x_ideal += x_inc;
-        y_ideal += y_inc;
     }
     pixels
 }
 
-pub fn bresenham_aa<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
+/// Алгоритм Брезенхема для окружности
+pub fn bresenham_circle<L: Logger>(center: Pos2, radius: f32, octants: u8, logger: &mut L) -> Vec<PixelRecord> {
     let mut pixels = Vec::new();
-    let x1 = p1.x.round() as i32;
-    let y1 = p1.y.round() as i32;
-    let x2 = p2.x.round() as i32;
-    let y2 = p2.y.round() as i32;
+    let cx = center.x.round() as i32;
+    let cy = center.y.round() as i32;
+    let r = radius.round() as i32;
+
+    let mut x = 0;
+    let mut y = r;
+    let mut d = 3 - 2 * r;
+    let mut step_index = 0;
+
+    // Цикл идёт по x от 0 до r/sqrt(2)
+    let x_end = (r as f32 * std::f32::consts::FRAC_1_SQRT_2).max(1.0);
+    while y >= x {
+        logger.iteration();
+        logger.progress((x as f32 / x_end).min(1.0));
+        if logger.can_write() {
+            logger.log(format!("x={}, y={}, d={}", x, y, d));
+        }
+        // Отражения для всех 8 октантов (порядок совпадает с исходной реализацией)
+        let reflections = [
+            (1, cx + x, cy + y),
+            (2, cx - x, cy + y),
+            (6, cx + x, cy - y),
+            (5, cx - x, cy - y),
+            (0, cx + y, cy + x),
+            (3, cx - y, cy + x),
+            (7, cx + y, cy - x),
+            (4, cx - y, cy - x),
+        ];
+        for (octant, px, py) in reflections {
+            if octants & (1 << octant) == 0 {
+                continue;
+            }
+            if logger.can_write() {
+                logger.log_pixel(px, py, format!("  октант {}: ({}, {})", octant, px, py));
+            }
+            pixels.push(PixelRecord { x: px, y: py, intensity: 1.0, step_index, tag: octant, value: d as f32 });
+        }
+
+        step_index += 1;
+        x += 1;
+        if d > 0 {
+            y -= 1;
+            d = d + 4 * (x - y) + 10;
+        } else {
+            d = d + 4 * x + 6;
+        }
+    }
+    logger.progress(1.0);
+    pixels
+}
+
+/// Сглаженная окружность в духе алгоритма Ву: во втором октанте для каждого x точное
+/// y = sqrt(r^2 - x^2) лежит между двумя пикселями, и интенсивность делится между ними
+/// по дробной части y. Остальные октанты получаются отражениями, как у Брезенхема; на осях (x = 0)
+/// и диагоналях (x = y) отражения совпадают, и такой пиксель выводится один раз
+pub fn wu_circle<L: Logger>(center: Pos2, radius: f32, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let cx = center.x.round() as i32;
+    let cy = center.y.round() as i32;
+    let r = radius.abs();
+    let x_end = r * std::f32::consts::FRAC_1_SQRT_2;
+
+    let mut x = 0;
+    while x as f32 <= x_end {
+        logger.iteration();
+        logger.progress(x as f32 / x_end.max(1.0));
+        let y = (r * r - (x * x) as f32).sqrt();
+        let (base, frac) = split_fraction(y, FractionMode::Floor);
+        if logger.can_write() {
+            logger.log_fields(cx + x, cy + base, &[
+                ("x", x.to_string()),
+                ("y", format!("{:.3}", y)),
+                ("I", format!("{:.2} / {:.2}", 1.0 - frac, frac)),
+            ]);
+        }
+        for (dy, intensity) in [(base, 1.0 - frac), (base + 1, frac)] {
+            if intensity <= 0.0 {
+                continue;
+            }
+            let reflections = [(x, dy), (-x, dy), (x, -dy), (-x, -dy), (dy, x), (-dy, x), (dy, -x), (-dy, -x)];
+            for (i, &(px, py)) in reflections.iter().enumerate() {
+                if !reflections[..i].contains(&(px, py)) {
+                    pixels.push((cx + px, cy + py, intensity));
+                }
+            }
+        }
+        x += 1;
+    }
+    logger.progress(1.0);
+    pixels
+}
+
+/// Окружность по параметрическому уравнению: угол меняется с шагом Δθ = 1/R (дуга длиной около
+/// пикселя), в каждой точке вычисляются R cos θ и R sin θ и округляются до целых.
+/// Два вызова тригонометрии на точку, а соседние точки часто попадают в один пиксель
+pub fn polar_circle<L: Logger>(center: Pos2, radius: f32, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    let r = radius.abs();
+    let delta = 1.0 / r.max(1.0);
+    let steps = (std::f32::consts::TAU / delta).ceil() as usize;
+    for i in 0..steps {
+        logger.iteration();
+        logger.progress(i as f32 / steps as f32);
+        let theta = i as f32 * delta;
+        let (sin, cos) = theta.sin_cos();
+        let p = Pos2::new((center.x + r * cos).round(), (center.y + r * sin).round());
+        if logger.can_write() {
+            logger.log_fields(p.x as i32, p.y as i32, &[
+                ("θ", format!("{:.4}", theta)),
+                ("cos θ", format!("{:.4}", cos)),
+                ("sin θ", format!("{:.4}", sin)),
+            ]);
+        }
+        pixels.push(p);
+    }
+    pixels
+}
+
+/// Окружность по явному уравнению: для каждого столбца x ∈ [0, R] вычисляется y = sqrt(R^2 - x^2)
+/// и округляется, остальные три четверти получаются отражением. Шаг всегда идёт по x, поэтому
+/// возле x ≈ R, где наклон круче 1, соседние столбцы расходятся по y больше чем на пиксель (разрывы)
+pub fn sqrt_circle<L: Logger>(center: Pos2, radius: f32, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    let cx = center.x.round() as i32;
+    let cy = center.y.round() as i32;
+    let r = radius.abs().round() as i32;
+    for x in 0..=r {
+        logger.iteration();
+        logger.progress(x as f32 / r.max(1) as f32);
+        let exact = ((r * r - x * x) as f32).sqrt();
+        let y = exact.round() as i32;
+        if logger.can_write() {
+            logger.log_fields(cx + x, cy + y, &[
+                ("x", x.to_string()),
+                ("sqrt(R² - x²)", format!("{:.4}", exact)),
+                ("y", y.to_string()),
+            ]);
+        }
+        for (px, py) in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+            pixels.push(Pos2::new((cx + px) as f32, (cy + py) as f32));
+        }
+    }
+    pixels
+}
+
+/// Окружность инкрементным поворотом: точка (R, 0) поворачивается на Δ = 1/R по рекуррентным
+/// формулам x' = x cos Δ - y sin Δ, y' = x sin Δ + y cos Δ. Синус и косинус вычисляются один раз,
+/// дальше только умножения и сложения, но ошибки округления накапливаются: радиус точки
+/// постепенно уходит от R (дрейф показывается в журнале)
+pub fn rotation_circle<L: Logger>(center: Pos2, radius: f32, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    let r = radius.abs();
+    let delta = 1.0 / r.max(1.0);
+    let (sin_d, cos_d) = delta.sin_cos();
+    let steps = (std::f32::consts::TAU / delta).ceil() as usize;
+    let (mut x, mut y) = (r, 0.0_f32);
+    for i in 0..steps {
+        logger.iteration();
+        logger.progress(i as f32 / steps as f32);
+        let p = Pos2::new((center.x + x).round(), (center.y + y).round());
+        if logger.can_write() {
+            logger.log_fields(p.x as i32, p.y as i32, &[
+                ("шаг", i.to_string()),
+                ("x", format!("{:.4}", x)),
+                ("y", format!("{:.4}", y)),
+                ("дрейф радиуса", format!("{:+.6}", x.hypot(y) - r)),
+            ]);
+        }
+        pixels.push(p);
+        (x, y) = (x * cos_d - y * sin_d, x * sin_d + y * cos_d);
+    }
+    if logger.can_write() {
+        logger.log(format!("Итоговый дрейф радиуса за оборот: {:+.6}", x.hypot(y) - r));
+    }
+    pixels
+}
+
+/// Прямоугольник со скруглёнными углами: стороны - отрезки Брезенхема, углы - по два октанта
+/// окружности Брезенхема радиуса radius с центрами, отступающими от углов на radius.
+/// tag - 0 для сторон, 1 для дуг; общие пиксели на стыках выводятся один раз
+pub fn rounded_rect<L: Logger>(center: Pos2, width: f32, height: f32, radius: f32, logger: &mut L) -> Vec<PixelRecord> {
+    let half_w = (width.abs() / 2.0).round() as i32;
+    let half_h = (height.abs() / 2.0).round() as i32;
+    let r = (radius.abs().round() as i32).min(half_w).min(half_h);
+    let (cx, cy) = (center.x.round() as i32, center.y.round() as i32);
+    let (left, right) = (cx - half_w + r, cx + half_w - r);
+    let (bottom, top) = (cy - half_h + r, cy + half_h - r);
+    let point = |x: i32, y: i32| Pos2::new(x as f32, y as f32);
+
+    let mut parts: Vec<(u8, Vec<PixelRecord>)> = Vec::new();
+    let sides = [
+        ("верхняя", point(left, top + r), point(right, top + r)),
+        ("правая", point(right + r, top), point(right + r, bottom)),
+        ("нижняя", point(right, bottom - r), point(left, bottom - r)),
+        ("левая", point(left - r, bottom), point(left - r, top)),
+    ];
+    // Октанты нумеруются против часовой стрелки от оси X: правому верхнему углу нужны 0 и 1 и т. д.
+    let corners = [
+        ("правый верхний", point(right, top), 0b0000_0011),
+        ("левый верхний", point(left, top), 0b0000_1100),
+        ("левый нижний", point(left, bottom), 0b0011_0000),
+        ("правый нижний", point(right, bottom), 0b1100_0000),
+    ];
+    for ((side, from, to), (corner, arc_center, octants)) in sides.into_iter().zip(corners) {
+        if logger.can_write() {
+            logger.log(format!("Сторона {}: ({}, {}) - ({}, {})", side, from.x, from.y, to.x, to.y));
+        }
+        parts.push((0, bresenham_line_rich(from, to, logger)));
+        if r > 0 {
+            if logger.can_write() {
+                logger.log(format!("Угол {}: центр ({}, {}), октанты {:08b}", corner, arc_center.x, arc_center.y, octants));
+            }
+            parts.push((1, bresenham_circle(arc_center, r as f32, octants, logger)));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut pixels = Vec::new();
+    for (tag, records) in parts {
+        for record in records {
+            if seen.insert((record.x, record.y)) {
+                let step_index = pixels.len();
+                pixels.push(PixelRecord { tag, step_index, ..record });
+            }
+        }
+    }
+    pixels
+}
+
+/// Закрашенный сектор: дуга окружности Брезенхема от угла start до угла end (в градусах, против
+/// часовой стрелки), два радиальных отрезка Брезенхема и заливка внутренности построчно - пересечения
+/// строки с многоугольником, аппроксимирующим сектор, дают пролёты без пикселей границы.
+/// Равные углы задают полный круг. tag - 0 для дуги, 1 для радиусов, 2 для заливки
+pub fn pie_slice<L: Logger>(center: Pos2, radius: f32, start: f32, end: f32, pattern: &FillPattern, logger: &mut L) -> Vec<PixelRecord> {
+    let mut sweep = (end - start).rem_euclid(360.0);
+    if sweep == 0.0 {
+        sweep = 360.0;
+    }
+    let (cx, cy) = (center.x.round() as i32, center.y.round() as i32);
+    let r = radius.abs().round();
+    let origin = Pos2::new(cx as f32, cy as f32);
+    let in_sweep = |dx: f32, dy: f32| (dy.atan2(dx).to_degrees() - start).rem_euclid(360.0) <= sweep;
+    let radial_end = |angle: f32| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Pos2::new((origin.x + r * cos).round(), (origin.y + r * sin).round())
+    };
+
+    let mut parts: Vec<(u8, Vec<PixelRecord>)> = Vec::new();
+    let arc: Vec<PixelRecord> = bresenham_circle(origin, r, ALL_OCTANTS, &mut NoOpLogger)
+        .into_iter()
+        .filter(|p| in_sweep((p.x - cx) as f32, (p.y - cy) as f32))
+        .collect();
+    if logger.can_write() {
+        logger.log(format!("Дуга R = {} от {:.1}° до {:.1}°: {} пикс.", r, start, start + sweep, arc.len()));
+    }
+    parts.push((0, arc));
+    if sweep < 360.0 {
+        for angle in [start, start + sweep] {
+            let to = radial_end(angle);
+            if logger.can_write() {
+                logger.log(format!("Радиус под углом {:.1}°: ({}, {}) - ({}, {})", angle, cx, cy, to.x, to.y));
+            }
+            parts.push((1, bresenham_line_rich(origin, to, logger)));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut pixels = Vec::new();
+    for (tag, records) in parts {
+        for record in records {
+            if seen.insert((record.x, record.y)) {
+                let step_index = pixels.len();
+                pixels.push(PixelRecord { tag, step_index, ..record });
+            }
+        }
+    }
+
+    // Многоугольник сектора: центр и точки дуги через каждые ~2°
+    let segments = (sweep / 2.0).ceil().max(1.0) as usize;
+    let mut polygon = vec![origin];
+    polygon.extend((0..=segments).map(|i| {
+        let (sin, cos) = (start + sweep * i as f32 / segments as f32).to_radians().sin_cos();
+        origin + r * Vec2::new(cos, sin)
+    }));
+    let rows = r as i32;
+    for y in (cy - rows)..=(cy + rows) {
+        logger.iteration();
+        logger.progress((y - cy + rows) as f32 / (2 * rows).max(1) as f32);
+        let xs = scanline_intersections(&polygon, y as f32);
+        let mut filled = 0;
+        for span in xs.chunks_exact(2) {
+            for x in (span[0].ceil() as i32)..=(span[1].floor() as i32) {
+                if seen.contains(&(x, y)) || !pattern.contains(x, y) {
+                    continue;
+                }
+                let step_index = pixels.len();
+                pixels.push(PixelRecord { x, y, intensity: 1.0, step_index, tag: 2, value: 0.0 });
+                filled += 1;
+            }
+        }
+        if logger.can_write() && filled > 0 {
+            let text: Vec<String> = xs.chunks_exact(2).map(|s| format!("[{:.2}, {:.2}]", s[0], s[1])).collect();
+            logger.log(format!("y = {}: пролёты {}, закрашено {}", y, text.join(" "), filled));
+        }
+    }
+    pixels
+}
+
+// Концы толстой линии
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+impl std::fmt::Display for LineCap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineCap::Butt => write!(f, "Плоский"),
+            LineCap::Square => write!(f, "Квадратный"),
+            LineCap::Round => write!(f, "Круглый"),
+        }
+    }
+}
+
+// Соединения звеньев толстой ломаной
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineJoin {
+    Miter,
+    Bevel,
+    Round,
+}
+
+impl std::fmt::Display for LineJoin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineJoin::Miter => write!(f, "Острое"),
+            LineJoin::Bevel => write!(f, "Срезанное"),
+            LineJoin::Round => write!(f, "Скруглённое"),
+        }
+    }
+}
+
+// Предел длины острого соединения в полутолщинах: при более остром угле соединение срезается
+pub const MITER_LIMIT: f32 = 4.0;
+
+// Пиксели, центры которых лежат внутри выпуклого многоугольника: пролёты между пересечениями строк с рёбрами
+fn fill_convex(polygon: &[Pos2]) -> Vec<(i32, i32)> {
+    let min_y = polygon.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_y = polygon.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+    let mut pixels = Vec::new();
+    for y in (min_y.ceil() as i32)..=(max_y.floor() as i32) {
+        for span in scanline_intersections(polygon, y as f32).chunks_exact(2) {
+            pixels.extend(((span[0].ceil() as i32)..=(span[1].floor() as i32)).map(|x| (x, y)));
+        }
+    }
+    pixels
+}
+
+// Круг: каждая строка заполняется между симметричными точками окружности Брезенхема
+fn fill_disc(center: Pos2, radius: f32) -> Vec<(i32, i32)> {
+    let mut rows = std::collections::BTreeMap::new();
+    for p in bresenham_circle(center, radius, ALL_OCTANTS, &mut NoOpLogger) {
+        let span = rows.entry(p.y).or_insert((p.x, p.x));
+        span.0 = span.0.min(p.x);
+        span.1 = span.1.max(p.x);
+    }
+    rows.into_iter().flat_map(|(y, (from, to))| (from..=to).map(move |x| (x, y))).collect()
+}
+
+/// Толстая ломаная толщиной width. Каждое звено - прямоугольник, смещённый на полтолщины в обе
+/// стороны от оси и залитый построчно. Концы: плоский (без добавки), квадратный (продление
+/// на полтолщины) и круглый (круг из октантов окружности Брезенхема). На изломе с внешней стороны
+/// остаётся клиновидная выемка; её закрывает соединение: острое (продолжение внешних кромок до
+/// пересечения, при длине больше MITER_LIMIT полутолщин - срезанное), срезанное (треугольник между
+/// кромками) или скруглённое (круг в вершине). tag - 0 для звеньев, 1 для концов, 2 для соединений
+pub fn thick_polyline<L: Logger>(points: &[Pos2], width: f32, cap: LineCap, join: LineJoin, logger: &mut L) -> Vec<PixelRecord> {
+    let mut points = points.to_vec();
+    points.dedup();
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    let h = width.abs() / 2.0;
+    let direction = |i: usize| (points[i + 1] - points[i]).normalized();
+    let normal = |d: Vec2| Vec2::new(-d.y, d.x) * h;
+    let mut parts: Vec<(u8, Vec<(i32, i32)>)> = Vec::new();
+
+    for (i, w) in points.windows(2).enumerate() {
+        logger.iteration();
+        let (a, b) = (w[0], w[1]);
+        let n = normal(direction(i));
+        let quad = [a + n, b + n, b - n, a - n];
+        let body = fill_convex(&quad);
+        if logger.can_write() {
+            logger.log(format!("Звено {}: ({:.1}, {:.1}) - ({:.1}, {:.1}), пикселей {}", i, a.x, a.y, b.x, b.y, body.len()));
+        }
+        parts.push((0, body));
+    }
+
+    let last = points.len() - 1;
+    for (end, p, outward) in [("начало", points[0], -direction(0)), ("конец", points[last], direction(last - 1))] {
+        let pixels = match cap {
+            LineCap::Butt => continue,
+            LineCap::Square => {
+                let (n, ext) = (normal(outward), outward * h);
+                fill_convex(&[p + n, p + n + ext, p - n + ext, p - n])
+            }
+            LineCap::Round => fill_disc(p, h),
+        };
+        if logger.can_write() {
+            logger.log(format!("Конец ({}): {}, пикселей {}", end, cap, pixels.len()));
+        }
+        parts.push((1, pixels));
+    }
+
+    for (i, &p) in points.iter().enumerate().take(last).skip(1) {
+        logger.iteration();
+        let (d0, d1) = (direction(i - 1), direction(i));
+        let cross = d0.x * d1.y - d0.y * d1.x;
+        if cross.abs() < 1e-6 && d0.dot(d1) > 0.0 {
+            continue;
+        }
+        // Внешняя сторона излома противоположна направлению поворота
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let (o0, o1) = (normal(d0) * side, normal(d1) * side);
+        let bevel = [p, p + o0, p + o1];
+        let (kind, pixels) = match join {
+            LineJoin::Round => (join.to_string(), fill_disc(p, h)),
+            LineJoin::Bevel => (join.to_string(), fill_convex(&bevel)),
+            LineJoin::Miter => {
+                let m = (o0 + o1).normalized();
+                let cosine = m.dot(o0) / h;
+                if cosine > 1.0 / MITER_LIMIT {
+                    let tip = p + m * (h / cosine);
+                    (format!("{}, длина {:.2} полутолщины", join, 1.0 / cosine), fill_convex(&[p, p + o0, tip, p + o1]))
+                } else {
+                    (format!("срезанное вместо острого (предел {})", MITER_LIMIT), fill_convex(&bevel))
+                }
+            }
+        };
+        if logger.can_write() {
+            logger.log(format!("Соединение в P{} ({:.1}, {:.1}): {}, пикселей {}", i, p.x, p.y, kind, pixels.len()));
+        }
+        parts.push((2, pixels));
+    }
+
+    // Звенья выводятся первыми, поэтому у концов и соединений остаются только добавленные ими пиксели
+    let mut seen = std::collections::HashSet::new();
+    let mut pixels = Vec::new();
+    for (tag, cells) in parts {
+        for (x, y) in cells {
+            if seen.insert((x, y)) {
+                let step_index = pixels.len();
+                pixels.push(PixelRecord { x, y, intensity: 1.0, step_index, tag, value: 0.0 });
+            }
+        }
+    }
+    pixels
+}
+
+// Наибольшее x >= 0 с x^2 <= n (целочисленный корень); для n < 0 - None
+fn isqrt(n: i64) -> Option<i64> {
+    if n < 0 {
+        return None;
+    }
+    let mut x = (n as f64).sqrt() as i64;
+    while x * x > n {
+        x -= 1;
+    }
+    while (x + 1) * (x + 1) <= n {
+        x += 1;
+    }
+    Some(x)
+}
+
+/// Кольцо (толстая окружность): закрашиваются все пиксели, центры которых лежат между
+/// окружностями r_inner^2 <= x^2 + y^2 <= r_outer^2. Каждая строка - один или два горизонтальных
+/// пролёта, границы которых находятся целочисленным корнем, поэтому дыр не бывает
+pub fn annulus<L: Logger>(center: Pos2, inner: f32, outer: f32, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    let cx = center.x.round() as i32;
+    let cy = center.y.round() as i32;
+    let (inner, outer) = (inner.abs().min(outer.abs()), inner.abs().max(outer.abs()));
+    let (inner2, outer2) = ((inner * inner).ceil() as i64, (outer * outer).floor() as i64);
+    let rows = outer.floor() as i32;
+    for y in -rows..=rows {
+        logger.iteration();
+        logger.progress((y + rows) as f32 / (2 * rows).max(1) as f32);
+        let y2 = (y as i64) * (y as i64);
+        let Some(x_out) = isqrt(outer2 - y2) else { continue };
+        // Внутренняя граница: первый x, для которого x^2 + y^2 >= r_inner^2
+        let x_in = match isqrt(inner2 - y2 - 1) {
+            Some(x) => x + 1,
+            None => 0,
+        };
+        if x_in > x_out {
+            continue;
+        }
+        let (x_in, x_out) = (x_in as i32, x_out as i32);
+        let spans = if x_in == 0 { vec![(-x_out, x_out)] } else { vec![(-x_out, -x_in), (x_in, x_out)] };
+        if logger.can_write() {
+            let text: Vec<String> = spans.iter().map(|(a, b)| format!("[{}, {}]", cx + a, cx + b)).collect();
+            logger.log(format!("y = {}: пролёты {}", cy + y, text.join(" ")));
+        }
+        for (from, to) in spans {
+            pixels.extend((from..=to).map(|x| Pos2::new((cx + x) as f32, (cy + y) as f32)));
+        }
+    }
+    pixels
+}
+
+/// Кольцо "наивным" способом: концентрические окружности Брезенхема для каждого целого радиуса.
+/// Соседние окружности местами расходятся по диагонали, и между ними остаются дыры (муар)
+pub fn concentric_circles<L: Logger>(center: Pos2, inner: f32, outer: f32, logger: &mut L) -> Vec<Pos2> {
+    let (inner, outer) = (inner.abs().min(outer.abs()).round() as i32, inner.abs().max(outer.abs()).round() as i32);
+    let mut pixels = Vec::new();
+    for r in inner..=outer {
+        logger.iteration();
+        logger.progress((r - inner) as f32 / (outer - inner).max(1) as f32);
+        let circle = bresenham_circle(center, r as f32, ALL_OCTANTS, &mut NoOpLogger);
+        if logger.can_write() {
+            logger.log(format!("Окружность R = {}: {} пикс.", r, circle.len()));
+        }
+        pixels.extend(circle.iter().map(PixelRecord::pos));
+    }
+    pixels
+}
+
+/// Парабола y - k = a(x - h)^2 методом средней точки на промежутке |x - h| <= extent.
+/// Строится правая ветвь от вершины, левая получается отражением. Область 1 (наклон < 1) - шаг по x,
+/// область 2 - шаг по y; d - значение F(x, y) = a x^2 - y в средней точке между кандидатами.
+/// tag - номер области, value - d на момент вывода пикселя
+pub fn midpoint_parabola<L: Logger>(vertex: Pos2, a: f32, extent: f32, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let h = vertex.x.round() as i32;
+    let k = vertex.y.round() as i32;
+    let sign = if a < 0.0 { -1 } else { 1 };
+    let a = a.abs();
+    let x_end = extent.abs().round() as i32;
+
+    let emit = |pixels: &mut Vec<PixelRecord>, x: i32, y: i32, region: u8, d: f32, logger: &mut L| {
+        logger.iteration();
+        logger.progress(x as f32 / x_end.max(1) as f32);
+        if logger.can_write() {
+            logger.log_fields(h + x, k + sign * y, &[
+                ("область", region.to_string()),
+                ("x", x.to_string()),
+                ("y", y.to_string()),
+                ("d", format!("{:.3}", d)),
+            ]);
+        }
+        let step_index = pixels.len();
+        for px in if x == 0 { vec![h] } else { vec![h + x, h - x] } {
+            pixels.push(PixelRecord { x: px, y: k + sign * y, intensity: 1.0, step_index, tag: region, value: d });
+        }
+    };
+
+    // Область 1: средняя точка (x + 1, y + 0.5)
+    let (mut x, mut y) = (0, 0);
+    let mut d = a - 0.5;
+    while x <= x_end && 2.0 * a * (x as f32 + 1.0) <= 1.0 {
+        emit(&mut pixels, x, y, 1, d, logger);
+        if d > 0.0 {
+            y += 1;
+            d -= 1.0;
+        }
+        d += a * (2 * x + 3) as f32;
+        x += 1;
+    }
+
+    // Область 2: средняя точка (x + 0.5, y + 1)
+    d = a * (x as f32 + 0.5).powi(2) - (y + 1) as f32;
+    while x <= x_end {
+        emit(&mut pixels, x, y, 2, d, logger);
+        if d < 0.0 {
+            d += a * (2 * x + 2) as f32;
+            x += 1;
+        }
+        d -= 1.0;
+        y += 1;
+    }
+    pixels
+}
+
+// Ограничение полуосей и смещений от центра гиперболы: при нём d (порядка 4 b^2 x^2) помещается в i128
+const MAX_HYPERBOLA_EXTENT: i128 = 1 << 24;
+
+/// Гипербола x^2/a^2 - y^2/b^2 = 1 методом средней точки в пределах прямоугольника bounds.
+/// Строится четверть правой ветви от вершины (a, 0), остальное - отражениями (левая ветвь - по запросу).
+/// Область 1 (наклон > 1) - шаг по y, область 2 - шаг по x. d = 4 * F в средней точке,
+/// F(x, y) = b^2 x^2 - a^2 y^2 - a^2 b^2; масштаб 4 убирает половинки, и вычисления остаются целыми.
+/// Вычисления ведутся в i128: d порядка b^2 x^2 переполняет i64 уже при a, b ~ 10^4 и x ~ 10^5.
+/// tag - номер области, value - d на момент вывода пикселя
+pub fn midpoint_hyperbola<L: Logger>(center: Pos2, a: f32, b: f32, both_branches: bool, bounds: Rect, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let h = center.x.round() as i32;
+    let k = center.y.round() as i32;
+    let a = (a.abs().round() as i128).clamp(1, MAX_HYPERBOLA_EXTENT);
+    let b = (b.abs().round() as i128).clamp(1, MAX_HYPERBOLA_EXTENT);
+    let (a2, b2) = (a * a, b * b);
+    // Дальше этих смещений от центра кривая не видна
+    let x_max = ((bounds.min.x - h as f32).abs().max((bounds.max.x - h as f32).abs()).ceil() as i128).min(MAX_HYPERBOLA_EXTENT);
+    let y_max = ((bounds.min.y - k as f32).abs().max((bounds.max.y - k as f32).abs()).ceil() as i128).min(MAX_HYPERBOLA_EXTENT);
+
+    let emit = |pixels: &mut Vec<PixelRecord>, x: i128, y: i128, region: u8, d: i128, logger: &mut L| {
+        logger.iteration();
+        logger.progress((y as f32 / y_max.max(1) as f32).max(x as f32 / x_max.max(1) as f32));
+        let (x, y) = (x as i32, y as i32);
+        if logger.can_write() {
+            logger.log_fields(h + x, k + y, &[
+                ("область", region.to_string()),
+                ("x", x.to_string()),
+                ("y", y.to_string()),
+                ("d", d.to_string()),
+            ]);
+        }
+        let step_index = pixels.len();
+        let ys = if y == 0 { vec![y] } else { vec![y, -y] };
+        let xs = if both_branches { vec![x, -x] } else { vec![x] };
+        for &sx in &xs {
+            for &sy in &ys {
+                pixels.push(PixelRecord { x: h + sx, y: k + sy, intensity: 1.0, step_index, tag: region, value: d as f32 });
+            }
+        }
+    };
+
+    // Область 1: средняя точка (x + 0.5, y + 1)
+    let (mut x, mut y) = (a, 0);
+    let mut d = b2 * (2 * a + 1).pow(2) - 4 * a2 - 4 * a2 * b2;
+    while x <= x_max && y <= y_max && b2 * x > a2 * y {
+        emit(&mut pixels, x, y, 1, d, logger);
+        if d < 0 {
+            d += 8 * b2 * (x + 1);
+            x += 1;
+        }
+        d -= 4 * a2 * (2 * y + 3);
+        y += 1;
+    }
+
+    // Область 2: средняя точка (x + 1, y + 0.5)
+    d = 4 * b2 * (x + 1).pow(2) - a2 * (2 * y + 1).pow(2) - 4 * a2 * b2;
+    while x <= x_max && y <= y_max {
+        emit(&mut pixels, x, y, 2, d, logger);
+        if d > 0 {
+            d -= 8 * a2 * (y + 1);
+            y += 1;
+        }
+        d += 4 * b2 * (2 * x + 3);
+        x += 1;
+    }
+    pixels
+}
+
+/// График явной функции y = f(x) по столбцам в пределах прямоугольника bounds.
+/// Для каждого целого x закрашивается вертикальный пролёт от середины между f(x - 1) и f(x)
+/// до середины между f(x) и f(x + 1), поэтому крутые участки не рвутся на отдельные точки.
+/// Столбец не тянется к соседу, если значение не определено или скачок больше высоты области
+/// (разрыв, как у tan в асимптоте). value - значение f(x) в столбце
+pub fn function_plot<L: Logger>(f: impl Fn(f32) -> f32, bounds: Rect, logger: &mut L) -> Vec<PixelRecord> {
+    let mut pixels = Vec::new();
+    let (x_from, x_to) = (bounds.min.x.ceil() as i32, bounds.max.x.floor() as i32);
+    if x_from > x_to {
+        return pixels;
+    }
+    let max_jump = bounds.height();
+    // Значения с соседями за краями области
+    let values: Vec<f32> = ((x_from - 1)..=(x_to + 1)).map(|x| f(x as f32)).collect();
+    let connected = |a: f32, b: f32| a.is_finite() && b.is_finite() && (a - b).abs() <= max_jump;
+    for (i, x) in (x_from..=x_to).enumerate() {
+        logger.iteration();
+        logger.progress(i as f32 / (x_to - x_from).max(1) as f32);
+        let y = values[i + 1];
+        if !y.is_finite() {
+            if logger.can_write() {
+                logger.log(format!("x = {}: функция не определена", x));
+            }
+            continue;
+        }
+        let (mut lo, mut hi) = (y, y);
+        for neighbour in [values[i], values[i + 2]] {
+            if connected(y, neighbour) {
+                lo = lo.min((y + neighbour) / 2.0);
+                hi = hi.max((y + neighbour) / 2.0);
+            }
+        }
+        if x < x_to && !connected(y, values[i + 2]) && logger.can_write() {
+            logger.log(format!("Разрыв между x = {} и x = {}: {:.3} -> {:.3}", x, x + 1, y, values[i + 2]));
+        }
+        // Пролёт обрезается видимой областью
+        let (from, to) = (lo.max(bounds.min.y).round() as i32, hi.min(bounds.max.y).round() as i32);
+        if from > to {
+            continue;
+        }
+        if logger.can_write() {
+            logger.log_pixel(x, y.round() as i32, format!("x = {}: f(x) = {:.3}, пролёт y = {}..{}", x, y, from, to));
+        }
+        for py in from..=to {
+            let step_index = pixels.len();
+            pixels.push(PixelRecord { x, y: py, intensity: 1.0, step_index, tag: 0, value: y });
+        }
+    }
+    pixels
+}
+
+// Границы числа шагов кривой в адаптивном режиме
+pub const MIN_CURVE_STEPS: usize = 16;
+pub const MAX_CURVE_STEPS: usize = 20_000;
+
+/// Число шагов кривой по её длине на экране: периметр опорной ломаной (оценка сверху
+/// для длины кривой Безье), умноженный на масштаб, - примерно один шаг на экранную точку
+pub fn adaptive_curve_steps(points: &[Pos2], zoom: f32) -> usize {
+    let perimeter: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    ((perimeter * zoom).ceil() as usize).clamp(MIN_CURVE_STEPS, MAX_CURVE_STEPS)
+}
+
+/// Ломаная через заданные точки: соседние точки соединяются отрезками Брезенхема.
+/// Отрезки с нечисловыми концами (разрывы функции) пропускаются
+pub fn polyline<L: Logger>(points: &[Pos2], logger: &mut L) -> Vec<Pos2> {
+    let mut pixels: Vec<Pos2> = Vec::new();
+    for (i, w) in points.windows(2).enumerate() {
+        logger.progress(i as f32 / (points.len() - 1) as f32);
+        if !(w[0].x.is_finite() && w[0].y.is_finite() && w[1].x.is_finite() && w[1].y.is_finite()) {
+            if logger.can_write() {
+                logger.log(format!("Разрыв между точками {} и {}", i, i + 1));
+            }
+            continue;
+        }
+        for p in bresenham_line(w[0], w[1], &mut NoOpLogger) {
+            // Общий конец соседних отрезков не дублируем
+            if pixels.last() != Some(&p) {
+                pixels.push(p);
+            }
+        }
+    }
+    pixels
+}
+
+/// Алгоритм Кастла-Питвея (Де Кастельжо)
+pub fn castle_pitway<L: Logger>(points: &[Pos2], steps: usize, rounding: RoundingMode, logger: &mut L) -> Vec<Pos2> {
+    let mut curve_pixels = Vec::new();
+    if points.len() < 2 { return curve_pixels; }
+
+    let steps = steps.max(1);
+    let log_every = (steps / 10).max(1);
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let mut temp_points = points.to_vec();
+        logger.iteration();
+        logger.progress(t);
+
+        if logger.can_write() && i % log_every == 0 { // Логируем каждый десятый участок
+            logger.log(format!("t = {:.2}", t));
+        }
+
+        while temp_points.len() > 1 {
+            temp_points = temp_points
+                .windows(2)
+                .map(|p| p[0].lerp(p[1], t))
+                .collect();
+        }
+        if let Some(p) = temp_points.first() {
+            let pixel = Pos2::new(round_with(rounding, p.x), round_with(rounding, p.y));
+            if !curve_pixels.contains(&pixel) {
+                curve_pixels.push(pixel);
+            }
+        }
+    }
+    curve_pixels
+}
+
+/// Рациональная кривая Безье схемой де Кастельжо в однородных координатах: точка P с весом w
+/// переходит в (w x, w y, w), интерполяция идёт в однородных координатах, результат делится на вес.
+/// При всех весах 1 совпадает с обычной кривой Безье; квадратичная кривая на точках (r, 0), (r, r), (0, r)
+/// с весами 1, √2/2, 1 - точная четверть окружности. Недостающие веса считаются равными 1.
+/// Точки кривой соединяются отрезками Брезенхема
+pub fn rational_bezier<L: Logger>(points: &[Pos2], weights: &[f32], steps: usize, logger: &mut L) -> Vec<Pos2> {
+    if points.len() < 2 { return Vec::new(); }
+    let homogeneous: Vec<(Vec2, f32)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let w = weights.get(i).copied().unwrap_or(1.0);
+            (p.to_vec2() * w, w)
+        })
+        .collect();
+
+    let steps = steps.max(1);
+    let log_every = (steps / 10).max(1);
+    let mut vertices = Vec::with_capacity(steps + 1);
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        logger.iteration();
+        let mut level = homogeneous.clone();
+        while level.len() > 1 {
+            level = level
+                .windows(2)
+                .map(|p| (p[0].0 + (p[1].0 - p[0].0) * t, p[0].1 + (p[1].1 - p[0].1) * t))
+                .collect();
+        }
+        let (v, w) = level[0];
+        let p = (v / w).to_pos2();
+        if logger.can_write() && i % log_every == 0 { // Логируем каждый десятый участок
+            logger.log_fields(p.x.round() as i32, p.y.round() as i32, &[
+                ("t", format!("{:.2}", t)),
+                ("w", format!("{:.3}", w)),
+                ("точка", format!("({:.2}, {:.2})", p.x, p.y)),
+            ]);
+        }
+        vertices.push(p);
+    }
+    polyline(&vertices, logger)
+}
+
+// Предел глубины рекурсии адаптивного разбиения: не больше 2^16 участков ломаной
+pub const MAX_SUBDIVISION_DEPTH: u32 = 16;
+
+// Отклонение опорной ломаной от хорды: наибольшее расстояние внутренних точек до отрезка [P0, Pn]
+fn control_polygon_flatness(points: &[Pos2]) -> f32 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    points[1..points.len() - 1]
+        .iter()
+        .map(|&p| distance_to_segment(p, first, last))
+        .fold(0.0, f32::max)
+}
+
+// Деление кривой Безье пополам (t = 0.5) схемой де Кастельжо: опорные точки левой и правой половин
+fn split_bezier(points: &[Pos2]) -> (Vec<Pos2>, Vec<Pos2>) {
+    let mut left = vec![points[0]];
+    let mut right = vec![points[points.len() - 1]];
+    let mut level = points.to_vec();
+    while level.len() > 1 {
+        level = level.windows(2).map(|p| p[0].lerp(p[1], 0.5)).collect();
+        left.push(level[0]);
+        right.push(level[level.len() - 1]);
+    }
+    right.reverse();
+    (left, right)
+}
+
+fn subdivide_bezier<L: Logger>(points: &[Pos2], tolerance: f32, depth: u32, vertices: &mut Vec<Pos2>, logger: &mut L) {
+    let flatness = control_polygon_flatness(points);
+    if flatness <= tolerance || depth >= MAX_SUBDIVISION_DEPTH {
+        let end = points[points.len() - 1];
+        logger.iteration();
+        if logger.can_write() {
+            logger.log_fields(end.x.round() as i32, end.y.round() as i32, &[
+                ("участок", vertices.len().to_string()),
+                ("глубина", depth.to_string()),
+                ("отклонение", format!("{:.3}", flatness)),
+                ("конец", format!("({:.2}, {:.2})", end.x, end.y)),
+            ]);
+        }
+        vertices.push(end);
+        return;
+    }
+    let (left, right) = split_bezier(points);
+    subdivide_bezier(&left, tolerance, depth + 1, vertices, logger);
+    subdivide_bezier(&right, tolerance, depth + 1, vertices, logger);
+}
+
+/// Кривая Безье адаптивным разбиением: опорная ломаная делится пополам, пока её отклонение от хорды
+/// больше tolerance пикселей, затем вершины получившихся плоских участков соединяются отрезками Брезенхема.
+/// Прямые участки кривой обходятся малым числом отрезков, изогнутые - большим
+pub fn bezier_subdivision<L: Logger>(points: &[Pos2], tolerance: f32, logger: &mut L) -> Vec<Pos2> {
+    if points.len() < 2 { return Vec::new(); }
+    let mut vertices = vec![points[0]];
+    subdivide_bezier(points, tolerance.max(0.01), 0, &mut vertices, logger);
+    if logger.can_write() {
+        logger.log(format!("Участков ломаной: {}", vertices.len() - 1));
+    }
+    polyline(&vertices, logger)
+}
+
+// Базисные функции равномерного кубического B-сплайна в точке t ∈ [0, 1]
+fn bspline_basis(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        (1.0 - t).powi(3) / 6.0,
+        (3.0 * t3 - 6.0 * t2 + 4.0) / 6.0,
+        (-3.0 * t3 + 3.0 * t2 + 3.0 * t + 1.0) / 6.0,
+        t3 / 6.0,
+    ]
+}
+
+/// Равномерный кубический B-сплайн: каждые четыре соседние опорные точки задают участок кривой,
+/// steps шагов по t делятся между участками поровну. Кривая не проходит через опорные точки,
+/// но на стыках участков непрерывна вместе с первой и второй производными.
+/// Замкнутый (периодический) сплайн берёт индексы по модулю n: участков столько же, сколько точек,
+/// и последний участок гладко переходит в первый.
+/// Вычисленные точки соединяются отрезками Брезенхема
+pub fn bspline<L: Logger>(points: &[Pos2], steps: usize, closed: bool, logger: &mut L) -> Vec<Pos2> {
+    let n = points.len();
+    if n < if closed { 3 } else { 4 } { return Vec::new(); }
+    let segments = if closed { n } else { n - 3 };
+    let per_segment = (steps / segments).max(1);
+    let mut vertices = Vec::with_capacity(segments * per_segment + 1);
+    for i in 0..segments {
+        let p = [points[i], points[(i + 1) % n], points[(i + 2) % n], points[(i + 3) % n]];
+        if logger.can_write() {
+            logger.log(format!("Участок {}: P{}..P{}, шагов {}", i, i, (i + 3) % n, per_segment));
+        }
+        // Начало участка совпадает с концом предыдущего
+        let first = if i == 0 { 0 } else { 1 };
+        for j in first..=per_segment {
+            logger.iteration();
+            let b = bspline_basis(j as f32 / per_segment as f32);
+            let v = p[0].to_vec2() * b[0] + p[1].to_vec2() * b[1] + p[2].to_vec2() * b[2] + p[3].to_vec2() * b[3];
+            vertices.push(v.to_pos2());
+        }
+    }
+    polyline(&vertices, logger)
+}
+
+// Базисные функции Эрмита в точке t ∈ [0, 1]: веса p0, m0, p1, m1
+fn hermite_basis(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [2.0 * t3 - 3.0 * t2 + 1.0, t3 - 2.0 * t2 + t, -2.0 * t3 + 3.0 * t2, t3 - t2]
+}
+
+/// Кубический полином Эрмита: концы p0, p1 и касательные m0, m1 в точке t ∈ [0, 1]
+pub fn hermite_point(p0: Pos2, m0: Vec2, p1: Pos2, m1: Vec2, t: f32) -> Pos2 {
+    let h = hermite_basis(t);
+    (p0.to_vec2() * h[0] + m0 * h[1] + p1.to_vec2() * h[2] + m1 * h[3]).to_pos2()
+}
+
+/// Кривая Эрмита по концам p0, p1 и касательным m0, m1: steps равных шагов по t,
+/// точки соединяются отрезками Брезенхема
+pub fn hermite_curve<L: Logger>(p0: Pos2, m0: Vec2, p1: Pos2, m1: Vec2, steps: usize, logger: &mut L) -> Vec<Pos2> {
+    let steps = steps.max(1);
+    let log_every = (steps / 10).max(1);
+    let mut vertices = Vec::with_capacity(steps + 1);
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        logger.iteration();
+        let p = hermite_point(p0, m0, p1, m1, t);
+        if logger.can_write() && i % log_every == 0 { // Логируем каждый десятый участок
+            let h = hermite_basis(t);
+            logger.log_fields(p.x.round() as i32, p.y.round() as i32, &[
+                ("t", format!("{:.2}", t)),
+                ("h00", format!("{:.3}", h[0])),
+                ("h10", format!("{:.3}", h[1])),
+                ("h01", format!("{:.3}", h[2])),
+                ("h11", format!("{:.3}", h[3])),
+                ("точка", format!("({:.2}, {:.2})", p.x, p.y)),
+            ]);
+        }
+        vertices.push(p);
+    }
+    polyline(&vertices, logger)
+}
+
+/// Сплайн Катмулла-Рома через все опорные точки. Касательная в точке P[i] равна
+/// (1 - tension) * (P[i+1] - P[i-1]) / 2: при tension = 0 - классический Катмулл-Ром,
+/// при tension = 1 касательные нулевые и кривая вырождается в ломаную. Крайние точки
+/// дублируются, чтобы кривая начиналась в первой точке и заканчивалась в последней; у замкнутого
+/// сплайна индексы берутся по модулю n и добавляется участок P[n-1] -> P[0].
+/// steps шагов по t делятся между участками поровну, точки соединяются отрезками Брезенхема
+pub fn catmull_rom<L: Logger>(points: &[Pos2], tension: f32, steps: usize, closed: bool, logger: &mut L) -> Vec<Pos2> {
+    if points.len() < 2 { return Vec::new(); }
+    let n = points.len();
+    let at = |i: isize| if closed { points[i.rem_euclid(n as isize) as usize] } else { points[i.clamp(0, n as isize - 1) as usize] };
+    let tangent = |i: isize| (at(i + 1) - at(i - 1)) * (1.0 - tension) / 2.0;
+    let segments = if closed { n } else { n - 1 };
+    let per_segment = (steps / segments).max(1);
+    let mut vertices = Vec::with_capacity(segments * per_segment + 1);
+    for i in 0..segments as isize {
+        let (m0, m1) = (tangent(i), tangent(i + 1));
+        if logger.can_write() {
+            logger.log(format!(
+                "Участок {}: P{} -> P{}, касательные ({:.2}, {:.2}), ({:.2}, {:.2})",
+                i, i, (i + 1) % n as isize, m0.x, m0.y, m1.x, m1.y
+            ));
+        }
+        // Начало участка совпадает с концом предыдущего
+        let first = if i == 0 { 0 } else { 1 };
+        for j in first..=per_segment {
+            logger.iteration();
+            vertices.push(hermite_point(at(i), m0, at(i + 1), m1, j as f32 / per_segment as f32));
+        }
+    }
+    polyline(&vertices, logger)
+}
+
+/// Алгоритм сглаживания Ву
+pub fn wu_line<L: Logger>(p1: Pos2, p2: Pos2, rounding: RoundingMode, fraction: FractionMode, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let mut x0 = p1.x;
+    let mut y0 = p1.y;
+    let mut x1 = p2.x;
+    let mut y1 = p2.y;
+
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+
+    let dx = x1 - x0;
+    let dy = y1 - y0;
+    let gradient = if dx == 0.0 { 1.0 } else { dy / dx };
+
+    let mut y = y0;
+    let (from, to) = (round_with(rounding, x0) as i32, round_with(rounding, x1) as i32);
+    for x in from..=to {
+        logger.progress((x - from) as f32 / (to - from).max(1) as f32);
+        let (y_base, fractional_part) = split_fraction(y, fraction);
+
+        let intensity1 = 1.0 - fractional_part;
+        let intensity2 = fractional_part;
+
+        if steep {
+            pixels.push((y_base, x, intensity1));
+            pixels.push((y_base + 1, x, intensity2));
+        } else {
+            pixels.push((x, y_base, intensity1));
+            pixels.push((x, y_base + 1, intensity2));
+        }
+
+        if logger.can_write() {
+            logger.log_pixel(
+                if steep {y_base} else {x}, if steep {x} else {y_base},
+                format!(
+                    "x: {}, y: {:.2}, пиксели: ({}, {}, {:.2}), ({}, {}, {:.2})",
+                    x, y,
+                    if steep {y_base} else {x}, if steep {x} else {y_base}, intensity1,
+                    if steep {y_base + 1} else {x}, if steep {x} else {y_base + 1}, intensity2
+                ),
+            );
+        }
+        y += gradient;
+    }
+    pixels
+}
+
+pub fn step_by_step_aa<L: Logger>(p1: Pos2, p2: Pos2, rounding: RoundingMode, fraction: FractionMode, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let dx = p2.x - p1.x;
+    let dy = p2.y - p1.y;
+
+    if dx.abs() > dy.abs() {
+        let k = dy / dx;
+        let b = p1.y - k * p1.x;
+        let (start_x, end_x) = if p1.x < p2.x { (p1.x, p2.x) } else { (p2.x, p1.x) };
+
+        let (from, to) = (round_with(rounding, start_x) as i32, round_with(rounding, end_x) as i32);
+        for x_int in from..=to {
+            logger.progress((x_int - from) as f32 / (to - from).max(1) as f32);
+            let x = x_int as f32;
+            let y_ideal = k * x + b;
+            let (y1, fractional) = split_fraction(y_ideal, fraction);
+            let y2 = y1 + 1;
+            let intensity1 = 1.0 - fractional;
+            let intensity2 = fractional;
+
+            pixels.push((x_int, y1, intensity1));
+            pixels.push((x_int, y2, intensity2));
+
+            if logger.can_write() {
+                logger.log_pixel(x_int, y1, format!("x: {}, y_ideal: {:.2} -> ({}, {:.2}), ({}, {:.2})", x_int, y_ideal, y1, intensity1, y2, intensity2));
+            }
+        }
+    } else {
+        // Аналогичная логика для dy > dx
+        let k = dx / dy;
+        let b = p1.x - k * p1.y;
+        let (start_y, end_y) = if p1.y < p2.y { (p1.y, p2.y) } else { (p2.y, p1.y) };
+
+        let (from, to) = (round_with(rounding, start_y) as i32, round_with(rounding, end_y) as i32);
+        for y_int in from..=to {
+            logger.progress((y_int - from) as f32 / (to - from).max(1) as f32);
+            let y = y_int as f32;
+            let x_ideal = k * y + b;
+            let (x1, fractional) = split_fraction(x_ideal, fraction);
+            let x2 = x1 + 1;
+            let intensity1 = 1.0 - fractional;
+            let intensity2 = fractional;
+
+            pixels.push((x1, y_int, intensity1));
+            pixels.push((x2, y_int, intensity2));
+
+            if logger.can_write() {
+                logger.log_pixel(x1, y_int, format!("y: {}, x_ideal: {:.2} -> ({}, {:.2}), ({}, {:.2})", y_int, x_ideal, x1, intensity1, x2, intensity2));
+            }
+        }
+    }
+    pixels
+}
+
+/// Алгоритм ЦДА со сглаживанием
+pub fn dda_aa<L: Logger>(p1: Pos2, p2: Pos2, rounding: RoundingMode, fraction: FractionMode, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let dx = p2.x - p1.x;
+    let dy = p2.y - p1.y;
+
+    let steps = if dx.abs() > dy.abs() { dx.abs() } else { dy.abs() };
+    let x_inc = dx / steps;
+    let y_inc = dy / steps;
+
+    let mut x_ideal = p1.x;
+    let mut y_ideal = p1.y;
+
+    let is_steep = dy.abs() > dx.abs();
+
+    let count = steps.round() as u32;
+    for i in 0..=count {
+        logger.progress(i as f32 / count.max(1) as f32);
+        if is_steep {
+            let (x1, fractional) = split_fraction(x_ideal, fraction);
+            let x2 = x1 + 1;
+            pixels.push((x1, round_with(rounding, y_ideal) as i32, 1.0 - fractional));
+            pixels.push((x2, round_with(rounding, y_ideal) as i32, fractional));
+        } else {
+            let (y1, fractional) = split_fraction(y_ideal, fraction);
+            let y2 = y1 + 1;
+            pixels.push((round_with(rounding, x_ideal) as i32, y1, 1.0 - fractional));
+            pixels.push((round_with(rounding, x_ideal) as i32, y2, fractional));
+        }
+
+        if logger.can_write() {
+            logger.log_pixel(x_ideal.round() as i32, y_ideal.round() as i32, format!("x: {:.2}, y: {:.2}", x_ideal, y_ideal));
+        }
+
+        x_ideal += x_inc;
+        y_ideal += y_inc;
+    }
+    pixels
+}
+
+pub fn bresenham_aa<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let x1 = p1.x.round() as i32;
+    let y1 = p1.y.round() as i32;
+    let x2 = p2.x.round() as i32;
+    let y2 = p2.y.round() as i32;
 
     let dx = (x2 - x1).abs();
     let dy = (y2 - y1).abs();
 
-    // Определяем направление шага
-    let sx = if x1 < x2 { 1 } else { -1 };
-    let sy = if y1 < y2 { 1 } else { -1 };
+    // Определяем направление шага
+    let sx = if x1 < x2 { 1 } else { -1 };
+    let sy = if y1 < y2 { 1 } else { -1 };
+
+    if dx >= dy {
+        // --- Пологая линия (доминантная ось X) ---
+        let mut d = 0; // Накопитель ошибки (числитель дроби d/dx)
+        let mut y = y1;
+        let mut x = x1;
+
+        // Итерируемся ровно dx раз
+        for i in 0..=dx {
+            logger.progress(i as f32 / dx.max(1) as f32);
+            // Интенсивность = насколько далеко мы ушли от центра пикселя y
+            // d / dx  -> число от 0.0 до 1.0
+            let intensity = d as f32 / dx as f32;
+
+            // Основной пиксель (чем меньше отклонение d, тем он ярче)
+            pixels.push((x, y, 1.0 - intensity));
+            // Соседний пиксель (получает остаток интенсивности)
+            pixels.push((x, y + sy, intensity));
+
+            if logger.can_write() {
+                logger.log_pixel(x, y, format!("x: {}, y: {}, d: {}/{} -> int: {:.2}", x, y, d, dx, intensity));
+            }
+
+            d += dy;
+            // Если накопитель превысил порог (1.0 или dx в целых числах)
+            if d >= dx {
+                d -= dx; // Сбрасываем ошибку (эквивалент fract() в float)
+                y += sy; // Переходим на следующую строку
+            }
+            x += sx;
+        }
+    } else {
+        // --- Крутая линия (доминантная ось Y) ---
+        // Логика та же, только меняем роли x и y, dx и dy
+        let mut d = 0;
+        let mut x = x1;
+        let mut y = y1;
+
+        for i in 0..=dy {
+            logger.progress(i as f32 / dy.max(1) as f32);
+            let intensity = d as f32 / dy as f32;
+
+            pixels.push((x, y, 1.0 - intensity));
+            pixels.push((x + sx, y, intensity));
+
+            if logger.can_write() {
+                logger.log_pixel(x, y, format!("y: {}, x: {}, d: {}/{} -> int: {:.2}", y, x, d, dy, intensity));
+            }
+
+            d += dx;
+            if d >= dy {
+                d -= dy;
+                x += sx;
+            }
+            y += sy;
+        }
+    }
+
+    pixels
+}
+
+// Число записей таблицы фильтра Гупты-Спроулла на отрезке расстояний [0, GS_MAX_DISTANCE]
+const GS_TABLE_SIZE: usize = 24;
+const GS_MAX_DISTANCE: f32 = 1.5;
+
+// Таблица интенсивностей Гупты-Спроулла: свёртка прямой толщиной в один пиксель с конусным
+// фильтром радиуса 1 (объём конуса нормирован к 1) в зависимости от расстояния до центра прямой.
+// Интеграл считается численно по сетке внутри круга фильтра
+fn gupta_sproull_table() -> [f32; GS_TABLE_SIZE] {
+    const GRID: usize = 64;
+    let cell = 2.0 / GRID as f32;
+    let mut table = [0.0; GS_TABLE_SIZE];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let distance = i as f32 * GS_MAX_DISTANCE / (GS_TABLE_SIZE - 1) as f32;
+        let mut sum = 0.0;
+        for gy in 0..GRID {
+            for gx in 0..GRID {
+                let u = -1.0 + (gx as f32 + 0.5) * cell;
+                let v = -1.0 + (gy as f32 + 0.5) * cell;
+                let r = (u * u + v * v).sqrt();
+                if r < 1.0 && (u - distance).abs() <= 0.5 {
+                    sum += (1.0 - r) * 3.0 / std::f32::consts::PI * cell * cell;
+                }
+            }
+        }
+        *entry = sum.min(1.0);
+    }
+    table
+}
+
+/// Алгоритм Гупты-Спроулла: отрезок средней точки, в каждом столбце закрашиваются три пикселя
+/// (основной и два соседа по неосновной оси). Для каждого вычисляется перпендикулярное расстояние
+/// до идеальной прямой D = 2 v dx / (2 sqrt(dx^2 + dy^2)), где 2 v dx получается из переменной решения
+/// без умножений, а интенсивность берётся из таблицы конусного фильтра по |D|
+pub fn gupta_sproull<L: Logger>(p1: Pos2, p2: Pos2, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let (x1, y1) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
+    let table = gupta_sproull_table();
+    let filter = |distance: f32| {
+        let index = (distance.abs() / GS_MAX_DISTANCE * (GS_TABLE_SIZE - 1) as f32).round() as usize;
+        table.get(index).copied().unwrap_or(0.0)
+    };
+
+    // Работаем в координатах (основная ось, неосновная ось), чтобы один цикл покрывал все октанты
+    let steep = (y2 - y1).abs() > (x2 - x1).abs();
+    let (major_start, minor_start, major_end, minor_end) = if steep { (y1, x1, y2, x2) } else { (x1, y1, x2, y2) };
+    let to_xy = |major: i32, minor: i32| if steep { (minor, major) } else { (major, minor) };
+    let dx = (major_end - major_start).abs();
+    let dy = (minor_end - minor_start).abs();
+    let s_major = if major_end >= major_start { 1 } else { -1 };
+    let s_minor = if minor_end >= minor_start { 1 } else { -1 };
+
+    let inv_denom = 1.0 / (2.0 * ((dx * dx + dy * dy) as f32).sqrt().max(1.0));
+    let two_dx_inv_denom = 2.0 * dx as f32 * inv_denom;
+    let mut d = 2 * dy - dx;
+    let mut two_v_dx = 0;
+    let (mut major, mut minor) = (major_start, minor_start);
+
+    for step in 0..=dx {
+        logger.iteration();
+        logger.progress(step as f32 / dx.max(1) as f32);
+        let distance = two_v_dx as f32 * inv_denom;
+        // Основной пиксель и соседи по неосновной оси: в сторону шага и против него
+        let column = [
+            (minor, distance),
+            (minor + s_minor, two_dx_inv_denom - distance),
+            (minor - s_minor, two_dx_inv_denom + distance),
+        ];
+        for (m, dist) in column {
+            let (x, y) = to_xy(major, m);
+            pixels.push((x, y, filter(dist)));
+        }
+        if logger.can_write() {
+            let (x, y) = to_xy(major, minor);
+            logger.log_fields(x, y, &[
+                ("d", d.to_string()),
+                ("D", format!("{:.3}", distance)),
+                ("I", format!("{:.2}", filter(distance))),
+            ]);
+        }
+
+        if d < 0 {
+            two_v_dx = d + dx;
+            d += 2 * dy;
+        } else {
+            two_v_dx = d - dx;
+            d += 2 * (dy - dx);
+            minor += s_minor;
+        }
+        major += s_major;
+    }
+    pixels
+}
+
+// Число подстрок развёртки на одну строку пикселей при вычислении покрытия
+const COVERAGE_SUBSCANLINES: usize = 16;
+
+// Абсциссы пересечений горизонтали y = sy с рёбрами многоугольника (полуоткрытые рёбра [y_min, y_max))
+fn scanline_intersections(vertices: &[Pos2], sy: f32) -> Vec<f32> {
+    let mut xs = Vec::new();
+    for i in 0..vertices.len() {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % vertices.len()];
+        if a.y == b.y {
+            continue;
+        }
+        let (lo, hi) = if a.y < b.y { (a, b) } else { (b, a) };
+        if sy >= lo.y && sy < hi.y {
+            xs.push(lo.x + (sy - lo.y) * (hi.x - lo.x) / (hi.y - lo.y));
+        }
+    }
+    xs.sort_by(f32::total_cmp);
+    xs
+}
+
+// Добавляет пикселям строки длину их пересечения с отрезком [x0, x1] подстроки (пиксель x занимает [x - 0.5, x + 0.5])
+fn add_span_coverage(coverage: &mut std::collections::BTreeMap<i32, f32>, x0: f32, x1: f32, weight: f32) {
+    let first = (x0 + 0.5).floor() as i32;
+    let last = (x1 + 0.5).floor() as i32;
+    if first == last {
+        // Оба конца отрезка попали в один пиксель
+        *coverage.entry(first).or_insert(0.0) += (x1 - x0) * weight;
+        return;
+    }
+    *coverage.entry(first).or_insert(0.0) += (first as f32 + 0.5 - x0) * weight;
+    for x in (first + 1)..last {
+        *coverage.entry(x).or_insert(0.0) += weight;
+    }
+    *coverage.entry(last).or_insert(0.0) += (x1 - (last as f32 - 0.5)) * weight;
+}
+
+/// Заливка многоугольника без сглаживания: закрашиваются пиксели, центры которых лежат внутри
+/// (правило чёт-нечет по пересечениям строки с рёбрами). Край получается ступенчатым - для
+/// сравнения с заливкой по площади покрытия
+pub fn scanline_fill<L: Logger>(vertices: &[Pos2], pattern: &FillPattern, logger: &mut L) -> Vec<Pos2> {
+    let mut pixels = Vec::new();
+    if vertices.len() < 3 { return pixels; }
+
+    let min_y = vertices.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_y = vertices.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+    let (first_row, last_row) = (min_y.ceil() as i32, max_y.floor() as i32);
+    for row in first_row..=last_row {
+        logger.iteration();
+        logger.progress((row - first_row) as f32 / (last_row - first_row).max(1) as f32);
+        let xs = scanline_intersections(vertices, row as f32);
+        let before = pixels.len();
+        for span in xs.chunks_exact(2) {
+            let (from, to) = (span[0].ceil() as i32, span[1].floor() as i32);
+            pixels.extend((from..=to).filter(|&x| pattern.contains(x, row)).map(|x| Pos2::new(x as f32, row as f32)));
+        }
+        if logger.can_write() && pixels.len() > before {
+            let text: Vec<String> = xs.chunks_exact(2).map(|s| format!("[{:.2}, {:.2}]", s[0], s[1])).collect();
+            logger.log(format!("y = {}: пролёты {}, пикселей {}", row, text.join(" "), pixels.len() - before));
+        }
+    }
+    pixels
+}
+
+/// Заливка многоугольника со сглаживанием краёв по площади покрытия, оценённой по подстрокам развёртки:
+/// строка пикселей разбивается на COVERAGE_SUBSCANLINES подстрок, вдоль каждой доля пикселя внутри
+/// считается по длине пролёта, интенсивность - среднее по подстрокам. Оценка приближённая по вертикали,
+/// но у смежных многоугольников покрытия общего ребра в сумме не превышают 1 и при сложении не дают пересвета.
+pub fn scanline_fill_aa<L: Logger>(vertices: &[Pos2], pattern: &FillPattern, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    if vertices.len() < 3 { return pixels; }
 
-    if dx >= dy {
-        // --- Пологая линия (доминантная ось X) ---
-        let mut d = 0; // Накопитель ошибки (числитель дроби d/dx)
-        let mut y = y1;
-        let mut x = x1;
+    let min_y = vertices.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_y = vertices.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+    let weight = 1.0 / COVERAGE_SUBSCANLINES as f32;
 
-        // Итерируемся ровно dx раз
-        for _ in 0..=dx {
-            // Интенсивность = насколько далеко мы ушли от центра пикселя y
-            // d / dx  -> число от 0.0 до 1.0
-            let intensity = d as f32 / dx as f32;
+    let (first_row, last_row) = (min_y.round() as i32, max_y.round() as i32);
+    for row in first_row..=last_row {
+        logger.progress((row - first_row) as f32 / (last_row - first_row).max(1) as f32);
+        let mut coverage = std::collections::BTreeMap::new();
+        for k in 0..COVERAGE_SUBSCANLINES {
+            let sy = row as f32 - 0.5 + (k as f32 + 0.5) * weight;
+            let xs = scanline_intersections(vertices, sy);
+            for span in xs.chunks_exact(2) {
+                add_span_coverage(&mut coverage, span[0], span[1], weight);
+            }
+        }
 
-            // Основной пиксель (чем меньше отклонение d, тем он ярче)
-            pixels.push((x, y, 1.0 - intensity));
-            // Соседний пиксель (получает остаток интенсивности)
-            pixels.push((x, y + sy, intensity));
+        let mut full = 0;
+        let mut partial = 0;
+        for (x, c) in coverage {
+            if c <= 0.0 || !pattern.contains(x, row) {
+                continue;
+            }
+            if c >= 1.0 { full += 1; } else { partial += 1; }
+            pixels.push((x, row, c.min(1.0)));
+        }
+        if logger.can_write() && full + partial > 0 {
+            logger.log(format!("y = {}: внутренних пикселей {}, граничных {}", row, full, partial));
+        }
+    }
+    pixels
+}
 
-            if logger.can_write() {
-                logger.log(format!("x: {}, y: {}, d: {}/{} -> int: {:.2}", x, y, d, dx, intensity));
+/// Заливка треугольника через функции рёбер (полуплоскости).
+/// E(x, y) = a x + b y + c для каждого ребра положительна внутри треугольника; при шаге по x
+/// функция увеличивается на a, поэтому в строке её значения получаются одним сложением.
+/// E / |ребро| - расстояние от центра пикселя до ребра, покрытие оценивается как произведение
+/// clamp(d + 0.5, 0, 1) по трём рёбрам: точно для пикселя у одного ребра, приближённо в острых углах
+pub fn edge_function_triangle<L: Logger>(vertices: [Pos2; 3], pattern: &FillPattern, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let [v0, v1, v2] = vertices;
+    let area = (v1 - v0).x * (v2 - v0).y - (v1 - v0).y * (v2 - v0).x;
+    if area == 0.0 {
+        if logger.can_write() {
+            logger.log("Вырожденный треугольник: вершины лежат на одной прямой".to_string());
+        }
+        return pixels;
+    }
+    // Обход против часовой стрелки, чтобы внутренность была слева от каждого ребра
+    let sign = area.signum();
+    let edges: Vec<(f32, f32, f32)> = [(v0, v1), (v1, v2), (v2, v0)]
+        .iter()
+        .map(|&(p, q)| {
+            let (a, b, c) = (p.y - q.y, q.x - p.x, p.x * q.y - q.x * p.y);
+            let len = p.distance(q);
+            (sign * a / len, sign * b / len, sign * c / len)
+        })
+        .collect();
+
+    let min_x = v0.x.min(v1.x).min(v2.x).round() as i32 - 1;
+    let max_x = v0.x.max(v1.x).max(v2.x).round() as i32 + 1;
+    let min_y = v0.y.min(v1.y).min(v2.y).round() as i32 - 1;
+    let max_y = v0.y.max(v1.y).max(v2.y).round() as i32 + 1;
+    for y in min_y..=max_y {
+        logger.progress((y - min_y) as f32 / (max_y - min_y).max(1) as f32);
+        // Значения функций рёбер в первом пикселе строки
+        let mut d: Vec<f32> = edges.iter().map(|&(a, b, c)| a * min_x as f32 + b * y as f32 + c).collect();
+        let mut full = 0;
+        let mut partial = 0;
+        for x in min_x..=max_x {
+            let coverage: f32 = d.iter().map(|di| (di + 0.5).clamp(0.0, 1.0)).product();
+            if coverage > 0.0 && pattern.contains(x, y) {
+                if coverage >= 1.0 { full += 1; } else { partial += 1; }
+                pixels.push((x, y, coverage));
+            }
+            for (di, &(a, _, _)) in d.iter_mut().zip(&edges) {
+                *di += a;
             }
+        }
+        if logger.can_write() && full + partial > 0 {
+            logger.log(format!("y = {}: внутренних пикселей {}, граничных {}", y, full, partial));
+        }
+    }
+    pixels
+}
 
-            d += dy;
-            // Если накопитель превысил порог (1.0 или dx в целых числах)
-            if d >= dx {
-                d -= dx; // Сбрасываем ошибку (эквивалент fract() в float)
-                y += sy; // Переходим на следующую строку
+// Связность заливки: соседи по сторонам (4) или ещё и по диагоналям (8)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbours(self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+            Connectivity::Eight => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, 1), (1, -1), (-1, -1)],
+        }
+    }
+}
+
+impl std::fmt::Display for Connectivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Connectivity::Four => write!(f, "4-связная"),
+            Connectivity::Eight => write!(f, "8-связная"),
+        }
+    }
+}
+
+// Виртуальный растр для заливки: множество пикселей границы и прямоугольник, за который заливка
+// не выходит (габариты границы с запасом в один пиксель), чтобы заливка снаружи тоже завершалась
+struct BoundaryRaster {
+    boundary: std::collections::HashSet<(i32, i32)>,
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+impl BoundaryRaster {
+    fn new(boundary: &[Pos2]) -> Option<Self> {
+        let boundary: std::collections::HashSet<(i32, i32)> =
+            boundary.iter().map(|p| (p.x.round() as i32, p.y.round() as i32)).collect();
+        let min_x = boundary.iter().map(|p| p.0).min()?;
+        let max_x = boundary.iter().map(|p| p.0).max()?;
+        let min_y = boundary.iter().map(|p| p.1).min()?;
+        let max_y = boundary.iter().map(|p| p.1).max()?;
+        Some(Self { boundary, min: (min_x - 1, min_y - 1), max: (max_x + 1, max_y + 1) })
+    }
+
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.min.0 && x <= self.max.0 && y >= self.min.1 && y <= self.max.1
+    }
+
+    fn area(&self) -> usize {
+        ((self.max.0 - self.min.0 + 1) * (self.max.1 - self.min.1 + 1)) as usize
+    }
+}
+
+/// Затравочная заливка (flood fill) области, ограниченной растеризованной границей.
+/// Обход в ширину: на каждой волне закрашиваются все ещё не закрашенные соседи текущего фронта.
+/// При 8-связности заливка просачивается через диагональные стыки 8-связной границы (например,
+/// окружности Брезенхема) и заполняет всё вокруг неё. Возвращает закрашенные пиксели без границы;
+/// обход идёт по всей области, но выводятся только пиксели, включённые в узоре
+pub fn flood_fill<L: Logger>(boundary: &[Pos2], seed: Pos2, connectivity: Connectivity, pattern: &FillPattern, logger: &mut L) -> Vec<Pos2> {
+    let mut filled = Vec::new();
+    let Some(raster) = BoundaryRaster::new(boundary) else { return filled };
+    let seed = (seed.x.round() as i32, seed.y.round() as i32);
+    if !raster.contains(seed) || raster.boundary.contains(&seed) {
+        if logger.can_write() {
+            logger.log(format!("Затравка ({}, {}) лежит на границе или вне растра - заливать нечего", seed.0, seed.1));
+        }
+        return filled;
+    }
+
+    let mut visited = std::collections::HashSet::from([seed]);
+    let mut frontier = vec![seed];
+    let mut wave = 0;
+    while !frontier.is_empty() {
+        logger.iteration();
+        logger.progress(visited.len() as f32 / raster.area() as f32);
+        if logger.can_write() {
+            logger.log(format!("Волна {}: фронт {} пикс., пройдено всего {}", wave, frontier.len(), visited.len()));
+        }
+        let mut next = Vec::new();
+        for &(x, y) in &frontier {
+            if pattern.contains(x, y) {
+                filled.push(Pos2::new(x as f32, y as f32));
             }
-            x += sx;
+            for &(dx, dy) in connectivity.neighbours() {
+                let p = (x + dx, y + dy);
+                if raster.contains(p) && !raster.boundary.contains(&p) && visited.insert(p) {
+                    next.push(p);
+                }
+            }
+        }
+        frontier = next;
+        wave += 1;
+    }
+    filled
+}
+
+/// Заливка до границы (boundary fill) на том же виртуальном растре, что и flood_fill.
+/// Классическая рекурсивная схема развёрнута в цикл с явным стеком: пиксель снимается со стека,
+/// и если это не граница и он ещё не закрашен, он закрашивается, а все его соседи кладутся на стек.
+/// Обход идёт в глубину, поэтому порядок закраски сильно отличается от волн flood_fill
+pub fn boundary_fill<L: Logger>(boundary: &[Pos2], seed: Pos2, connectivity: Connectivity, pattern: &FillPattern, logger: &mut L) -> Vec<Pos2> {
+    let mut filled = Vec::new();
+    let Some(raster) = BoundaryRaster::new(boundary) else { return filled };
+    let seed = (seed.x.round() as i32, seed.y.round() as i32);
+    if !raster.contains(seed) || raster.boundary.contains(&seed) {
+        if logger.can_write() {
+            logger.log(format!("Затравка ({}, {}) лежит на границе или вне растра - заливать нечего", seed.0, seed.1));
         }
+        return filled;
+    }
+
+    let mut visited = std::collections::HashSet::new();
+    let mut stack = vec![seed];
+    let mut max_depth = 1;
+    while let Some(p) = stack.pop() {
+        logger.iteration();
+        if !raster.contains(p) || raster.boundary.contains(&p) || !visited.insert(p) {
+            continue;
+        }
+        logger.progress(visited.len() as f32 / raster.area() as f32);
+        if logger.can_write() {
+            logger.log_fields(p.0, p.1, &[("стек", stack.len().to_string())]);
+        }
+        if pattern.contains(p.0, p.1) {
+            filled.push(Pos2::new(p.0 as f32, p.1 as f32));
+        }
+        stack.extend(connectivity.neighbours().iter().map(|&(dx, dy)| (p.0 + dx, p.1 + dy)));
+        max_depth = max_depth.max(stack.len());
+    }
+    if logger.can_write() {
+        logger.log(format!("Область {} пикс., закрашено {}, наибольшая глубина стека {}", visited.len(), filled.len(), max_depth));
+    }
+    filled
+}
+
+/// Среднеквадратичное отклонение центров пикселей от идеальной прямой (взвешенное по интенсивности)
+pub fn line_rms_error(records: impl Iterator<Item = PixelRecord>, p1: Pos2, p2: Pos2) -> f32 {
+    let dir = p2 - p1;
+    let len = dir.length();
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for r in records {
+        let d = if len == 0.0 {
+            r.pos().distance(p1)
+        } else {
+            ((r.pos() - p1).x * dir.y - (r.pos() - p1).y * dir.x).abs() / len
+        };
+        sum += r.intensity * d * d;
+        weight += r.intensity;
+    }
+    if weight > 0.0 { (sum / weight).sqrt() } else { 0.0 }
+}
+
+/// Максимальное и среднеквадратичное отклонение центров пикселей от идеальной окружности
+pub fn radial_error(records: impl Iterator<Item = PixelRecord>, center: Pos2, radius: f32) -> (f32, f32) {
+    let mut max = 0.0_f32;
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for r in records {
+        let d = (r.pos().distance(center) - radius).abs();
+        max = max.max(d);
+        sum += r.intensity * d * d;
+        weight += r.intensity;
+    }
+    let rms = if weight > 0.0 { (sum / weight).sqrt() } else { 0.0 };
+    (max, rms)
+}
+
+/// Точка идеальной прямой в столбце (или строке) пикселя: пересечение с прямой x = const при
+/// ведущей оси X и с y = const при ведущей оси Y - именно это значение округляют алгоритмы отрезка
+pub fn line_sample(p1: Pos2, p2: Pos2, pixel: Pos2) -> Option<Pos2> {
+    let d = p2 - p1;
+    if d.x.abs() >= d.y.abs() && d.x != 0.0 {
+        Some(Pos2::new(pixel.x, p1.y + (pixel.x - p1.x) * d.y / d.x))
+    } else if d.y != 0.0 {
+        Some(Pos2::new(p1.x + (pixel.y - p1.y) * d.x / d.y, pixel.y))
     } else {
-        // --- Крутая линия (доминантная ось Y) ---
-        // Логика та же, только меняем роли x и y, dx и dy
-        let mut d = 0;
-        let mut x = x1;
-        let mut y = y1;
+        None
+    }
+}
 
-        for _ in 0..=dy {
-            let intensity = d as f32 / dy as f32;
+/// Точка идеальной окружности в столбце или строке пикселя, на той же стороне от центра.
+/// В октантах, где окружность ближе к горизонтали, берётся столбец, иначе - строка
+pub fn circle_sample(center: Pos2, radius: f32, pixel: Pos2) -> Option<Pos2> {
+    let rel = pixel - center;
+    let sign = |v: f32| if v < 0.0 { -1.0 } else { 1.0 };
+    if rel.x.abs() <= rel.y.abs() {
+        let h = radius * radius - rel.x * rel.x;
+        (h >= 0.0).then(|| Pos2::new(pixel.x, center.y + sign(rel.y) * h.sqrt()))
+    } else {
+        let h = radius * radius - rel.y * rel.y;
+        (h >= 0.0).then(|| Pos2::new(center.x + sign(rel.x) * h.sqrt(), pixel.y))
+    }
+}
 
-            pixels.push((x, y, 1.0 - intensity));
-            pixels.push((x + sx, y, intensity));
+/// Число различных пикселей окружности в каждом октанте (нумерация как у меток bresenham_circle).
+/// Пиксели на швах (осях и диагоналях) засчитываются обоим соседним октантам
+pub fn octant_counts(pixels: &[PixelRecord], center: Pos2) -> [usize; 8] {
+    let cx = center.x.round() as i32;
+    let cy = center.y.round() as i32;
+    let unique: std::collections::HashSet<(i32, i32)> = pixels.iter().map(|r| (r.x, r.y)).collect();
+    let mut counts = [0; 8];
+    for (x, y) in unique {
+        let (dx, dy) = (x - cx, y - cy);
+        // Октант k - это u >= v >= 0 в своей системе координат
+        let frames = [(dx, dy), (dy, dx), (dy, -dx), (-dx, dy), (-dx, -dy), (-dy, -dx), (-dy, dx), (dx, -dy)];
+        for (octant, (u, v)) in frames.into_iter().enumerate() {
+            if u >= v && v >= 0 {
+                counts[octant] += 1;
+            }
+        }
+    }
+    counts
+}
 
-            if logger.can_write() {
-                logger.log(format!("y: {}, x: {}, d: {}/{} -> int: {:.2}", y, x, d, dy, intensity));
+/// Проверка, что во всех октантах одинаковое число пикселей
+pub fn verify_octant_balance(pixels: &[PixelRecord], center: Pos2) -> bool {
+    let counts = octant_counts(pixels, center);
+    counts.iter().all(|&c| c == counts[0])
+}
+
+/// Число повторно выведенных пикселей (совпадающих координат)
+pub fn duplicate_count(records: impl Iterator<Item = PixelRecord>) -> usize {
+    let mut seen = std::collections::HashSet::new();
+    records.filter(|r| !seen.insert((r.x, r.y))).count()
+}
+
+// Ограничение коэффициента суперсэмплинга: factor^2 выборок на пиксель
+pub const MAX_SSAA_FACTOR: u32 = 16;
+
+// Расстояние от точки до отрезка [a, b]
+fn distance_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+    if len_sq == 0.0 {
+        return p.distance(a);
+    }
+    let t = ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+/// Эталонное сглаживание суперсэмплингом: отрезок шириной 1 пиксель,
+/// покрытие пикселя - доля из factor x factor выборок, попавших в отрезок
+pub fn ssaa_line<L: Logger>(p1: Pos2, p2: Pos2, factor: u32, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let factor = factor.clamp(1, MAX_SSAA_FACTOR);
+    let samples = (factor * factor) as f32;
+
+    let min_x = p1.x.min(p2.x).floor() as i32 - 1;
+    let max_x = p1.x.max(p2.x).ceil() as i32 + 1;
+    let min_y = p1.y.min(p2.y).floor() as i32 - 1;
+    let max_y = p1.y.max(p2.y).ceil() as i32 + 1;
+
+    for y in min_y..=max_y {
+        logger.progress((y - min_y) as f32 / (max_y - min_y).max(1) as f32);
+        for x in min_x..=max_x {
+            let center = Pos2::new(x as f32, y as f32);
+            // Пиксель целиком дальше 0.5 + половина диагонали - покрытие заведомо нулевое
+            if distance_to_segment(center, p1, p2) > 0.5 + std::f32::consts::FRAC_1_SQRT_2 {
+                continue;
             }
+            let mut covered = 0;
+            for sy in 0..factor {
+                for sx in 0..factor {
+                    let sample = Pos2::new(
+                        x as f32 - 0.5 + (sx as f32 + 0.5) / factor as f32,
+                        y as f32 - 0.5 + (sy as f32 + 0.5) / factor as f32,
+                    );
+                    if distance_to_segment(sample, p1, p2) <= 0.5 {
+                        covered += 1;
+                    }
+                }
+            }
+            if covered > 0 {
+                let intensity = covered as f32 / samples;
+                pixels.push((x, y, intensity));
+                if logger.can_write() {
+                    logger.log_pixel(x, y, format!("({}, {}): {}/{} выборок -> {:.3}", x, y, covered, samples, intensity));
+                }
+            }
+        }
+    }
+    pixels
+}
 
-            d += dx;
-            if d >= dy {
-                d -= dy;
-                x += sx;
+/// Понижение разрешения результата, построенного на растре в factor раз мельче (суперсэмплинг).
+/// Подпиксель X относится к пикселю floor((2X + 1) / (2 factor)), интенсивность пикселя - доля его
+/// factor x factor подпикселей, покрытых результатом. Контур толщиной в подпиксель дал бы яркость
+/// 1/factor, поэтому при dilate каждый подпиксель сначала расширяется до квадрата factor x factor -
+/// это контур толщиной в один исходный пиксель
+pub fn downsample<L: Logger>(records: impl Iterator<Item = PixelRecord>, factor: u32, dilate: bool, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let k = factor.max(1) as i32;
+    let mut fine = std::collections::HashMap::new();
+    let offsets = if dilate { -(k / 2)..k - k / 2 } else { 0..1 };
+    for r in records {
+        for dy in offsets.clone() {
+            for dx in offsets.clone() {
+                let cell = fine.entry((r.x + dx, r.y + dy)).or_insert(0.0_f32);
+                *cell = cell.max(r.intensity);
             }
-            y += sy;
         }
     }
 
+    // Ключ (y, x), чтобы пиксели выводились построчно
+    let mut coarse = std::collections::BTreeMap::new();
+    for ((x, y), intensity) in fine {
+        let pixel = ((2 * y + 1).div_euclid(2 * k), (2 * x + 1).div_euclid(2 * k));
+        *coarse.entry(pixel).or_insert(0.0) += intensity;
+    }
+    let samples = (k * k) as f32;
+    let mut pixels = Vec::with_capacity(coarse.len());
+    for ((y, x), covered) in coarse {
+        let intensity = (covered / samples).min(1.0);
+        if logger.can_write() {
+            logger.log_pixel(x, y, format!("({}, {}): {:.1}/{} подпикселей -> {:.3}", x, y, covered, samples, intensity));
+        }
+        pixels.push((x, y, intensity));
+    }
+    pixels
+}
+
+/// Точное покрытие пикселей окружностью толщиной 1 пиксель (кольцо r ± 0.5), по factor x factor выборкам.
+/// Строки обходятся только в пределах кольца, поэтому работа растёт линейно с радиусом
+pub fn circle_coverage(center: Pos2, radius: f32, factor: u32) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let factor = factor.clamp(1, MAX_SSAA_FACTOR);
+    let samples = (factor * factor) as f32;
+    let outer = radius + 0.5 + std::f32::consts::SQRT_2;
+    let inner = (radius - 0.5 - std::f32::consts::SQRT_2).max(0.0);
+
+    for y in (center.y - outer).floor() as i32..=(center.y + outer).ceil() as i32 {
+        let dy = y as f32 - center.y;
+        let x_out = (outer * outer - dy * dy).max(0.0).sqrt();
+        let x_in = (inner * inner - dy * dy).max(0.0).sqrt();
+        let left = (center.x - x_out).floor() as i32..=(center.x - x_in).ceil() as i32;
+        let right = ((center.x + x_in).floor() as i32).max(*left.end() + 1)..=(center.x + x_out).ceil() as i32;
+        for x in left.chain(right) {
+            let mut covered = 0;
+            for sy in 0..factor {
+                for sx in 0..factor {
+                    let sample = Pos2::new(
+                        x as f32 - 0.5 + (sx as f32 + 0.5) / factor as f32,
+                        y as f32 - 0.5 + (sy as f32 + 0.5) / factor as f32,
+                    );
+                    if (sample.distance(center) - radius).abs() <= 0.5 {
+                        covered += 1;
+                    }
+                }
+            }
+            if covered > 0 {
+                pixels.push((x, y, covered as f32 / samples));
+            }
+        }
+    }
+    pixels
+}
+
+// Приближённое расстояние со знаком от точки (dx, dy) относительно центра до эллипса с полуосями rx, ry:
+// F / |grad F| для F = (dx/rx)^2 + (dy/ry)^2 - 1 (первый порядок, точно на самой границе)
+fn ellipse_distance(dx: f32, dy: f32, rx: f32, ry: f32) -> f32 {
+    let f = (dx / rx).powi(2) + (dy / ry).powi(2) - 1.0;
+    let grad = Vec2::new(2.0 * dx / (rx * rx), 2.0 * dy / (ry * ry)).length();
+    if grad < f32::EPSILON { rx.min(ry) } else { f / grad }
+}
+
+/// Эллипс со сглаживанием: интенсивность пикселя равна 1 - |d|, где d - расстояние от центра пикселя
+/// до идеальной границы. Строки обходятся только в полосе шириной ±1.5 пикселя вокруг границы
+pub fn ellipse_aa<L: Logger>(center: Pos2, rx: f32, ry: f32, logger: &mut L) -> Vec<AntialiasedPixel> {
+    let mut pixels = Vec::new();
+    let (rx, ry) = (rx.abs().max(0.5), ry.abs().max(0.5));
+    let (outer_x, outer_y) = (rx + 1.5, ry + 1.5);
+    let (inner_x, inner_y) = ((rx - 1.5).max(0.0), (ry - 1.5).max(0.0));
+    // Полуширина строки эллипса с полуосями a, b на высоте dy от центра
+    let half_width = |a: f32, b: f32, dy: f32| if b > 0.0 { a * (1.0 - (dy / b).powi(2)).max(0.0).sqrt() } else { 0.0 };
+
+    let first_row = (center.y - outer_y).floor() as i32;
+    let last_row = (center.y + outer_y).ceil() as i32;
+    for y in first_row..=last_row {
+        logger.progress((y - first_row) as f32 / (last_row - first_row).max(1) as f32);
+        let dy = y as f32 - center.y;
+        let x_out = half_width(outer_x, outer_y, dy);
+        let x_in = half_width(inner_x, inner_y, dy);
+        let left = (center.x - x_out).floor() as i32..=(center.x - x_in).ceil() as i32;
+        let right = ((center.x + x_in).floor() as i32).max(*left.end() + 1)..=(center.x + x_out).ceil() as i32;
+        for x in left.chain(right) {
+            let d = ellipse_distance(x as f32 - center.x, dy, rx, ry);
+            let intensity = 1.0 - d.abs();
+            if intensity <= 0.0 {
+                continue;
+            }
+            pixels.push((x, y, intensity));
+            if logger.can_write() {
+                logger.log_fields(x, y, &[
+                    ("x", x.to_string()),
+                    ("y", y.to_string()),
+                    ("d", format!("{:.3}", d)),
+                    ("интенсивность", format!("{:.3}", intensity)),
+                ]);
+            }
+        }
+    }
     pixels
-}
\ No newline at end of file
+}
+
+/// Средняя по объединению пикселей абсолютная разница интенсивности результата и эталонного покрытия
+pub fn coverage_error(records: impl Iterator<Item = PixelRecord>, reference: &[AntialiasedPixel]) -> f32 {
+    let mut diff: std::collections::HashMap<(i32, i32), f32> = std::collections::HashMap::new();
+    for r in records {
+        let v = diff.entry((r.x, r.y)).or_insert(0.0);
+        *v = (*v + r.intensity).min(1.0);
+    }
+    for &(x, y, c) in reference {
+        *diff.entry((x, y)).or_insert(0.0) -= c;
+    }
+    if diff.is_empty() {
+        return 0.0;
+    }
+    diff.values().map(|d| d.abs()).sum::<f32>() / diff.len() as f32
+}
+
+/// Разность множеств пикселей: (есть только в a, есть только в b)
+pub fn pixel_set_diff(a: impl Iterator<Item = PixelRecord>, b: impl Iterator<Item = PixelRecord>) -> (usize, usize) {
+    let (only_a, only_b) = pixel_set_delta(a, b);
+    (only_a.len(), only_b.len())
+}
+
+// Различающиеся пиксели двух результатов: (есть только в a, есть только в b)
+pub type PixelDelta = (Vec<(i32, i32)>, Vec<(i32, i32)>);
+
+/// Сами различающиеся пиксели двух результатов
+pub fn pixel_set_delta(a: impl Iterator<Item = PixelRecord>, b: impl Iterator<Item = PixelRecord>) -> PixelDelta {
+    let set_a: std::collections::HashSet<(i32, i32)> = a.filter(|r| r.intensity > 0.0).map(|r| (r.x, r.y)).collect();
+    let set_b: std::collections::HashSet<(i32, i32)> = b.filter(|r| r.intensity > 0.0).map(|r| (r.x, r.y)).collect();
+    (set_a.difference(&set_b).copied().collect(), set_b.difference(&set_a).copied().collect())
+}
+
+/// Число различных пикселей (без учёта повторов)
+pub fn unique_count(records: impl Iterator<Item = PixelRecord>) -> usize {
+    records.map(|r| (r.x, r.y)).collect::<std::collections::HashSet<_>>().len()
+}
+
+/// Теоретическое число пикселей отрезка: длина по доминантной оси плюс один
+pub fn theoretical_line_pixels(p1: Pos2, p2: Pos2) -> usize {
+    let dx = (p2.x.round() - p1.x.round()).abs();
+    let dy = (p2.y.round() - p1.y.round()).abs();
+    dx.max(dy) as usize + 1
+}
+
+/// Пикселей на единицу длины: число выведенных пикселей, делённое на евклидову длину отрезка hypot(dx, dy).
+/// У длинного горизонтального отрезка значение стремится к 1, у диагонального - к 1/√2: 8-связная линия
+/// ставит один пиксель на шаг по основной оси, а шаг по диагонали длиннее в √2 раз. Повторы увеличивают
+/// значение, пропуски - уменьшают. Отрезок нулевой длины (одна точка) считается длиной 1
+pub fn pixels_per_unit_length(records: impl Iterator<Item = PixelRecord>, p1: Pos2, p2: Pos2) -> f32 {
+    let count = records.filter(|r| r.intensity > 0.0).count();
+    let length = (p2 - p1).length();
+    count as f32 / if length > 0.0 { length } else { 1.0 }
+}
+
+/// Концы отрезка прямой y = kx + b на промежутке x ∈ [x_from, x_to]
+pub fn line_from_slope_intercept(k: f32, b: f32, x_from: f32, x_to: f32) -> (Pos2, Pos2) {
+    (Pos2::new(x_from, k * x_from + b), Pos2::new(x_to, k * x_to + b))
+}
+
+/// Концы вертикального отрезка x = c на промежутке y ∈ [y_from, y_to]
+pub fn vertical_line(c: f32, y_from: f32, y_to: f32) -> (Pos2, Pos2) {
+    (Pos2::new(c, y_from), Pos2::new(c, y_to))
+}
+
+/// Описанная окружность треугольника abc: центр - пересечение серединных перпендикуляров.
+/// None, если точки лежат на одной прямой (знаменатель обращается в ноль)
+pub fn circumcircle(a: Pos2, b: Pos2, c: Pos2) -> Option<(Pos2, f32)> {
+    let (ab, ac) = (b - a, c - a);
+    let denom = 2.0 * (ab.x * ac.y - ab.y * ac.x);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let (ab2, ac2) = (ab.length_sq(), ac.length_sq());
+    let offset = Vec2::new(ac.y * ab2 - ab.y * ac2, ab.x * ac2 - ac.x * ab2) / denom;
+    Some((a + offset, offset.length()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Логгер, запоминающий поля записей о пикселях
+    #[derive(Default)]
+    struct FieldsLogger {
+        rows: Vec<Vec<(String, String)>>,
+    }
+
+    impl FieldsLogger {
+        fn column(&self, name: &str) -> Vec<String> {
+            self.rows
+                .iter()
+                .filter_map(|row| row.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone()))
+                .collect()
+        }
+    }
+
+    impl Logger for FieldsLogger {
+        fn log(&mut self, _message: String) {}
+
+        fn log_step(&mut self, x: i32, y: i32, _message: String, fields: &[(&str, String)]) {
+            self.log_fields(x, y, fields);
+        }
+
+        fn log_fields(&mut self, _x: i32, _y: i32, fields: &[(&str, String)]) {
+            self.rows.push(fields.iter().map(|(n, v)| (n.to_string(), v.clone())).collect());
+        }
+
+        fn can_write(&self) -> bool {
+            true
+        }
+
+        fn results(self) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn bresenham_line_values_match_logged_error() {
+        let mut logger = FieldsLogger::default();
+        let pixels = bresenham_line_rich(Pos2::new(-3.0, 2.0), Pos2::new(14.0, -5.0), &mut logger);
+        let logged = logger.column("ошибка");
+        assert_eq!(pixels.len(), logged.len());
+        for (pixel, err) in pixels.iter().zip(&logged) {
+            assert_eq!(pixel.value, err.parse::<f32>().unwrap());
+        }
+    }
+
+    #[test]
+    fn radial_error_of_known_pixels() {
+        let center = Pos2::new(2.0, -3.0);
+        let on_circle = [PixelRecord::from_pos(0, Pos2::new(7.0, -3.0)), PixelRecord::from_pos(1, Pos2::new(2.0, 2.0))];
+        assert_eq!(radial_error(on_circle.iter().copied(), center, 5.0), (0.0, 0.0));
+        let off = [PixelRecord::from_pos(0, Pos2::new(7.0, -3.0)), PixelRecord::from_pos(1, Pos2::new(2.0, 3.0))];
+        let (max, rms) = radial_error(off.iter().copied(), center, 5.0);
+        assert_eq!(max, 1.0);
+        assert!((rms - 0.5_f32.sqrt()).abs() < 1e-6);
+    }
+
+    // Радиальные ошибки методов сравнения окружностей при r = 10
+    #[test]
+    fn circle_methods_radial_error() {
+        let (center, r) = (Pos2::new(3.0, -4.0), 10.0);
+        let error = |records: Vec<PixelRecord>| radial_error(records.into_iter(), center, r).0;
+        let from_pos = |pixels: Vec<Pos2>| pixels.into_iter().map(|p| PixelRecord::from_pos(0, p)).collect();
+
+        // Ближайший к окружности пиксель в каждом столбце
+        assert!(error(from_pos(sqrt_circle(center, r, &mut NoOpLogger))) <= 0.5);
+        // Брезенхем обновляет d уже по новому x и у стыка октантов уходит от окружности дальше
+        // полупикселя: при r = 10 пиксель (6, 7) лежит на расстоянии около 0.78
+        assert!(error(bresenham_circle(center, r, ALL_OCTANTS, &mut NoOpLogger)) < 0.8);
+        // Округление обеих координат точки окружности сдвигает её не больше чем на √2 / 2
+        let rounded = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(error(from_pos(polar_circle(center, r, &mut NoOpLogger))) <= rounded);
+        assert!(error(from_pos(rotation_circle(center, r, &mut NoOpLogger))) <= rounded);
+        // Сглаженная окружность закрашивает пару пикселей по обе стороны от кривой
+        let wu = wu_circle(center, r, &mut NoOpLogger).into_iter().map(|p| PixelRecord::from_antialiased(0, p)).collect();
+        assert!(error(wu) <= 1.0);
+    }
+
+    #[test]
+    fn bresenham_circle_octants_are_balanced() {
+        for r in [1.0, 2.0, 5.0, 10.0, 17.0, 50.0, 123.0] {
+            let center = Pos2::new(-7.0, 4.0);
+            let pixels = bresenham_circle(center, r, ALL_OCTANTS, &mut NoOpLogger);
+            assert!(verify_octant_balance(&pixels, center), "r = {}: {:?}", r, octant_counts(&pixels, center));
+        }
+    }
+
+    #[test]
+    fn octant_balance_detects_missing_pixel() {
+        let center = Pos2::ZERO;
+        let mut pixels = bresenham_circle(center, 10.0, ALL_OCTANTS, &mut NoOpLogger);
+        // Пиксель (8, 5) лежит внутри октанта, не на шве
+        let before = octant_counts(&pixels, center);
+        pixels.retain(|p| (p.x, p.y) != (8, 5));
+        let after = octant_counts(&pixels, center);
+        assert_eq!(after.iter().sum::<usize>() + 1, before.iter().sum::<usize>());
+        assert!(!verify_octant_balance(&pixels, center));
+    }
+
+    // Большие полуоси и область: промежуточные d превышают диапазон i64
+    #[test]
+    fn large_hyperbola_stays_near_curve() {
+        let (a, b) = (10_000.0_f64, 10_000.0_f64);
+        let bounds = Rect::from_min_max(Pos2::new(-2e5, -2e5), Pos2::new(2e5, 2e5));
+        let pixels = midpoint_hyperbola(Pos2::ZERO, a as f32, b as f32, true, bounds, &mut NoOpLogger);
+        assert!(pixels.iter().any(|p| p.y.abs() >= 199_000));
+        for p in &pixels {
+            let (x, y) = ((p.x as f64).abs(), (p.y as f64).abs());
+            let across = (x - a * (1.0 + (y / b).powi(2)).sqrt()).abs();
+            let along = if x >= a { (y - b * ((x / a).powi(2) - 1.0).sqrt()).abs() } else { f64::INFINITY };
+            assert!(across.min(along) <= 0.5 + 1e-6, "({}, {})", p.x, p.y);
+        }
+    }
+
+    #[test]
+    fn pixels_per_unit_length_of_clean_lines() {
+        let density = |p1: Pos2, p2: Pos2| pixels_per_unit_length(bresenham_line_rich(p1, p2, &mut NoOpLogger).into_iter(), p1, p2);
+        // 20 шагов и 21 пиксель на длине 20√2 и 20
+        let diagonal = density(Pos2::new(0.0, 0.0), Pos2::new(20.0, 20.0));
+        assert!((diagonal - 21.0 / 20.0 / std::f32::consts::SQRT_2).abs() < 1e-5, "{}", diagonal);
+        assert!((density(Pos2::new(-3.0, 5.0), Pos2::new(17.0, 5.0)) - 1.05).abs() < 1e-5);
+        // На длинных отрезках вклад второго конца исчезает: 1/√2 по диагонали, 1 по оси
+        let long = density(Pos2::new(0.0, 0.0), Pos2::new(-1000.0, 1000.0));
+        assert!((long - std::f32::consts::FRAC_1_SQRT_2).abs() < 2e-3, "{}", long);
+        assert!((density(Pos2::new(0.0, 0.0), Pos2::new(0.0, 1000.0)) - 1.0).abs() < 2e-3);
+        assert_eq!(density(Pos2::new(2.0, 2.0), Pos2::new(2.0, 2.0)), 1.0);
+
+        let (p1, p2) = (Pos2::new(0.0, 0.0), Pos2::new(30.0, 7.0));
+        let line = bresenham_line_rich(p1, p2, &mut NoOpLogger);
+        let clean = pixels_per_unit_length(line.iter().copied(), p1, p2);
+        // Повторно выведенные пиксели увеличивают значение, пропущенный конец - уменьшает
+        assert!(pixels_per_unit_length(line.iter().chain(&line[..5]).copied(), p1, p2) > clean);
+        assert!(pixels_per_unit_length(line[1..].iter().copied(), p1, p2) < clean);
+    }
+
+    // Пары пикселей столбца x: взвешенный интенсивностями центр по y
+    fn column_center(pixels: &[AntialiasedPixel], x: i32) -> (f32, Vec<f32>) {
+        let column: Vec<_> = pixels.iter().filter(|p| p.0 == x).collect();
+        let center = column.iter().map(|p| p.1 as f32 * p.2).sum::<f32>() / column.iter().map(|p| p.2).sum::<f32>();
+        (center, column.iter().map(|p| p.2).collect())
+    }
+
+    #[test]
+    fn floor_and_trunc_differ_only_below_zero() {
+        // y = x / 2 - 2.25 пересекает ось X при x = 4.5
+        let (p1, p2) = (Pos2::new(0.0, -2.25), Pos2::new(10.0, 2.75));
+        let floor = step_by_step_aa(p1, p2, RoundingMode::HalfUp, FractionMode::Floor, &mut NoOpLogger);
+        let trunc = step_by_step_aa(p1, p2, RoundingMode::HalfUp, FractionMode::Truncate, &mut NoOpLogger);
+        for x in 0..=10 {
+            let ideal = x as f32 / 2.0 - 2.25;
+            let (center, intensities) = column_center(&floor, x);
+            assert!((center - ideal).abs() < 1e-5, "floor, x = {}", x);
+            assert!(intensities.iter().all(|i| (0.0..=1.0).contains(i)));
+
+            let (_, truncated) = column_center(&trunc, x);
+            let in_range = truncated.iter().all(|i| (0.0..=1.0).contains(i));
+            // Для отрицательных нецелых y trunc берёт пиксель выше, и дробная часть становится отрицательной
+            assert_eq!(in_range, ideal >= 0.0, "trunc, x = {}", x);
+        }
+    }
+
+    #[test]
+    fn pen_pattern_keeps_expected_runs() {
+        let line = bresenham_line(Pos2::new(0.0, 0.0), Pos2::new(29.0, 0.0), &mut NoOpLogger);
+        assert_eq!(apply_pen_pattern(line.clone(), PenPattern::Solid), line);
+
+        // Штрих: пиксели 0-5 рисуются, 6-9 пропускаются, и так далее
+        let xs = |pattern| -> Vec<i32> { apply_pen_pattern(line.clone(), pattern).iter().map(|p| p.x as i32).collect() };
+        assert_eq!(xs(PenPattern::Dashed), [0, 1, 2, 3, 4, 5, 10, 11, 12, 13, 14, 15, 20, 21, 22, 23, 24, 25]);
+        assert_eq!(xs(PenPattern::Dotted), (0..30).step_by(3).collect::<Vec<_>>());
+        assert!(apply_pen_pattern(Vec::new(), PenPattern::Dashed).is_empty());
+    }
+
+    #[test]
+    fn polygon_fill_aa_coverage() {
+        let square = [Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0), Pos2::new(10.0, 10.0), Pos2::new(0.0, 10.0)];
+        let pixels = scanline_fill_aa(&square, &FillPattern::solid(), &mut NoOpLogger);
+        let at = |x: i32, y: i32| pixels.iter().find(|p| (p.0, p.1) == (x, y)).map(|p| p.2);
+        assert_eq!(at(5, 5), Some(1.0));
+        assert!(pixels.iter().filter(|p| (1..10).contains(&p.0) && (1..10).contains(&p.1)).all(|p| p.2 == 1.0));
+        // Стороны квадрата проходят через центры крайних пикселей: покрыта половина, в углах - четверть
+        assert!((at(0, 5).unwrap() - 0.5).abs() < 1e-5);
+        assert!((at(10, 0).unwrap() - 0.25).abs() < 1e-5);
+        let area: f32 = pixels.iter().map(|p| p.2).sum();
+        assert!((area - 100.0).abs() < 1e-3, "{}", area);
+
+        // Вершина NaN не должна приводить к панике
+        let broken = [Pos2::new(0.0, 0.0), Pos2::new(f32::NAN, 3.0), Pos2::new(4.0, 5.0)];
+        scanline_fill_aa(&broken, &FillPattern::solid(), &mut NoOpLogger);
+    }
+
+    #[test]
+    fn polygon_fill_aa_sliver_matches_supersampling() {
+        // Узкий треугольник: по всей длине тоньше пикселя, покрытие задают только края
+        let sliver = [Pos2::new(0.2, 0.1), Pos2::new(25.7, 1.4), Pos2::new(0.3, 0.9)];
+        let pixels = scanline_fill_aa(&sliver, &FillPattern::solid(), &mut NoOpLogger);
+
+        // Эталон: доля из 32x32 выборок в пикселе, попавших внутрь треугольника
+        let n = 32;
+        let inside = |p: Pos2| {
+            let side = |a: Pos2, b: Pos2| (b - a).x * (p - a).y - (b - a).y * (p - a).x;
+            let s = [side(sliver[0], sliver[1]), side(sliver[1], sliver[2]), side(sliver[2], sliver[0])];
+            s.iter().all(|&v| v >= 0.0) || s.iter().all(|&v| v <= 0.0)
+        };
+        let mut max_error: f32 = 0.0;
+        for y in -1..=3 {
+            for x in -1..=27 {
+                let covered = (0..n * n)
+                    .filter(|i| inside(Pos2::new(
+                        x as f32 - 0.5 + ((i % n) as f32 + 0.5) / n as f32,
+                        y as f32 - 0.5 + ((i / n) as f32 + 0.5) / n as f32,
+                    )))
+                    .count();
+                let reference = covered as f32 / (n * n) as f32;
+                let actual = pixels.iter().find(|p| (p.0, p.1) == (x, y)).map_or(0.0, |p| p.2);
+                max_error = max_error.max((actual - reference).abs());
+            }
+        }
+        // Погрешность дают только COVERAGE_SUBSCANLINES подстрок по вертикали (сейчас около 0.026)
+        assert!(max_error < 0.05, "{}", max_error);
+    }
+
+    #[test]
+    fn ssaa_line_error_decreases_with_samples() {
+        let (p1, p2) = (Pos2::new(0.3, 0.2), Pos2::new(40.1, 13.7));
+        let (k, b) = ((p2.y - p1.y) / (p2.x - p1.x), p1.y - (p2.y - p1.y) / (p2.x - p1.x) * p1.x);
+        // Вдали от концов отрезок - полоса |y - (k x + b)| <= h; в каждом столбце пикселя её пересечение
+        // с пикселем по вертикали считается точно, по горизонтали интеграл берётся мелкими шагами
+        let h = 0.5 * (1.0 + k * k).sqrt();
+        let exact = |x: i32, y: i32| {
+            let steps = 1000;
+            (0..steps)
+                .map(|i| {
+                    let u = x as f32 - 0.5 + (i as f32 + 0.5) / steps as f32;
+                    let center = k * u + b;
+                    ((center + h).min(y as f32 + 0.5) - (center - h).max(y as f32 - 0.5)).max(0.0)
+                })
+                .sum::<f32>()
+                / steps as f32
+        };
+
+        let errors: Vec<f32> = [2, 4, 8]
+            .iter()
+            .map(|&factor| {
+                let pixels = ssaa_line(p1, p2, factor, &mut NoOpLogger);
+                let mut total = 0.0;
+                let mut count = 0;
+                for x in 5..=35 {
+                    let row = (k * x as f32 + b).round() as i32;
+                    for y in row - 2..=row + 2 {
+                        let actual = pixels.iter().find(|p| (p.0, p.1) == (x, y)).map_or(0.0, |p| p.2);
+                        let reference = exact(x, y);
+                        if actual > 0.0 || reference > 0.0 {
+                            total += (actual - reference).abs();
+                            count += 1;
+                        }
+                    }
+                }
+                total / count as f32
+            })
+            .collect();
+        assert!(errors[0] > errors[1] && errors[1] > errors[2], "{:?}", errors);
+    }
+
+    #[test]
+    fn wu_circle_has_no_seam_duplicates() {
+        for r in [1.0, 7.5, 10.0, 17.0, 50.0] {
+            let pixels = wu_circle(Pos2::new(3.0, -2.0), r, &mut NoOpLogger);
+            let unique: std::collections::HashSet<_> = pixels.iter().map(|p| (p.0, p.1)).collect();
+            assert_eq!(pixels.len(), unique.len(), "r = {}", r);
+        }
+        assert_eq!(wu_circle(Pos2::new(0.0, 0.0), 10.0, &mut NoOpLogger).len(), 104);
+    }
+
+    #[test]
+    fn octant_grouping_puts_computed_octant_first() {
+        let circle = bresenham_circle(Pos2::new(2.0, -1.0), 12.0, ALL_OCTANTS, &mut NoOpLogger);
+        let grouped = group_by_octant(circle.clone());
+        let steps = circle.iter().map(|r| r.step_index).max().unwrap() + 1;
+        assert!(grouped[..steps].iter().enumerate().all(|(i, r)| r.tag == 1 && r.step_index == i));
+        let tags: Vec<u8> = grouped.iter().step_by(steps).map(|r| r.tag).collect();
+        assert_eq!(tags, OCTANT_ORDER);
+        let key = |r: &PixelRecord| (r.x, r.y, r.tag, r.step_index);
+        let mut before: Vec<_> = circle.iter().map(key).collect();
+        let mut after: Vec<_> = grouped.iter().map(key).collect();
+        before.sort_unstable();
+        after.sort_unstable();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn rounding_modes_differ_on_half_ties() {
+        // На отрезке (0,0)-(4,1) при x = 2 идеальное y = 0.5
+        let (p1, p2) = (Pos2::new(0.0, 0.0), Pos2::new(4.0, 1.0));
+        let at_two = |pixels: Vec<Pos2>| pixels.iter().find(|p| p.x == 2.0).unwrap().y;
+        assert_eq!(at_two(dda(p1, p2, RoundingMode::HalfUp, &mut NoOpLogger)), 1.0);
+        assert_eq!(at_two(dda(p1, p2, RoundingMode::HalfToEven, &mut NoOpLogger)), 0.0);
+        assert_eq!(at_two(step_by_step(p1, p2, RoundingMode::HalfUp, &mut NoOpLogger)), 1.0);
+        assert_eq!(at_two(step_by_step(p1, p2, RoundingMode::HalfToEven, &mut NoOpLogger)), 0.0);
+    }
+
+    #[test]
+    fn half_up_reproduces_plain_round() {
+        // Режим по умолчанию обязан давать тот же результат, что и f32::round до введения режимов
+        let lines = [((0.0, 0.0), (4.0, 1.0)), ((-3.0, 2.0), (7.0, -5.0)), ((1.0, 1.0), (2.0, 9.0)), ((0.0, 0.0), (10.0, 5.0))];
+        for ((x1, y1), (x2, y2)) in lines {
+            let (p1, p2) = (Pos2::new(x1, y1), Pos2::new(x2, y2));
+            let (dx, dy) = (p2.x - p1.x, p2.y - p1.y);
+            let steps = dx.abs().max(dy.abs());
+            let mut expected = Vec::new();
+            let (mut x, mut y) = (p1.x, p1.y);
+            for _ in 0..=steps.round() as u32 {
+                expected.push(Pos2::new(x.round(), y.round()));
+                x += dx / steps;
+                y += dy / steps;
+            }
+            assert_eq!(dda(p1, p2, RoundingMode::HalfUp, &mut NoOpLogger), expected);
+
+            let expected: Vec<Pos2> = if dx.abs() > dy.abs() {
+                let (k, b) = (dy / dx, p1.y - dy / dx * p1.x);
+                (p1.x.min(p2.x).round() as i32..=p1.x.max(p2.x).round() as i32)
+                    .map(|x| Pos2::new(x as f32, (k * x as f32 + b).round()))
+                    .collect()
+            } else {
+                let (k, b) = (dx / dy, p1.x - dx / dy * p1.y);
+                (p1.y.min(p2.y).round() as i32..=p1.y.max(p2.y).round() as i32)
+                    .map(|y| Pos2::new((k * y as f32 + b).round(), y as f32))
+                    .collect()
+            };
+            assert_eq!(step_by_step(p1, p2, RoundingMode::HalfUp, &mut NoOpLogger), expected);
+        }
+    }
+}
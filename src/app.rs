@@ -1,25 +1,758 @@
 // src/app.rs
 
-use crate::algo::{self, Algorithm, AntialiasedPixel};
-use crate::logger::{Logger, NoOpLogger, StringLogger};
+use crate::algo::{self, Algorithm, AntialiasedPixel, Connectivity, FillPattern, FractionMode, LineCap, LineJoin, PenPattern, Projection, PixelDelta, PixelRecord, RoundingMode, Stamp};
+use crate::clip;
+use crate::expr::Expr;
+use crate::history::History;
+use crate::logger::{Logger, NoOpLogger, ProgressLogger, RangeFilterLogger, StringLogger, TableLogger};
+use crate::report::{self, PixelGrid, Report, ReportFormat, Table};
+use crate::shortcuts::{self, Action, Shortcuts};
 use egui::{
-    color_picker, Align2, Color32, Painter, Pos2, Rect, Rounding, Sense, Stroke, Vec2,
+    Align2, Color32, Painter, Pos2, Rect, Rounding, Sense, Stroke, Vec2,
 };
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
 
-// Структура для хранения состояния открытого текстового окна
+// Ниже этого масштаба логический пиксель меньше экранного, и результат рисуется прореженным
+const DOWNSAMPLE_ZOOM_THRESHOLD: f32 = 1.0;
+
+// Минимальный экранный размер клетки шахматного фона
+const CHECKER_MIN_CELL: f32 = 8.0;
+
+// Предел числа выборок пользовательской кривой
+const MAX_CUSTOM_SAMPLES: usize = 20_000;
+
+// Доля холста, которую занимает прямоугольник после "вписать в окно"
+const FIT_MARGIN: f32 = 0.9;
+
+// Масштаб вида по умолчанию (клавиша Home)
+const DEFAULT_ZOOM: f32 = 2.0;
+
+// Сдвиг холста стрелкой и множитель масштаба клавишами +/-
+const KEY_PAN_STEP: f32 = 40.0;
+const KEY_ZOOM_FACTOR: f32 = 1.25;
+
+// Масштаб колесом растёт в exp(WHEEL_ZOOM_RATE * прокрутка) раз: одинаковая прокрутка - одинаковый множитель
+const WHEEL_ZOOM_RATE: f32 = 0.008;
+
+// Постоянная времени плавного масштабирования, в секундах
+const ZOOM_ANIMATION_TIME: f32 = 0.06;
+
+// Размер миникарты и её отступ от угла холста, в экранных точках
+const MINIMAP_SIZE: Vec2 = Vec2::new(160.0, 120.0);
+const MINIMAP_MARGIN: f32 = 10.0;
+
+// Центры пикселей и точки идеала рисуются, только когда пиксель не меньше этого числа экранных точек
+const SUBPIXEL_MIN_ZOOM: f32 = 6.0;
+
+// Файл, в котором между запусками хранятся панель управления и положения текстовых окон
+const LAYOUT_PATH: &str = "layout.txt";
+const PANEL_DEFAULT_WIDTH: f32 = 200.0;
+// Сколько закрытых текстовых окон хранится для повторного открытия; более старые забываются
+const MAX_CLOSED_WINDOWS: usize = 20;
+
+// Предел модуля координат и размеров фигур: дальше расчёт заметно затягивается, а результат всё равно не разглядеть
+const MAX_COORDINATE: f32 = 10_000.0;
+
+// Радиус захвата точки мышью, в экранных точках
+const POINT_GRAB_RADIUS: f32 = 8.0;
+
+// Касательная кривой Эрмита равна HERMITE_HANDLE_SCALE * (ручка - конец): при этом ручки
+// совпадают с внутренними опорными точками эквивалентной кубической кривой Безье
+const HERMITE_HANDLE_SCALE: f32 = 3.0;
+
+// Индикатор прогресса показывается, только если расчёт идёт дольше этого времени
+const PROGRESS_DELAY: std::time::Duration = std::time::Duration::from_millis(200);
+
+// Вид холста: сдвиг центра в экранных точках и масштаб по осям. При разделённом экране
+// у каждой половины свой вид, а результат и параметры общие
+#[derive(Clone, Copy)]
+struct Viewport {
+    pan: Vec2,
+    zoom_x: f32,
+    zoom_y: f32,
+    // Размер холста на прошлом кадре - нужен, чтобы вписывать прямоугольник вне отрисовки
+    size: Vec2,
+    animation: Option<ZoomAnimation>,
+}
+
+// Плавный переход к целевому масштабу: логическая точка anchor_logic всё время остаётся
+// на экране в anchor_offset от центра холста
+#[derive(Clone, Copy)]
+struct ZoomAnimation {
+    target: Vec2,
+    anchor_offset: Vec2,
+    anchor_logic: Pos2,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self { pan: Vec2::ZERO, zoom_x: DEFAULT_ZOOM, zoom_y: DEFAULT_ZOOM, size: Vec2::new(800.0, 600.0), animation: None }
+    }
+}
+
+impl Viewport {
+    // Масштаб по осям: число экранных точек на логическую единицу
+    fn zoom(&self) -> Vec2 {
+        Vec2::new(self.zoom_x, self.zoom_y)
+    }
+
+    fn min_zoom(&self) -> f32 {
+        self.zoom_x.min(self.zoom_y)
+    }
+
+    fn transform(&self, rect: &Rect) -> egui::emath::RectTransform {
+        egui::emath::RectTransform::from_to(
+            Rect::from_center_size(Pos2::ZERO, rect.size() / self.zoom()),
+            Rect::from_center_size(rect.center() + self.pan, rect.size()),
+        )
+    }
+
+    // Видимая логическая область
+    fn bounds(&self) -> Rect {
+        Rect::from_center_size((-self.pan / self.zoom()).to_pos2(), self.size / self.zoom())
+    }
+
+    // Масштабирование в factor раз относительно точки anchor_offset (от центра холста). Идущая анимация
+    // не обрывается: множитель применяется к её целевому масштабу. Пропорции осей сохраняются,
+    // поэтому множитель ограничивается так, чтобы обе оси остались в пределах limits
+    fn zoom_by(&mut self, factor: f32, anchor_offset: Vec2, limits: (f32, f32)) {
+        let base = self.animation.map_or(self.zoom(), |a| a.target);
+        let factor = factor.min(limits.1 / base.max_elem()).max(limits.0 / base.min_elem());
+        let anchor_logic = ((anchor_offset - self.pan) / self.zoom()).to_pos2();
+        self.animation = Some(ZoomAnimation { target: base * factor, anchor_offset, anchor_logic });
+    }
+
+    // Шаг анимации: логарифм масштаба экспоненциально приближается к целевому. Возвращает true, пока она идёт
+    fn animate(&mut self, dt: f32, smooth: bool) -> bool {
+        let Some(animation) = self.animation else { return false };
+        let t = if smooth { 1.0 - (-dt / ZOOM_ANIMATION_TIME).exp() } else { 1.0 };
+        let step = |from: f32, to: f32| (from.ln() + (to.ln() - from.ln()) * t).exp();
+        self.zoom_x = step(self.zoom_x, animation.target.x);
+        self.zoom_y = step(self.zoom_y, animation.target.y);
+        let done = (self.zoom_x / animation.target.x - 1.0).abs() < 1e-3 && (self.zoom_y / animation.target.y - 1.0).abs() < 1e-3;
+        if done {
+            self.zoom_x = animation.target.x;
+            self.zoom_y = animation.target.y;
+            self.animation = None;
+        }
+        self.pan = animation.anchor_offset - animation.anchor_logic.to_vec2() * self.zoom();
+        !done
+    }
+
+    // Сдвиг во время анимации сдвигает и её неподвижную точку
+    fn pan_by(&mut self, delta: Vec2) {
+        self.pan += delta;
+        if let Some(animation) = &mut self.animation {
+            animation.anchor_offset += delta;
+        }
+    }
+
+    // Сдвигает вид так, чтобы логическая точка оказалась в центре холста
+    fn center_on(&mut self, point: Pos2) {
+        self.animation = None;
+        self.pan = Vec2::new(-point.x * self.zoom_x, -point.y * self.zoom_y);
+    }
+
+    // Масштаб и сдвиг, при которых прямоугольник (в логических координатах) занимает
+    // FIT_MARGIN холста и стоит по центру
+    fn fit_to(&mut self, target: Rect, keep_aspect: bool, limits: (f32, f32)) {
+        let size = target.size().max(Vec2::splat(1.0));
+        let zoom_x = (self.size.x * FIT_MARGIN / size.x).clamp(limits.0, limits.1);
+        let zoom_y = (self.size.y * FIT_MARGIN / size.y).clamp(limits.0, limits.1);
+        if keep_aspect {
+            self.zoom_x = zoom_x.min(zoom_y);
+            self.zoom_y = self.zoom_x;
+        } else {
+            self.zoom_x = zoom_x;
+            self.zoom_y = zoom_y;
+        }
+        self.center_on(target.center());
+    }
+
+    // Масштаб, введённый по осям вручную. При сохранении пропорций вторая ось
+    // принимает значение изменённой
+    fn set_axis_zoom(&mut self, zoom: Vec2, keep_aspect: bool) {
+        self.animation = None;
+        if !keep_aspect {
+            self.zoom_x = zoom.x;
+            self.zoom_y = zoom.y;
+        } else if zoom.x != self.zoom_x {
+            self.zoom_x = zoom.x;
+            self.zoom_y = zoom.x;
+        } else {
+            self.zoom_x = zoom.y;
+            self.zoom_y = zoom.y;
+        }
+    }
+}
+
+// Текущее перетаскивание на холсте: что именно двигает мышь от нажатия до отпускания
+#[derive(Clone, Copy, PartialEq)]
+enum CanvasDrag {
+    None,
+    Pan,
+    Point(usize),
+    SelectionNew(Pos2),
+    SelectionEdges { left: bool, right: bool, top: bool, bottom: bool },
+    Measure,
+    // Перетаскивание по миникарте; логическая область карты фиксируется на время перетаскивания
+    Minimap(Rect),
+}
+
+// Шаги сетки: основной, дополнительный и непрозрачность дополнительных линий
+struct GridSteps {
+    major: f32,
+    minor: f32,
+    minor_alpha: f32,
+}
+
+// Максимальное число линий одного уровня сетки вдоль оси за кадр
+const MAX_GRID_LINES: f32 = 400.0;
+
+// Раскладка интерфейса: панель управления и положения текстовых окон по виду окна и его номеру среди открытых окон того же вида
+#[derive(Clone, PartialEq)]
+struct Layout {
+    panel_open: bool,
+    panel_width: f32,
+    // Текстовые окна - вкладками в нижней панели, а не плавающими окнами
+    windows_docked: bool,
+    window_positions: std::collections::BTreeMap<String, Pos2>,
+}
+
+impl Layout {
+    // Строки вида "panel_width = 300" и "window Справка#0 = 120, 80"; без файла - панель
+    // развёрнута, окна размещаются egui
+    fn load(path: &str) -> Self {
+        let mut layout = Self { panel_open: true, panel_width: PANEL_DEFAULT_WIDTH, windows_docked: false, window_positions: Default::default() };
+        let text = std::fs::read_to_string(path).unwrap_or_default();
+        for line in text.lines() {
+            let Some((key, value)) = line.rsplit_once('=') else { continue };
+            let (key, value) = (key.trim(), value.trim());
+            if let Some(window_key) = key.strip_prefix("window ") {
+                let position = value.split_once(',').and_then(|(x, y)| Some(Pos2::new(x.trim().parse().ok()?, y.trim().parse().ok()?)));
+                if let Some(position) = position {
+                    layout.window_positions.insert(window_key.to_string(), position);
+                }
+            }
+            match key {
+                "panel_open" => layout.panel_open = value.parse().unwrap_or(layout.panel_open),
+                "panel_width" => layout.panel_width = value.parse().unwrap_or(layout.panel_width),
+                "windows_docked" => layout.windows_docked = value.parse().unwrap_or(layout.windows_docked),
+                _ => {}
+            }
+        }
+        layout
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "panel_open = {}\npanel_width = {}\nwindows_docked = {}\n",
+            self.panel_open, self.panel_width.round(), self.windows_docked
+        );
+        for (window_key, position) in &self.window_positions {
+            text.push_str(&format!("window {} = {}, {}\n", window_key, position.x.round(), position.y.round()));
+        }
+        text
+    }
+}
+
+// Сквозной номер текстового окна: заголовки повторяются, а egui различает окна по id
+static NEXT_TEXT_WINDOW_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Структура для хранения состояния текстового окна; закрытые окна остаются в списке, пока их не больше MAX_CLOSED_WINDOWS
 struct TextWindow {
+    id: usize,
+    // Вид окна для сохранения положения: заголовки вроде "Логи: ЦДА" зависят от алгоритма, вид - нет
+    kind: String,
     title: String,
     content: String,
     is_open: bool,
+    table: Option<Table>,
+    monospace: bool,
+}
+
+impl TextWindow {
+    fn new(title: String, content: String) -> Self {
+        let kind = title.clone();
+        Self { id: NEXT_TEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed), kind, title, content, is_open: true, table: None, monospace: false }
+    }
+
+    // Вид окна, если заголовок меняется от запуска к запуску
+    fn kind(mut self, kind: &str) -> Self {
+        self.kind = kind.to_string();
+        self
+    }
+
+    // Окно с таблицей: содержимое - Markdown, доступен экспорт в файл
+    fn with_table(title: String, table: Table) -> Self {
+        Self { content: table.to_markdown(), table: Some(table), monospace: true, ..Self::new(title, String::new()) }
+    }
+
+    // Моноширинный шрифт, чтобы выровненные столбцы текста не разъезжались
+    fn monospace(mut self) -> Self {
+        self.monospace = true;
+        self
+    }
+}
+
+// Способ задания отрезка для алгоритмов прямых
+#[derive(Clone, Copy, PartialEq)]
+enum LineInput {
+    TwoPoints,
+    SlopeIntercept, // y = kx + b на промежутке по x
+    Vertical,       // x = c на промежутке по y
+}
+
+// Окно отсечения отрезка перед растеризацией
+#[derive(Clone, Copy, PartialEq)]
+enum ClipMode {
+    Off,
+    Rectangle, // прямоугольник, алгоритм Коэна-Сазерленда
+    Polygon,   // выпуклый многоугольник, алгоритм Сайруса-Бека
+}
+
+// Граница, внутри которой работает затравочная заливка
+#[derive(Clone, Copy, PartialEq)]
+enum FillBoundary {
+    Circle,  // окружность Брезенхема с параметрами из BresenhamCircle
+    Polygon, // замкнутая ломаная по вершинам многоугольника
+}
+
+// Готовые семейства параметрических кривых: выражения x(t), y(t) собираются из амплитуды и частот
+#[derive(Clone, Copy, PartialEq)]
+enum CurveFamily {
+    Custom,    // выражения вводятся вручную
+    Rose,      // роза r = A cos(k t), k = a / b
+    Lissajous, // фигура Лиссажу x = A sin(a t + pi/2), y = A sin(b t)
+    Cycloid,   // циклоида x = A (t - sin t), y = A (1 - cos t), a арок
+}
+
+impl CurveFamily {
+    fn label(self) -> &'static str {
+        match self {
+            CurveFamily::Custom => "Своя",
+            CurveFamily::Rose => "Роза",
+            CurveFamily::Lissajous => "Лиссажу",
+            CurveFamily::Cycloid => "Циклоида",
+        }
+    }
+}
+
+// Генератор псевдослучайных чисел SplitMix64: одно и то же зерно всегда даёт одну и ту же последовательность
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Равномерно на [from, to): старшие 24 бита - ровно столько помещается в мантиссу f32
+    fn range(&mut self, from: f32, to: f32) -> f32 {
+        from + (to - from) * (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Точка с целыми координатами внутри прямоугольника
+    fn point(&mut self, area: Rect) -> Pos2 {
+        Pos2::new(self.range(area.min.x, area.max.x).round(), self.range(area.min.y, area.max.y).round())
+    }
 }
 
 // Структура для хранения параметров алгоритмов
+#[derive(Clone, PartialEq)]
 struct AppParameters {
     p1: Pos2,
     p2: Pos2,
+    line_input: LineInput,
+    line_k: f32,
+    line_b: f32,
+    line_c: f32,
+    line_from: f32,
+    line_to: f32,
+    // Окно отсечения для алгоритмов отрезка: прямоугольник или выпуклый многоугольник
+    clip_mode: ClipMode,
+    clip_window: Rect,
+    clip_polygon: Vec<Pos2>,
     circle_center: Pos2,
     circle_radius: f32,
+    circle_octants: u8,
+    circle_through: [Pos2; 3],
+    // Кольцо с центром circle_center; concentric - наивное построение окружностями
+    ring_inner: f32,
+    ring_outer: f32,
+    sector_start: f32,
+    sector_end: f32,
+    ring_concentric: bool,
+    rrect_center: Pos2,
+    rrect_width: f32,
+    rrect_height: f32,
+    rrect_radius: f32,
+    ellipse_center: Pos2,
+    ellipse_rx: f32,
+    ellipse_ry: f32,
+    parabola_vertex: Pos2,
+    parabola_a: f32,
+    parabola_extent: f32,
+    hyperbola_center: Pos2,
+    hyperbola_a: f32,
+    hyperbola_b: f32,
+    hyperbola_both: bool,
     castle_points: Vec<Pos2>,
+    // Веса опорных точек рациональной кривой Безье (недостающие равны 1)
+    castle_weights: Vec<f32>,
+    castle_steps: usize,
+    castle_adaptive: bool,
+    // Замкнутая кривая: опорный список зацикливается
+    curve_closed: bool,
+    bezier_tolerance: f32,
+    spline_tension: f32,
+    // Концы кривой Эрмита и концы стрелок касательных (ручки)
+    hermite_p0: Pos2,
+    hermite_p1: Pos2,
+    hermite_h0: Pos2,
+    hermite_h1: Pos2,
+    hermite_steps: usize,
+    curve_pen: PenPattern,
+    brush_stamp: Stamp,
+    brush_spacing: u32,
+    polygon_points: Vec<Pos2>,
+    // Заливка многоугольника по центрам пикселей, без сглаживания краёв (для сравнения)
+    polygon_aliased: bool,
+    thick_points: Vec<Pos2>,
+    thick_width: f32,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    triangle_points: [Pos2; 3],
+    fill_boundary: FillBoundary,
+    fill_seed: Pos2,
+    fill_connectivity: Connectivity,
+    fill_pattern: FillPattern,
+    ssaa_factor: u32,
+    // Суперсэмплинг результата любого алгоритма: сторона квадрата подпикселей (1 - выключен)
+    supersample: u32,
+    custom_x: String,
+    custom_y: String,
+    custom_t_from: f32,
+    custom_t_to: f32,
+    custom_samples: usize,
+    function_expr: String,
+    // Концы трёхмерного отрезка (x, y, z) и способ проекции на холст
+    line3d_p1: [f32; 3],
+    line3d_p2: [f32; 3],
+    projection: Projection,
+    curve_family: CurveFamily,
+    family_amplitude: f32,
+    family_a: f32,
+    family_b: f32,
+}
+
+impl AppParameters {
+    // Ошибки параметров алгоритма: (поле, текст). Пока список не пуст, запуск недоступен
+    fn validate(&self, algorithm: Algorithm) -> Vec<(&'static str, String)> {
+        // Координаты по модулю не больше MAX_COORDINATE; NaN тоже не проходит сравнение
+        fn check_points(errors: &mut Vec<(&'static str, String)>, field: &'static str, points: &[Pos2]) {
+            if !points.iter().all(|p| p.x.abs() <= MAX_COORDINATE && p.y.abs() <= MAX_COORDINATE) {
+                errors.push((field, format!("Координаты по модулю должны быть не больше {}", MAX_COORDINATE)));
+            }
+        }
+        fn check_size(errors: &mut Vec<(&'static str, String)>, field: &'static str, name: &str, value: f32) {
+            if !(value > 0.0 && value <= MAX_COORDINATE) {
+                errors.push((field, format!("{}: допустимы значения больше 0 и не больше {}", name, MAX_COORDINATE)));
+            }
+        }
+        fn check_count(errors: &mut Vec<(&'static str, String)>, field: &'static str, points: &[Pos2], min: usize) {
+            if points.len() < min {
+                errors.push((field, format!("Нужно не меньше {} точек", min)));
+            }
+            check_points(errors, field, points);
+        }
+
+        let mut errors = Vec::new();
+        match algorithm {
+            algo if algo.is_line() => check_points(&mut errors, "line", &[self.p1, self.p2]),
+            algo if algo.is_control_curve() => {
+                let min = if algo == Algorithm::BSpline { 4 } else { 2 };
+                check_count(&mut errors, "castle_points", &self.castle_points, min);
+            }
+            Algorithm::BresenhamCircle | Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle | Algorithm::PieSlice => {
+                check_points(&mut errors, "circle_center", &[self.circle_center]);
+                check_size(&mut errors, "circle_radius", "Радиус", self.circle_radius);
+            }
+            Algorithm::Annulus => {
+                check_points(&mut errors, "circle_center", &[self.circle_center]);
+                check_size(&mut errors, "ring", "Внешний радиус", self.ring_outer.max(self.ring_inner));
+            }
+            Algorithm::ThreePointCircle => check_points(&mut errors, "circle_through", &self.circle_through),
+            Algorithm::RoundedRect => {
+                check_points(&mut errors, "rrect_center", &[self.rrect_center]);
+                check_size(&mut errors, "rrect_size", "Ширина", self.rrect_width);
+                check_size(&mut errors, "rrect_size", "Высота", self.rrect_height);
+            }
+            Algorithm::EllipseAA => check_points(&mut errors, "ellipse_center", &[self.ellipse_center]),
+            Algorithm::MidpointParabola => check_points(&mut errors, "parabola_vertex", &[self.parabola_vertex]),
+            Algorithm::Hyperbola => check_points(&mut errors, "hyperbola_center", &[self.hyperbola_center]),
+            Algorithm::Hermite => check_points(&mut errors, "hermite", &[self.hermite_p0, self.hermite_h0, self.hermite_p1, self.hermite_h1]),
+            Algorithm::PolygonFillAA => check_count(&mut errors, "polygon_points", &self.polygon_points, 3),
+            Algorithm::ThickPolyline => check_count(&mut errors, "thick_points", &self.thick_points, 2),
+            Algorithm::TriangleFillAA => check_points(&mut errors, "triangle_points", &self.triangle_points),
+            Algorithm::FloodFill | Algorithm::BoundaryFill => {
+                match self.fill_boundary {
+                    FillBoundary::Circle => {
+                        check_points(&mut errors, "circle_center", &[self.circle_center]);
+                        check_size(&mut errors, "circle_radius", "Радиус", self.circle_radius);
+                    }
+                    FillBoundary::Polygon => check_count(&mut errors, "polygon_points", &self.polygon_points, 3),
+                }
+                check_points(&mut errors, "fill_seed", &[self.fill_seed]);
+            }
+            Algorithm::Line3D if !self.line3d_p1.iter().chain(&self.line3d_p2).all(|c| c.abs() <= MAX_COORDINATE) => {
+                errors.push(("line3d", format!("Координаты по модулю должны быть не больше {}", MAX_COORDINATE)));
+            }
+            Algorithm::FunctionPlot => {
+                if let Err(e) = Expr::parse_in(&self.function_expr, "x") {
+                    errors.push(("function_expr", e));
+                }
+            }
+            Algorithm::CustomParametric => {
+                for (name, text) in [("x(t)", &self.custom_x), ("y(t)", &self.custom_y)] {
+                    if let Err(e) = Expr::parse(text) {
+                        errors.push(("custom_expr", format!("{}: {}", name, e)));
+                    }
+                }
+            }
+            _ => {}
+        }
+        errors
+    }
+
+    // Случайные допустимые параметры алгоритма по зерну: фигуры целиком помещаются в видимую часть
+    // холста view, число опорных точек и вершин сохраняется; остальные настройки не меняются
+    fn randomize(&mut self, algorithm: Algorithm, seed: u32, view: Rect) {
+        let mut rng = Rng(seed as u64);
+        let area = view.shrink2(view.size() * 0.1);
+        let extent = area.width().min(area.height());
+        let radius = |rng: &mut Rng, from: f32, to: f32| rng.range(from * extent, to * extent).round().max(1.0);
+        match algorithm {
+            algo if algo.is_line() => {
+                self.line_input = LineInput::TwoPoints;
+                self.p1 = rng.point(area);
+                self.p2 = rng.point(area);
+            }
+            algo if algo.is_control_curve() => {
+                self.castle_points = (0..self.castle_points.len()).map(|_| rng.point(area)).collect();
+                if algo == Algorithm::RationalBezier {
+                    self.castle_weights = (0..self.castle_points.len()).map(|_| (rng.range(0.2, 3.0) * 100.0).round() / 100.0).collect();
+                }
+            }
+            Algorithm::BresenhamCircle | Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle | Algorithm::PieSlice => {
+                self.circle_radius = radius(&mut rng, 0.05, 0.45);
+                self.circle_center = rng.point(area.shrink(self.circle_radius));
+                if algorithm == Algorithm::PieSlice {
+                    self.sector_start = rng.range(0.0, 360.0).round();
+                    self.sector_end = (self.sector_start + rng.range(20.0, 340.0)).round().rem_euclid(360.0);
+                }
+            }
+            Algorithm::Annulus => {
+                self.ring_outer = radius(&mut rng, 0.1, 0.45);
+                self.ring_inner = (self.ring_outer * rng.range(0.2, 0.8)).round();
+                self.circle_center = rng.point(area.shrink(self.ring_outer));
+            }
+            Algorithm::ThreePointCircle => self.circle_through = [rng.point(area), rng.point(area), rng.point(area)],
+            Algorithm::RoundedRect => {
+                self.rrect_width = radius(&mut rng, 0.2, 0.9);
+                self.rrect_height = radius(&mut rng, 0.2, 0.9);
+                self.rrect_radius = (self.rrect_width.min(self.rrect_height) * rng.range(0.0, 0.5)).round();
+                self.rrect_center = rng.point(area.shrink2(Vec2::new(self.rrect_width, self.rrect_height) / 2.0));
+            }
+            Algorithm::EllipseAA => {
+                self.ellipse_rx = radius(&mut rng, 0.05, 0.45);
+                self.ellipse_ry = radius(&mut rng, 0.05, 0.45);
+                self.ellipse_center = rng.point(area.shrink2(Vec2::new(self.ellipse_rx, self.ellipse_ry)));
+            }
+            Algorithm::MidpointParabola => {
+                self.parabola_vertex = rng.point(area);
+                self.parabola_a = (rng.range(-0.1, 0.1) * 1000.0).round() / 1000.0;
+                self.parabola_extent = radius(&mut rng, 0.1, 0.5);
+            }
+            Algorithm::Hyperbola => {
+                self.hyperbola_center = rng.point(area);
+                self.hyperbola_a = radius(&mut rng, 0.03, 0.25);
+                self.hyperbola_b = radius(&mut rng, 0.03, 0.25);
+            }
+            Algorithm::Hermite => {
+                self.hermite_p0 = rng.point(area);
+                self.hermite_p1 = rng.point(area);
+                self.hermite_h0 = rng.point(area);
+                self.hermite_h1 = rng.point(area);
+            }
+            Algorithm::PolygonFillAA => self.polygon_points = random_star_polygon(&mut rng, area, self.polygon_points.len()),
+            Algorithm::ThickPolyline => {
+                self.thick_points = (0..self.thick_points.len()).map(|_| rng.point(area)).collect();
+                self.thick_width = rng.range(1.0, 15.0).round();
+            }
+            Algorithm::TriangleFillAA => self.triangle_points = [rng.point(area), rng.point(area), rng.point(area)],
+            // Затравка - центр границы: он внутри и окружности, и звёздного многоугольника (см. random_star_polygon)
+            Algorithm::FloodFill | Algorithm::BoundaryFill => match self.fill_boundary {
+                FillBoundary::Circle => {
+                    self.circle_radius = radius(&mut rng, 0.05, 0.3);
+                    self.circle_center = rng.point(area.shrink(self.circle_radius));
+                    self.fill_seed = self.circle_center;
+                }
+                FillBoundary::Polygon => {
+                    self.polygon_points = random_star_polygon(&mut rng, area, self.polygon_points.len());
+                    self.fill_seed = area.center().round();
+                }
+            },
+            Algorithm::CustomParametric => {
+                self.curve_family = [CurveFamily::Rose, CurveFamily::Lissajous, CurveFamily::Cycloid][rng.next_u64() as usize % 3];
+                self.family_amplitude = radius(&mut rng, 0.1, 0.3);
+                self.family_a = rng.range(1.0, 8.0).floor();
+                self.family_b = rng.range(1.0, 8.0).floor();
+                self.apply_curve_family();
+            }
+            Algorithm::FunctionPlot => {
+                self.function_expr = format!("sin(x / {}) * {}", rng.range(3.0, 40.0).round(), radius(&mut rng, 0.05, 0.4));
+            }
+            Algorithm::Line3D => {
+                let mut coordinate = || (rng.range(-0.5, 0.5) * extent).round();
+                self.line3d_p1 = [coordinate(), coordinate(), coordinate()];
+                self.line3d_p2 = [coordinate(), coordinate(), coordinate()];
+            }
+            _ => {}
+        }
+    }
+
+    // Касательные кривой Эрмита в начале и в конце
+    fn hermite_tangents(&self) -> (Vec2, Vec2) {
+        (
+            (self.hermite_h0 - self.hermite_p0) * HERMITE_HANDLE_SCALE,
+            (self.hermite_h1 - self.hermite_p1) * HERMITE_HANDLE_SCALE,
+        )
+    }
+
+    // Подставляет в x(t), y(t) и промежуток t выражения выбранного семейства кривых
+    fn apply_curve_family(&mut self) {
+        let (amp, a, b) = (self.family_amplitude, self.family_a, self.family_b);
+        let (x, y, t_to) = match self.curve_family {
+            CurveFamily::Custom => return,
+            // При k = a / b кривая замыкается за b оборотов
+            CurveFamily::Rose => (
+                format!("{} * cos({} / {} * t) * cos(t)", amp, a, b),
+                format!("{} * cos({} / {} * t) * sin(t)", amp, a, b),
+                b.abs().max(1.0).round() * std::f32::consts::TAU,
+            ),
+            CurveFamily::Lissajous => (
+                format!("{} * sin({} * t + pi / 2)", amp, a),
+                format!("{} * sin({} * t)", amp, b),
+                std::f32::consts::TAU,
+            ),
+            // Сдвиг на A pi a ставит середину кривой в начало координат
+            CurveFamily::Cycloid => {
+                let arches = a.abs().max(1.0).round();
+                (
+                    format!("{} * (t - sin(t)) - {} * pi * {}", amp, amp, arches),
+                    format!("{} * (1 - cos(t))", amp),
+                    arches * std::f32::consts::TAU,
+                )
+            }
+        };
+        self.custom_x = x;
+        self.custom_y = y;
+        self.custom_t_from = 0.0;
+        self.custom_t_to = t_to;
+    }
+
+    // Опорные точки и веса кривой Безье. У замкнутой кривой в конец повторяется P0: кривая
+    // возвращается в начало, но гладкой в P0 будет, только если P[n-1], P0 и P1 лежат на одной прямой
+    fn bezier_control(&self) -> (Vec<Pos2>, Vec<f32>) {
+        let mut points = self.castle_points.clone();
+        let mut weights = self.castle_weights.clone();
+        weights.resize(points.len(), 1.0);
+        if self.curve_closed && !points.is_empty() {
+            points.push(points[0]);
+            weights.push(weights[0]);
+        }
+        (points, weights)
+    }
+
+    // Опорная ломаная с учётом замыкания (для оценки длины кривой)
+    fn control_polygon(&self) -> Vec<Pos2> {
+        let mut points = self.castle_points.clone();
+        if self.curve_closed && !points.is_empty() {
+            points.push(points[0]);
+        }
+        points
+    }
+
+    // Те же фигуры на растре в factor раз мельче: центр пикселя x переходит в factor * x + (factor - 1) / 2,
+    // так что factor x factor подпикселей каждого исходного пикселя получают целые координаты
+    fn supersampled(&self, factor: u32) -> Self {
+        let k = factor as f32;
+        let mut params = self.clone();
+        params.supersample = 1;
+        for p in params.points_mut() {
+            *p = supersampled_point(*p, factor);
+        }
+        for v in params.lengths_mut() {
+            *v *= k;
+        }
+        // y = a x^2 при растяжении обеих осей в k раз переходит в y = (a / k) x^2
+        params.parabola_a /= k;
+        // Кривые длиннее в k раз - шагов нужно во столько же раз больше, иначе появятся разрывы
+        params.castle_steps = (params.castle_steps * factor as usize).min(algo::MAX_CURVE_STEPS);
+        params.hermite_steps = (params.hermite_steps * factor as usize).min(algo::MAX_CURVE_STEPS);
+        params
+    }
+
+    // Все точки фигур, включая окно отсечения: преобразования координат применяются к ним одинаково
+    fn points_mut(&mut self) -> impl Iterator<Item = &mut Pos2> {
+        let single = [
+            &mut self.p1, &mut self.p2, &mut self.circle_center, &mut self.ellipse_center, &mut self.rrect_center,
+            &mut self.parabola_vertex, &mut self.hyperbola_center, &mut self.fill_seed,
+            &mut self.hermite_p0, &mut self.hermite_p1, &mut self.hermite_h0, &mut self.hermite_h1,
+            &mut self.clip_window.min, &mut self.clip_window.max,
+        ];
+        let lists = [
+            &mut self.castle_points[..], &mut self.polygon_points[..], &mut self.thick_points[..],
+            &mut self.triangle_points[..], &mut self.circle_through[..], &mut self.clip_polygon[..],
+        ];
+        single.into_iter().chain(lists.into_iter().flatten())
+    }
+
+    // Линейные размеры фигур (радиусы, полуоси, толщина)
+    fn lengths_mut(&mut self) -> [&mut f32; 12] {
+        [
+            &mut self.circle_radius, &mut self.ring_inner, &mut self.ring_outer,
+            &mut self.rrect_width, &mut self.rrect_height, &mut self.rrect_radius, &mut self.thick_width,
+            &mut self.ellipse_rx, &mut self.ellipse_ry, &mut self.parabola_extent, &mut self.hyperbola_a, &mut self.hyperbola_b,
+        ]
+    }
+
+    // Концы отрезка по активному способу задания; при вводе уравнением P1/P2 служат лишь для отображения
+    fn line_endpoints(&self) -> (Pos2, Pos2) {
+        match self.line_input {
+            LineInput::TwoPoints => (self.p1, self.p2),
+            LineInput::SlopeIntercept => algo::line_from_slope_intercept(self.line_k, self.line_b, self.line_from, self.line_to),
+            LineInput::Vertical => algo::vertical_line(self.line_c, self.line_from, self.line_to),
+        }
+    }
+
+    // Округляет все координаты и размеры фигур до целых (коэффициент наклона k не трогаем)
+    fn snap_to_integers(&mut self) {
+        for p in self.points_mut() {
+            *p = p.round();
+        }
+        for v in self.lengths_mut() {
+            *v = v.round();
+        }
+        for v in [&mut self.line_b, &mut self.line_c, &mut self.line_from, &mut self.line_to] {
+            *v = v.round();
+        }
+    }
 }
 
 impl Default for AppParameters {
@@ -27,14 +760,98 @@ impl Default for AppParameters {
         Self {
             p1: Pos2::new(-50.0, -10.0),
             p2: Pos2::new(50.0, 20.0),
+            line_input: LineInput::TwoPoints,
+            line_k: 0.3,
+            line_b: 5.0,
+            line_c: 0.0,
+            line_from: -50.0,
+            line_to: 50.0,
+            clip_mode: ClipMode::Off,
+            clip_window: Rect::from_min_max(Pos2::new(-40.0, -30.0), Pos2::new(40.0, 30.0)),
+            clip_polygon: vec![
+                Pos2::new(-30.0, -40.0),
+                Pos2::new(40.0, -20.0),
+                Pos2::new(30.0, 35.0),
+                Pos2::new(-40.0, 20.0),
+            ],
             circle_center: Pos2::new(0.0, 0.0),
             circle_radius: 60.0,
+            circle_octants: algo::ALL_OCTANTS,
+            ring_inner: 40.0,
+            ring_outer: 60.0,
+            sector_start: 30.0,
+            sector_end: 120.0,
+            ring_concentric: false,
+            circle_through: [Pos2::new(-50.0, 0.0), Pos2::new(10.0, 45.0), Pos2::new(40.0, -30.0)],
+            rrect_center: Pos2::new(0.0, 0.0),
+            rrect_width: 120.0,
+            rrect_height: 80.0,
+            rrect_radius: 20.0,
+            ellipse_center: Pos2::new(0.0, 0.0),
+            ellipse_rx: 80.0,
+            ellipse_ry: 45.0,
+            parabola_vertex: Pos2::new(0.0, -60.0),
+            parabola_a: 0.02,
+            parabola_extent: 80.0,
+            hyperbola_center: Pos2::new(0.0, 0.0),
+            hyperbola_a: 30.0,
+            hyperbola_b: 20.0,
+            hyperbola_both: true,
             castle_points: vec![
                 Pos2::new(-80.0, -50.0),
                 Pos2::new(-30.0, 80.0),
                 Pos2::new(30.0, -80.0),
                 Pos2::new(80.0, 50.0),
             ],
+            castle_weights: Vec::new(),
+            castle_steps: 1000,
+            castle_adaptive: false,
+            curve_closed: false,
+            bezier_tolerance: 0.5,
+            spline_tension: 0.0,
+            hermite_p0: Pos2::new(-70.0, -30.0),
+            hermite_p1: Pos2::new(70.0, -30.0),
+            hermite_h0: Pos2::new(-40.0, 50.0),
+            hermite_h1: Pos2::new(100.0, 50.0),
+            hermite_steps: 200,
+            curve_pen: PenPattern::Solid,
+            brush_stamp: Stamp::cross3(),
+            brush_spacing: 1,
+            polygon_points: vec![
+                Pos2::new(-60.0, -40.0),
+                Pos2::new(70.0, -20.0),
+                Pos2::new(20.0, 60.0),
+            ],
+            polygon_aliased: false,
+            thick_points: vec![
+                Pos2::new(-90.0, -40.0),
+                Pos2::new(-30.0, 40.0),
+                Pos2::new(20.0, -30.0),
+                Pos2::new(90.0, 20.0),
+            ],
+            thick_width: 9.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            triangle_points: [Pos2::new(-60.0, -40.0), Pos2::new(70.0, -20.0), Pos2::new(20.0, 60.0)],
+            fill_boundary: FillBoundary::Circle,
+            fill_seed: Pos2::new(0.0, 0.0),
+            fill_connectivity: Connectivity::Four,
+            fill_pattern: FillPattern::solid(),
+            ssaa_factor: 4,
+            supersample: 1,
+            custom_x: "60 * cos(3 * t)".to_string(),
+            custom_y: "60 * sin(2 * t)".to_string(),
+            custom_t_from: 0.0,
+            custom_t_to: std::f32::consts::TAU,
+            custom_samples: 500,
+            function_expr: "sin(x / 10) * 30".to_string(),
+            line3d_p1: [-40.0, -30.0, -50.0],
+            line3d_p2: [60.0, 40.0, 70.0],
+            projection: Projection::Isometric,
+            curve_family: CurveFamily::Custom,
+            family_amplitude: 60.0,
+            family_a: 3.0,
+            family_b: 2.0,
         }
     }
 }
@@ -44,6 +861,248 @@ enum RenderResult {
     None,
     Pixels(Vec<Pos2>),
     Antialiased(Vec<AntialiasedPixel>),
+    Rich(Vec<PixelRecord>),
+}
+
+impl RenderResult {
+    fn len(&self) -> usize {
+        match self {
+            RenderResult::Pixels(pixels) => pixels.len(),
+            RenderResult::Antialiased(pixels) => pixels.len(),
+            RenderResult::Rich(records) => records.len(),
+            RenderResult::None => 0,
+        }
+    }
+
+    // Единый поток записей: простые варианты преобразуются в PixelRecord на лету
+    fn records(&self) -> Box<dyn Iterator<Item = PixelRecord> + '_> {
+        match self {
+            RenderResult::Pixels(pixels) => Box::new(pixels.iter().enumerate().map(|(i, &p)| PixelRecord::from_pos(i, p))),
+            RenderResult::Antialiased(pixels) => Box::new(pixels.iter().enumerate().map(|(i, &p)| PixelRecord::from_antialiased(i, p))),
+            RenderResult::Rich(records) => Box::new(records.iter().copied()),
+            RenderResult::None => Box::new(std::iter::empty()),
+        }
+    }
+
+    // Преобразование в расширенный вариант
+    fn to_rich(&self) -> RenderResult {
+        RenderResult::Rich(self.records().collect())
+    }
+}
+
+// Цвета, выбранные перед запуском: пиксели результата и идеальная фигура
+#[derive(Clone, Copy)]
+struct RunColors {
+    pixel: Color32,
+    ideal: Color32,
+}
+
+impl Default for RunColors {
+    fn default() -> Self {
+        Self { pixel: Color32::BLACK, ideal: Color32::RED }
+    }
+}
+
+// Прошлый результат со снимком параметров, на которых он получен (для "луковой кожи")
+struct PastResult {
+    algorithm: Algorithm,
+    params: AppParameters,
+    result: RenderResult,
+    colors: RunColors,
+}
+
+// Результат одного участника "гонки" алгоритмов отрезка
+struct RaceRow {
+    algorithm: Algorithm,
+    result: RenderResult,
+    time: std::time::Duration,
+    only_here: usize,
+    only_baseline: usize,
+    color: Color32,
+    visible: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RaceSort {
+    Name,
+    Pixels,
+    Time,
+    Diff,
+}
+
+// Состояние окна "Все алгоритмы"
+struct Race {
+    rows: Vec<RaceRow>,
+    sort: RaceSort,
+    ascending: bool,
+    is_open: bool,
+}
+
+// Список алгоритмов по группам (группы разделяются чертой); в этом же порядке их перебирают клавиши
+const ALGORITHM_MENU: &[&[(Algorithm, &str)]] = &[
+    &[
+        (Algorithm::StepByStep, "StepByStep"),
+        (Algorithm::DDA, "DDA"),
+        (Algorithm::BresenhamLine, "BresenhamLine"),
+        (Algorithm::MidpointLine, "MidpointLine"),
+        (Algorithm::DiamondExit, "Diamond exit (GPU)"),
+        (Algorithm::SymmetricBresenham, "Bresenham (двусторонний)"),
+        (Algorithm::RunSliceLine, "Bresenham (по сериям)"),
+        (Algorithm::DoubleStepLine, "Ву (двухшаговый)"),
+        (Algorithm::BrushLine, "BrushLine"),
+    ],
+    &[
+        (Algorithm::StepByStepAA, "StepByStep (AA)"),
+        (Algorithm::DdaAA, "DDA (AA)"),
+        (Algorithm::BresenhamAA, "Bresenham (AA, упрощённый)"),
+        (Algorithm::GuptaSproull, "Gupta-Sproull"),
+        (Algorithm::WuLine, "WuLine"),
+        (Algorithm::SsaaLine, "SSAA (эталон)"),
+    ],
+    &[
+        (Algorithm::BresenhamCircle, "BresenhamCircle"),
+        (Algorithm::WuCircle, "WuCircle (AA)"),
+        (Algorithm::RotationCircle, "Окружность (поворот)"),
+        (Algorithm::SqrtCircle, "Окружность (y = sqrt)"),
+        (Algorithm::PolarCircle, "Окружность (полярная)"),
+        (Algorithm::ThreePointCircle, "Окружность по 3 точкам"),
+        (Algorithm::Annulus, "Кольцо"),
+        (Algorithm::RoundedRect, "Скруглённый прямоугольник"),
+        (Algorithm::PieSlice, "Сектор"),
+        (Algorithm::ThickPolyline, "Толстая ломаная"),
+        (Algorithm::EllipseAA, "Ellipse (AA)"),
+        (Algorithm::MidpointParabola, "MidpointParabola"),
+        (Algorithm::Hyperbola, "Hyperbola"),
+        (Algorithm::CastlePitway, "CastlePitway"),
+        (Algorithm::BezierSubdivision, "Bezier (разбиение)"),
+        (Algorithm::BSpline, "B-сплайн"),
+        (Algorithm::CatmullRom, "Catmull-Rom"),
+        (Algorithm::Hermite, "Hermite"),
+        (Algorithm::RationalBezier, "Bezier (рациональная)"),
+        (Algorithm::CustomParametric, "x(t), y(t)"),
+        (Algorithm::FunctionPlot, "y = f(x)"),
+        (Algorithm::Line3D, "Bresenham 3D"),
+    ],
+    &[
+        (Algorithm::PolygonFillAA, "PolygonFill (AA)"),
+        (Algorithm::TriangleFillAA, "Triangle (функции рёбер)"),
+        (Algorithm::FloodFill, "FloodFill"),
+        (Algorithm::BoundaryFill, "BoundaryFill"),
+    ],
+];
+
+// Непрозрачность прошлых результатов, от самого свежего к старым
+const ONION_OPACITY: [f32; 4] = [0.40, 0.25, 0.15, 0.10];
+
+// Палитра для раскраски пикселей по метке
+const TAG_COLORS: [Color32; 8] = [
+    Color32::from_rgb(230, 25, 75),
+    Color32::from_rgb(60, 180, 75),
+    Color32::from_rgb(0, 130, 200),
+    Color32::from_rgb(245, 130, 48),
+    Color32::from_rgb(145, 30, 180),
+    Color32::from_rgb(70, 160, 160),
+    Color32::from_rgb(240, 50, 230),
+    Color32::from_rgb(128, 128, 0),
+];
+
+// Снимок для истории отмены: выбранный алгоритм и его параметры (вид холста в историю не входит)
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    algorithm: Algorithm,
+    params: AppParameters,
+}
+
+// Наложения на холсте, переключаемые одной клавишей
+#[derive(Clone, Copy)]
+enum Overlay {
+    Grid,
+    Axes,
+    AxisLabels,
+    Ideal,
+    Markers,
+    ControlPolygon,
+    Checkerboard,
+    Minimap,
+    SubPixel,
+    Crosshair,
+}
+
+impl Overlay {
+    const ALL: [Overlay; 10] = [
+        Overlay::Grid,
+        Overlay::Axes,
+        Overlay::AxisLabels,
+        Overlay::Ideal,
+        Overlay::Markers,
+        Overlay::ControlPolygon,
+        Overlay::Checkerboard,
+        Overlay::Minimap,
+        Overlay::SubPixel,
+        Overlay::Crosshair,
+    ];
+
+    // Клавиша G занята окном "перейти к точке", поэтому сетка - на H
+    fn key(self) -> egui::Key {
+        match self {
+            Overlay::Grid => egui::Key::H,
+            Overlay::Axes => egui::Key::A,
+            Overlay::AxisLabels => egui::Key::X,
+            Overlay::Ideal => egui::Key::I,
+            Overlay::Markers => egui::Key::E,
+            Overlay::ControlPolygon => egui::Key::C,
+            Overlay::Checkerboard => egui::Key::K,
+            Overlay::Minimap => egui::Key::M,
+            Overlay::SubPixel => egui::Key::P,
+            Overlay::Crosshair => egui::Key::R,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Overlay::Grid => "Сетка",
+            Overlay::Axes => "Оси",
+            Overlay::AxisLabels => "Подписи осей",
+            Overlay::Ideal => "Идеальная фигура",
+            Overlay::Markers => "Маркеры концов и опорных точек",
+            Overlay::ControlPolygon => "Опорная ломаная",
+            Overlay::Checkerboard => "Шахматный фон (прозрачность)",
+            Overlay::Minimap => "Миникарта",
+            Overlay::SubPixel => "Центры пикселей и точки идеала",
+            Overlay::Crosshair => "Перекрестие у курсора",
+        }
+    }
+}
+
+// Окно "перейти к точке": ввод логической координаты с клавиатуры
+struct GotoPopup {
+    text: String,
+    place_point: bool,
+    error: Option<String>,
+    request_focus: bool,
+}
+
+// Расчёт, выполняющийся в фоновом потоке
+struct PendingRun {
+    algorithm: Algorithm,
+    // Снимок параметров, с которыми идёт расчёт
+    params: AppParameters,
+    colors: RunColors,
+    show_log: bool,
+    table_log: bool,
+    // Перезапуск при перетаскивании: его результат не уходит в "луковую кожу"
+    drag_rerun: bool,
+    started: std::time::Instant,
+    progress: Arc<AtomicU32>,
+    handle: std::thread::JoinHandle<RunOutput>,
+}
+
+// Что возвращает фоновый расчёт
+struct RunOutput {
+    result: RenderResult,
+    elapsed: std::time::Duration,
+    log: String,
+    iterations: Option<usize>,
 }
 
 // Основная структура приложения
@@ -51,28 +1110,233 @@ pub struct GraphicsLabApp {
     selected_algorithm: Algorithm,
     params: AppParameters,
     log_enabled: bool,
+    log_as_table: bool,
+    // Фильтр лога по координатам пикселей (включительно)
+    log_filter_enabled: bool,
+    log_filter_min: (i32, i32),
+    log_filter_max: (i32, i32),
+    fraction_mode: FractionMode,
+    rounding_mode: RoundingMode,
 
-    // Состояние холста
-    pan: Vec2,
-    zoom: f32,
+    // Пошаговый просмотр: отображаются только первые playback_step пикселей
+    playback_enabled: bool,
+    playback_step: usize,
+
+    // Прореживание отрисовки при мелком масштабе
+    downsample_enabled: bool,
+    // Рамки вокруг закрашенных пикселей, когда логический пиксель не меньше pixel_border_zoom экранных точек
+    pixel_borders: bool,
+    pixel_border_zoom: f32,
+    pixel_border_color: Color32,
+    color_by_tag: bool,
+    // Гамма-коррекция при переводе интенсивности в непрозрачность
+    gamma_correct: bool,
+    gamma: f32,
+    show_dominant_axis: bool,
+    show_circle_coverage: bool,
+    checkerboard_enabled: bool,
+    show_grid: bool,
+    show_axes: bool,
+    show_axis_labels: bool,
+    show_ideal: bool,
+    show_markers: bool,
+    show_control_polygon: bool,
+    show_minimap: bool,
+    show_subpixel: bool,
+    show_crosshair: bool,
+    color_by_value: bool,
+    // Разность правила ромба с Брезенхемом для тех же концов: (только ромб, только Брезенхем)
+    diamond_diff: bool,
+    diamond_delta: Option<PixelDelta>,
+    mirror_quadrants: bool,
+    integer_input: bool,
+    // Привязка перетаскиваемых и поставленных на холсте точек к целым логическим координатам
+    snap_to_grid: bool,
+    last_draw_time: std::time::Duration,
+
+    // Состояние холста: два вида для разделённого экрана; view - вид под курсором,
+    // к нему относятся клавиши, масштаб на панели и строка состояния
+    viewports: [Viewport; 2],
+    view: usize,
+    split_view: bool,
+    keep_aspect: bool,
+    // Пределы масштаба и плавное масштабирование колесом и клавишами
+    zoom_min: f32,
+    zoom_max: f32,
+    smooth_zoom: bool,
+
+    // Перетаскивание точек на холсте и история изменений параметров
+    canvas_drag: CanvasDrag,
+    // Жест двумя пальцами: время его начала и текущий центр на экране
+    touch_center: Option<(f64, Pos2)>,
+    active_point: usize,
+    hovered_point: Option<usize>,
+    // Строка списка опорных точек под мышью и строка, перетаскиваемая за "☰"
+    hovered_row: Option<usize>,
+    dragged_row: Option<usize>,
+    // Перезапуск алгоритма (без лога) на каждое смещение перетаскиваемой точки
+    rerun_on_drag: bool,
+    // Точку сдвинули, пока шёл расчёт: перезапуск после его завершения
+    drag_rerun_queued: bool,
+    goto: Option<GotoPopup>,
+    history: History<Snapshot>,
+    // Ошибки проверки параметров выбранного алгоритма на текущем кадре
+    param_errors: Vec<(&'static str, String)>,
+    // Зерно последних случайных параметров
+    random_seed: u32,
+    // Состояние после последнего записанного в историю действия
+    history_base: Snapshot,
+
+    // Назначения клавиш и окно их настройки; rebinding - действие, ждущее нажатия новой клавиши
+    shortcuts: Shortcuts,
+    shortcuts_open: bool,
+    rebinding: Option<Action>,
+    shortcuts_message: Option<String>,
+
+    // Текущая раскладка и последняя записанная в LAYOUT_PATH
+    layout: Layout,
+    saved_layout: Layout,
+
+    // Прямоугольное выделение в логических координатах
+    selection_mode: bool,
+    selection: Option<Rect>,
+
+    // Линейка: измеряемый отрезок (начало, конец) в логических координатах
+    ruler_mode: bool,
+    ruler: Option<(Pos2, Pos2)>,
 
     // Результаты и окна
     last_run_algorithm: Option<Algorithm>,
+    // Параметры, с которыми получен текущий результат: метрики не должны зависеть от последующих правок
+    last_run_params: AppParameters,
     render_result: RenderResult,
+    // Цвета для следующего запуска и цвета, с которыми получен текущий результат
+    run_colors: RunColors,
+    result_colors: RunColors,
+    pending_run: Option<PendingRun>,
+    // Эталонное покрытие для окружности и средняя ошибка результата относительно него
+    circle_coverage: Option<(Vec<AntialiasedPixel>, f32)>,
+    // Отражения текущего результата во все четверти (без исходных пикселей) и число уникальных пикселей
+    // вместе с ними; считаются один раз после запуска, пока включено отражение
+    mirrored: Option<(RenderResult, usize)>,
+    // Запомненный результат и разность с ним текущего: (добавленные, исчезнувшие пиксели)
+    stashed_result: Option<Vec<PixelRecord>>,
+    stash_delta: Option<PixelDelta>,
+    onion_enabled: bool,
+    onion_depth: usize,
+    onion_skins: std::collections::VecDeque<PastResult>,
+    race: Option<Race>,
+    race_include_aa: bool,
+    pixel_table_auto: bool,
+    last_log: String,
+    last_run_time: std::time::Duration,
+    last_iterations: Option<usize>,
+    report_format: ReportFormat,
+    pixel_log_path: String,
     text_windows: Vec<TextWindow>,
+    // Разложить открытые окна плиткой на следующем кадре
+    tile_windows: bool,
+    // Вкладки нижней панели: id выбранного окна и самого нового из уже показанных
+    active_tab: Option<usize>,
+    newest_tab: Option<usize>,
 }
 
 impl Default for GraphicsLabApp {
     fn default() -> Self {
+        let layout = Layout::load(LAYOUT_PATH);
         Self {
             selected_algorithm: Algorithm::BresenhamLine,
             params: AppParameters::default(),
             log_enabled: false,
-            pan: Vec2::ZERO,
-            zoom: 2.0,
+            log_as_table: false,
+            log_filter_enabled: false,
+            log_filter_min: (0, 0),
+            log_filter_max: (100, 100),
+            fraction_mode: FractionMode::Floor,
+            rounding_mode: RoundingMode::HalfUp,
+            playback_enabled: false,
+            playback_step: 0,
+            downsample_enabled: true,
+            pixel_borders: true,
+            pixel_border_zoom: 8.0,
+            pixel_border_color: Color32::from_gray(160),
+            color_by_tag: false,
+            gamma_correct: false,
+            gamma: 2.2,
+            show_dominant_axis: false,
+            show_circle_coverage: false,
+            checkerboard_enabled: false,
+            show_grid: true,
+            show_axes: true,
+            show_axis_labels: true,
+            show_ideal: true,
+            show_markers: true,
+            show_control_polygon: false,
+            show_minimap: true,
+            show_subpixel: false,
+            show_crosshair: false,
+            color_by_value: false,
+            diamond_diff: true,
+            diamond_delta: None,
+            mirror_quadrants: false,
+            integer_input: false,
+            snap_to_grid: false,
+            last_draw_time: std::time::Duration::ZERO,
+            viewports: [Viewport::default(); 2],
+            view: 0,
+            split_view: false,
+            keep_aspect: true,
+            zoom_min: 0.1,
+            zoom_max: 1000.0,
+            smooth_zoom: true,
+            canvas_drag: CanvasDrag::None,
+            touch_center: None,
+            active_point: 0,
+            hovered_point: None,
+            hovered_row: None,
+            dragged_row: None,
+            rerun_on_drag: false,
+            drag_rerun_queued: false,
+            goto: None,
+            history: History::new(),
+            param_errors: Vec::new(),
+            random_seed: 1,
+            history_base: Snapshot { algorithm: Algorithm::BresenhamLine, params: AppParameters::default() },
+            shortcuts: Shortcuts::load(shortcuts::SHORTCUTS_PATH),
+            shortcuts_open: false,
+            rebinding: None,
+            shortcuts_message: None,
+            layout: layout.clone(),
+            saved_layout: layout,
+            selection_mode: false,
+            ruler_mode: false,
+            ruler: None,
+            selection: None,
             last_run_algorithm: None,
+            last_run_params: AppParameters::default(),
             render_result: RenderResult::None,
+            run_colors: RunColors::default(),
+            result_colors: RunColors::default(),
+            pending_run: None,
+            circle_coverage: None,
+            mirrored: None,
+            stashed_result: None,
+            stash_delta: None,
+            onion_enabled: true,
+            onion_depth: 3,
+            onion_skins: std::collections::VecDeque::new(),
+            race: None,
+            race_include_aa: false,
+            pixel_table_auto: false,
+            last_log: String::new(),
+            last_run_time: std::time::Duration::ZERO,
+            last_iterations: None,
+            report_format: ReportFormat::Markdown,
+            pixel_log_path: "pixels.json".to_string(),
             text_windows: Vec::new(),
+            tile_windows: false,
+            active_tab: None,
+            newest_tab: None,
         }
     }
 }
@@ -80,9 +1344,39 @@ impl Default for GraphicsLabApp {
 // Реализация логики приложения
 impl eframe::App for GraphicsLabApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // --- Панель управления (без изменений) ---
-        egui::SidePanel::left("control_panel").show(ctx, |ui| {
-            ui.heading("Управление");
+        self.poll_pending_run(ctx);
+        let (dt, smooth) = (ctx.input(|i| i.stable_dt).min(0.1), self.smooth_zoom);
+        for view in &mut self.viewports {
+            if view.animate(dt, smooth) {
+                ctx.request_repaint();
+            }
+        }
+        self.update_circle_coverage();
+        self.update_mirrored();
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("Окна", |ui| self.windows_menu_ui(ui));
+            });
+        });
+
+        self.hovered_row = None;
+        self.param_errors = self.params.validate(self.selected_algorithm);
+        // --- Панель управления: сворачивается до узкой полосы со стрелкой ---
+        if !self.layout.panel_open {
+            egui::SidePanel::left("control_panel_collapsed").resizable(false).exact_width(24.0).show(ctx, |ui| {
+                if ui.small_button("▶").on_hover_text("Развернуть панель управления").clicked() {
+                    self.layout.panel_open = true;
+                }
+            });
+        }
+        let panel = egui::SidePanel::left("control_panel").resizable(true).default_width(self.layout.panel_width).show_animated(ctx, self.layout.panel_open, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Управление");
+                if ui.small_button("◀").on_hover_text("Свернуть панель управления").clicked() {
+                    self.layout.panel_open = false;
+                }
+            });
             ui.separator();
 
             // ... (весь код панели управления остается здесь) ...
@@ -90,34 +1384,185 @@ impl eframe::App for GraphicsLabApp {
                 .selected_text(format!("{}", self.selected_algorithm))
                 .show_ui(ui, |ui| {
                     ui.style_mut().wrap = Some(false); // Предотвращаем перенос строк в комбобоксе
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::StepByStep, "StepByStep");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::DDA, "DDA");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::BresenhamLine, "BresenhamLine");
-                    ui.separator();
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::StepByStepAA, "StepByStep (AA)");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::DdaAA, "DDA (AA)");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::BresenhamAA, "Bresenham (Gupta-Sproull)");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::WuLine, "WuLine");
-                    ui.separator();
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::BresenhamCircle, "BresenhamCircle");
-                    ui.selectable_value(&mut self.selected_algorithm, Algorithm::CastlePitway, "CastlePitway");
+                    for (i, group) in ALGORITHM_MENU.iter().enumerate() {
+                        if i > 0 {
+                            ui.separator();
+                        }
+                        for &(algorithm, label) in *group {
+                            ui.selectable_value(&mut self.selected_algorithm, algorithm, label);
+                        }
+                    }
                 });
 
             ui.separator();
+            if ui
+                .checkbox(&mut self.integer_input, "Целочисленный ввод")
+                .on_hover_text("Все координаты и размеры округляются до целых: введённые вручную - когда правка закончена, точки на холсте - сразу, как при привязке к сетке")
+                .changed()
+                && self.integer_input
+            {
+                self.params.snap_to_integers();
+            }
+            ui.checkbox(&mut self.snap_to_grid, "Привязка к сетке")
+                .on_hover_text("Точки, перетащенные или поставленные на холсте, попадают в центры пикселей");
+            ui.checkbox(&mut self.rerun_on_drag, "Пересчитывать при перетаскивании")
+                .on_hover_text("Алгоритм запускается заново (без лога) при каждом смещении точки на холсте");
             self.show_parameters_ui(ui);
             ui.separator();
 
-            ui.checkbox(&mut self.log_enabled, "Сохранять вычисления");
-            if ui.button("Запуск").clicked() {
-                self.run_algorithm();
-            }
-            if ui.button("Справка").clicked() {
-                self.show_help();
-            }
-        });
+            egui::ComboBox::from_label("Суперсэмплинг")
+                .selected_text(match self.params.supersample {
+                    1 => "выкл".to_string(),
+                    factor => format!("{}x{}", factor, factor),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.params.supersample, 1, "выкл");
+                    for factor in [2, 4, 8] {
+                        ui.selectable_value(&mut self.params.supersample, factor, format!("{}x{}", factor, factor));
+                    }
+                })
+                .response
+                .on_hover_text("Расчёт на растре в N раз мельче и усреднение подпикселей - эталонное сглаживание для любого алгоритма");
+
+            egui::ComboBox::from_label("Округление")
+                .selected_text(format!("{}", self.rounding_mode))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.rounding_mode, RoundingMode::HalfUp, "HalfUp");
+                    ui.selectable_value(&mut self.rounding_mode, RoundingMode::HalfToEven, "HalfToEven");
+                    ui.selectable_value(&mut self.rounding_mode, RoundingMode::Floor, "Floor");
+                });
+
+            ui.checkbox(&mut self.log_enabled, "Сохранять вычисления");
+            if self.log_enabled {
+                ui.checkbox(&mut self.log_as_table, "Лог таблицей");
+                self.show_log_filter_ui(ui);
+            }
+            self.show_playback_ui(ui);
+            self.show_zoom_ui(ui);
+            self.show_onion_ui(ui);
+            self.show_selection_ui(ui);
+            self.show_ruler_ui(ui);
+            egui::CollapsingHeader::new("Вид").default_open(true).show(ui, |ui| self.show_view_ui(ui));
+            if ui.checkbox(&mut self.mirror_quadrants, "Отразить во все четверти").changed() {
+                self.mirrored = None;
+                self.update_mirrored();
+            }
+            if let (true, Some((_, unique))) = (self.mirror_quadrants, &self.mirrored) {
+                ui.label(format!("Пикселей с отражениями: {}", unique));
+            }
+            if ui.button("Подробный результат").clicked() {
+                self.render_result = self.render_result.to_rich();
+            }
+            self.show_stash_ui(ui);
+            ui.horizontal(|ui| {
+                ui.label("Цвет пикселей:");
+                egui::color_picker::color_edit_button_srgba(ui, &mut self.run_colors.pixel, egui::color_picker::Alpha::Opaque);
+                ui.label("идеальной фигуры:");
+                egui::color_picker::color_edit_button_srgba(ui, &mut self.run_colors.ideal, egui::color_picker::Alpha::Opaque);
+            })
+            .response
+            .on_hover_text("Применяются к следующему запуску; прошлые результаты сохраняют свои цвета");
+            if ui
+                .add_enabled(self.pending_run.is_none() && self.param_errors.is_empty(), egui::Button::new("Запуск"))
+                .on_disabled_hover_text("Исправьте параметры, отмеченные предупреждениями")
+                .clicked()
+            {
+                self.run_algorithm();
+            }
+            self.show_progress_ui(ui);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.history.len() > 0, egui::Button::new(format!("Отменить ({})", self.history.len())))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui.add_enabled(self.history.redo_len() > 0, egui::Button::new(format!("Повторить ({})", self.history.redo_len())))
+                    .on_hover_text("Ctrl+Y, Ctrl+Shift+Z")
+                    .clicked()
+                {
+                    self.redo();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Случайно").on_hover_text("Случайные параметры выбранного алгоритма с новым зерном").clicked() {
+                    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+                    self.random_seed = nanos ^ self.random_seed.wrapping_mul(0x9E37_79B9);
+                    self.params.randomize(self.selected_algorithm, self.random_seed, self.visible_bounds());
+                }
+                ui.add(egui::DragValue::new(&mut self.random_seed).prefix("зерно: "));
+                if ui.button("По зерну").on_hover_text("Повторить случайные параметры с этим зерном").clicked() {
+                    self.params.randomize(self.selected_algorithm, self.random_seed, self.visible_bounds());
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Справка").clicked() {
+                    self.show_help();
+                }
+                if ui.button("Клавиши").clicked() {
+                    self.shortcuts_open = true;
+                }
+            });
+            if ui.button("Сравнить окружности").clicked() {
+                self.compare_circles();
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Все алгоритмы").clicked() {
+                    self.run_race();
+                }
+                ui.checkbox(&mut self.race_include_aa, "со сглаживанием");
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Таблица пикселей").clicked() {
+                    self.show_pixel_count_table();
+                }
+                ui.checkbox(&mut self.pixel_table_auto, "обновлять при запуске");
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("report_format")
+                    .selected_text(format!("{}", self.report_format))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.report_format, ReportFormat::Markdown, "Markdown");
+                        ui.selectable_value(&mut self.report_format, ReportFormat::Html, "HTML");
+                    });
+                if ui.add_enabled(self.last_run_algorithm.is_some(), egui::Button::new("Сохранить отчёт")).clicked() {
+                    self.save_report();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.last_run_algorithm.is_some(), egui::Button::new("Экспорт лога пикселей (JSON)")).clicked() {
+                    self.export_pixel_log();
+                }
+                if ui.add_enabled(self.last_run_algorithm.is_some(), egui::Button::new("Экспорт матрицы (NumPy)"))
+                    .on_hover_text("numpy.loadtxt(\"grid_<метод>.txt\")")
+                    .clicked()
+                {
+                    self.export_grid();
+                }
+                if ui.add_enabled(self.last_run_algorithm.is_some(), egui::Button::new("Экспорт PNG")).clicked() {
+                    self.export_png();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.pixel_log_path).desired_width(140.0));
+                if ui.button("Воспроизвести лог").clicked() {
+                    self.replay_pixel_log();
+                }
+            });
+        });
+        if let Some(panel) = panel.filter(|_| self.layout.panel_open) {
+            self.layout.panel_width = panel.response.rect.width();
+        }
 
-        // --- Отрисовка текстовых окон (без изменений) ---
+        // --- Отрисовка текстовых окон ---
         self.draw_text_windows(ctx);
+        self.trim_closed_windows();
+        self.save_layout(ctx);
+        self.draw_race_window(ctx);
+        self.draw_goto_window(ctx);
+        self.draw_shortcuts_window(ctx);
 
         // --- Переменные для обмена данными между панелями ---
         // Мы вычислим их в CentralPanel, а используем в TopBottomPanel.
@@ -125,18 +1570,58 @@ impl eframe::App for GraphicsLabApp {
 
         // --- Центральная панель (холст) ---
         egui::CentralPanel::default().show(ctx, |ui| {
-            let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+            let full = ui.available_rect_before_wrap();
+            let rects = if self.split_view {
+                let half = (full.width() - 4.0) / 2.0;
+                vec![
+                    Rect::from_min_size(full.min, Vec2::new(half, full.height())),
+                    Rect::from_min_size(Pos2::new(full.max.x - half, full.min.y), Vec2::new(half, full.height())),
+                ]
+            } else {
+                vec![full]
+            };
+            // Клавиши и строка состояния относятся к виду под курсором (или к последнему такому)
+            if let Some(pointer) = ctx.input(|i| i.pointer.hover_pos()) {
+                if let Some(index) = rects.iter().position(|r| r.contains(pointer)) {
+                    if self.canvas_drag == CanvasDrag::None {
+                        self.view = index;
+                    }
+                }
+            }
+            let focused = self.view.min(rects.len() - 1);
 
-            self.handle_canvas_input(&response);
+            for (index, rect) in rects.into_iter().enumerate() {
+                self.view = index;
+                let response = ui.allocate_rect(rect, Sense::click_and_drag());
+                let painter = ui.painter_at(rect);
+                self.viewport_mut().size = rect.size();
 
-            let to_screen = self.get_transform(&response.rect);
-            let from_screen = to_screen.inverse();
+                self.handle_canvas_input(&response, index == focused);
+                if self.rerun_on_drag && matches!(self.canvas_drag, CanvasDrag::Point(_)) && response.drag_delta() != Vec2::ZERO {
+                    self.drag_rerun();
+                }
+
+                let to_screen = self.get_transform(&rect);
+                let from_screen = to_screen.inverse();
 
-            self.draw_grid(&painter, &response.rect, from_screen);
-            self.draw_results(&painter, to_screen);
+                self.draw_grid(&painter, &rect, from_screen);
+                let draw_start = std::time::Instant::now();
+                self.draw_results(&painter, to_screen);
+                self.last_draw_time = draw_start.elapsed();
+                self.draw_selection(&painter, to_screen);
+                self.draw_point_highlight(&painter, to_screen);
+                self.draw_ruler(&painter, to_screen);
+                self.draw_minimap(&painter, rect);
+                if let Some(hover_pos) = response.hover_pos().filter(|_| self.show_crosshair) {
+                    self.draw_crosshair(&painter, rect, to_screen, hover_pos);
+                }
 
-            // Сохраняем информацию для строки состояния
-            canvas_info = Some((response.rect.clone(), from_screen, response.hover_pos()));
+                // Сохраняем информацию для строки состояния
+                if index == focused {
+                    canvas_info = Some((rect, from_screen, response.hover_pos()));
+                }
+            }
+            self.view = focused;
         });
 
         // --- Нижняя панель (строка состояния) ---
@@ -146,175 +1631,2397 @@ impl eframe::App for GraphicsLabApp {
                 self.draw_status_bar_content(ui, &rect, from_screen, hover_pos);
             }
         });
+
+        self.record_history(ctx);
     }
 }
 
 // Вспомогательные функции
 impl GraphicsLabApp {
 
+    // Скорость поля длины: при целочисленном вводе перетаскивание меняет значение на целые шаги
+    fn length_speed(&self, fine: f32) -> f64 {
+        if self.integer_input { 1.0 } else { fine as f64 }
+    }
+
     fn show_parameters_ui(&mut self, ui: &mut egui::Ui) {
         ui.label("Параметры:");
         match self.selected_algorithm {
             Algorithm::StepByStep | Algorithm::DDA | Algorithm::BresenhamLine | Algorithm::WuLine |
-            Algorithm::StepByStepAA | Algorithm::DdaAA | Algorithm::BresenhamAA => {
+            Algorithm::StepByStepAA | Algorithm::DdaAA | Algorithm::BresenhamAA | Algorithm::BrushLine |
+            Algorithm::SsaaLine | Algorithm::GuptaSproull | Algorithm::MidpointLine | Algorithm::DiamondExit | Algorithm::SymmetricBresenham | Algorithm::RunSliceLine | Algorithm::DoubleStepLine => {
+                self.show_line_endpoints_ui(ui);
+                Self::clip_window_ui(ui, &mut self.params);
+                if self.params.clip_mode != ClipMode::Off
+                    && ui.button("Вписать окно отсечения").on_hover_text("Показать окно отсечения на весь холст").clicked()
+                {
+                    self.fit_view_to_clip_window();
+                }
+                ui.checkbox(&mut self.show_dominant_axis, "Показать доминантную ось");
+                if self.selected_algorithm == Algorithm::BresenhamLine {
+                    ui.checkbox(&mut self.color_by_value, "Цвет по ошибке err");
+                }
+                if self.selected_algorithm == Algorithm::MidpointLine {
+                    ui.checkbox(&mut self.color_by_value, "Цвет по переменной решения d");
+                }
+                if self.selected_algorithm == Algorithm::DiamondExit
+                    && ui.checkbox(&mut self.diamond_diff, "Разность с Брезенхемом").changed()
+                {
+                    self.update_diamond_delta();
+                }
+                if matches!(self.selected_algorithm, Algorithm::WuLine | Algorithm::StepByStepAA | Algorithm::DdaAA) {
+                    ui.horizontal(|ui| {
+                        ui.label("Дробная часть:");
+                        ui.selectable_value(&mut self.fraction_mode, FractionMode::Floor, "floor");
+                        ui.selectable_value(&mut self.fraction_mode, FractionMode::Truncate, "trunc (неверно)");
+                    });
+                }
+                if self.selected_algorithm == Algorithm::BrushLine {
+                    self.show_stamp_editor(ui);
+                }
+                if self.selected_algorithm == Algorithm::SsaaLine {
+                    ui.add(egui::Slider::new(&mut self.params.ssaa_factor, 1..=algo::MAX_SSAA_FACTOR).text("Выборок на ось"));
+                    let factor = self.params.ssaa_factor;
+                    ui.label(format!("Выборок на пиксель: {}×{} = {}", factor, factor, factor * factor));
+                    if self.last_run_algorithm == Some(Algorithm::SsaaLine) {
+                        ui.label(format!("Время последнего расчёта: {:.3} мс", self.last_run_time.as_secs_f64() * 1000.0));
+                    }
+                }
+            }
+            Algorithm::BresenhamCircle => {
                 ui.horizontal(|ui| {
-                    ui.label("P1:");
-                    ui.add(egui::DragValue::new(&mut self.params.p1.x).speed(1.0).prefix("x:"));
-                    ui.add(egui::DragValue::new(&mut self.params.p1.y).speed(1.0).prefix("y:"));
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut self.params.circle_center.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut self.params.circle_center.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "circle_center");
+                ui.add(egui::DragValue::new(&mut self.params.circle_radius).speed(1.0).prefix("Радиус:"));
+                Self::field_warning(ui, &self.param_errors, "circle_radius");
+
+                ui.label("Октанты:");
+                egui::Grid::new("octant_grid").show(ui, |ui| {
+                    for octant in 0..8 {
+                        let mut enabled = self.params.circle_octants & (1 << octant) != 0;
+                        if ui.checkbox(&mut enabled, format!("{}", octant)).changed() {
+                            self.params.circle_octants ^= 1 << octant;
+                        }
+                        if octant % 4 == 3 {
+                            ui.end_row();
+                        }
+                    }
                 });
                 ui.horizontal(|ui| {
-                    ui.label("P2:");
-                    ui.add(egui::DragValue::new(&mut self.params.p2.x).speed(1.0).prefix("x:"));
-                    ui.add(egui::DragValue::new(&mut self.params.p2.y).speed(1.0).prefix("y:"));
+                    if ui.button("Все").clicked() {
+                        self.params.circle_octants = algo::ALL_OCTANTS;
+                    }
+                    if ui.button("Ни одного").clicked() {
+                        self.params.circle_octants = 0;
+                    }
                 });
+                if ui.button("Проверить баланс октантов").clicked() {
+                    self.verify_circle_octants();
+                }
+                if ui.checkbox(&mut self.show_circle_coverage, "Точное покрытие (эталон)").changed() {
+                    self.circle_coverage = None;
+                }
             }
-            Algorithm::BresenhamCircle => {
+            Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle => {
                 ui.horizontal(|ui| {
                     ui.label("Центр:");
                     ui.add(egui::DragValue::new(&mut self.params.circle_center.x).speed(1.0).prefix("x:"));
                     ui.add(egui::DragValue::new(&mut self.params.circle_center.y).speed(1.0).prefix("y:"));
                 });
-                ui.add(egui::DragValue::new(&mut self.params.circle_radius).speed(1.0).prefix("Радиус:"));
+                Self::field_warning(ui, &self.param_errors, "circle_center");
+                let radius_speed = self.length_speed(0.1);
+                ui.add(egui::DragValue::new(&mut self.params.circle_radius).speed(radius_speed).prefix("Радиус:"));
+                Self::field_warning(ui, &self.param_errors, "circle_radius");
             }
-            Algorithm::CastlePitway => {
-                ui.label("Опорные точки:");
-                for (i, p) in self.params.castle_points.iter_mut().enumerate() {
+            Algorithm::RoundedRect => {
+                let radius_speed = self.length_speed(0.5);
+                let params = &mut self.params;
+                ui.horizontal(|ui| {
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut params.rrect_center.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.rrect_center.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "rrect_center");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut params.rrect_width).speed(1.0).clamp_range(0.0..=f32::MAX).prefix("Ширина: "));
+                    ui.add(egui::DragValue::new(&mut params.rrect_height).speed(1.0).clamp_range(0.0..=f32::MAX).prefix("Высота: "));
+                });
+                Self::field_warning(ui, &self.param_errors, "rrect_size");
+                let max_radius = params.rrect_width.min(params.rrect_height) / 2.0;
+                ui.add(egui::DragValue::new(&mut params.rrect_radius).speed(radius_speed).clamp_range(0.0..=max_radius).prefix("Радиус углов: "));
+            }
+            Algorithm::ThickPolyline => {
+                let width_speed = self.length_speed(0.5);
+                let params = &mut self.params;
+                ui.label("Вершины:");
+                Self::point_list_ui(ui, &mut params.thick_points, 2);
+                Self::field_warning(ui, &self.param_errors, "thick_points");
+                ui.add(egui::DragValue::new(&mut params.thick_width).speed(width_speed).clamp_range(1.0..=200.0).prefix("Толщина: "));
+                ui.horizontal(|ui| {
+                    ui.label("Концы:");
+                    for cap in [LineCap::Butt, LineCap::Square, LineCap::Round] {
+                        ui.selectable_value(&mut params.line_cap, cap, cap.to_string());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Соединения:");
+                    for join in [LineJoin::Miter, LineJoin::Bevel, LineJoin::Round] {
+                        ui.selectable_value(&mut params.line_join, join, join.to_string());
+                    }
+                });
+            }
+            Algorithm::PieSlice => {
+                let params = &mut self.params;
+                ui.horizontal(|ui| {
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut params.circle_center.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.circle_center.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "circle_center");
+                ui.add(egui::DragValue::new(&mut params.circle_radius).speed(1.0).clamp_range(0.0..=f32::MAX).prefix("Радиус:"));
+                Self::field_warning(ui, &self.param_errors, "circle_radius");
+                ui.horizontal(|ui| {
+                    ui.label("Углы:");
+                    ui.add(egui::DragValue::new(&mut params.sector_start).speed(1.0).clamp_range(-360.0..=360.0).prefix("от: ").suffix("°"));
+                    ui.add(egui::DragValue::new(&mut params.sector_end).speed(1.0).clamp_range(-360.0..=360.0).prefix("до: ").suffix("°"));
+                });
+                Self::fill_pattern_ui(ui, &mut params.fill_pattern);
+            }
+            Algorithm::Annulus => {
+                let radius_speed = self.length_speed(0.5);
+                let params = &mut self.params;
+                ui.horizontal(|ui| {
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut params.circle_center.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.circle_center.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "circle_center");
+                ui.horizontal(|ui| {
+                    ui.label("Радиусы:");
+                    ui.add(egui::DragValue::new(&mut params.ring_inner).speed(radius_speed).clamp_range(0.0..=f32::MAX).prefix("внутр.: "));
+                    ui.add(egui::DragValue::new(&mut params.ring_outer).speed(radius_speed).clamp_range(0.0..=f32::MAX).prefix("внеш.: "));
+                });
+                Self::field_warning(ui, &self.param_errors, "ring");
+                ui.label(format!("Толщина: {:.1}", (params.ring_outer - params.ring_inner).abs()));
+                ui.checkbox(&mut params.ring_concentric, "Концентрическими окружностями (с дырами)");
+            }
+            Algorithm::ThreePointCircle => {
+                for (i, p) in self.params.circle_through.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("A{}:", i));
+                        ui.add(egui::DragValue::new(&mut p.x).speed(1.0).prefix("x:"));
+                        ui.add(egui::DragValue::new(&mut p.y).speed(1.0).prefix("y:"));
+                    });
+                }
+                Self::field_warning(ui, &self.param_errors, "circle_through");
+                let [a, b, c] = self.params.circle_through;
+                match algo::circumcircle(a, b, c) {
+                    Some((center, radius)) => ui.label(format!("Центр: ({:.2}, {:.2}), радиус: {:.2}", center.x, center.y, radius)),
+                    None => ui.colored_label(Color32::RED, "Точки лежат на одной прямой"),
+                };
+            }
+            Algorithm::EllipseAA => {
+                ui.horizontal(|ui| {
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut self.params.ellipse_center.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut self.params.ellipse_center.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "ellipse_center");
+                ui.horizontal(|ui| {
+                    ui.label("Полуоси:");
+                    ui.add(egui::DragValue::new(&mut self.params.ellipse_rx).speed(1.0).clamp_range(0.5..=10_000.0).prefix("a:"));
+                    ui.add(egui::DragValue::new(&mut self.params.ellipse_ry).speed(1.0).clamp_range(0.5..=10_000.0).prefix("b:"));
+                });
+            }
+            Algorithm::MidpointParabola => {
+                ui.label("y - y0 = a(x - x0)²");
+                ui.horizontal(|ui| {
+                    ui.label("Вершина:");
+                    ui.add(egui::DragValue::new(&mut self.params.parabola_vertex.x).speed(1.0).prefix("x0:"));
+                    ui.add(egui::DragValue::new(&mut self.params.parabola_vertex.y).speed(1.0).prefix("y0:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "parabola_vertex");
+                ui.add(egui::DragValue::new(&mut self.params.parabola_a).speed(0.001).clamp_range(-10.0..=10.0).prefix("a: "));
+                ui.add(egui::DragValue::new(&mut self.params.parabola_extent).speed(1.0).clamp_range(1.0..=500.0).prefix("|x - x0| ≤ "));
+            }
+            Algorithm::Hyperbola => {
+                ui.label("(x - x0)²/a² - (y - y0)²/b² = 1");
+                ui.horizontal(|ui| {
+                    ui.label("Центр:");
+                    ui.add(egui::DragValue::new(&mut self.params.hyperbola_center.x).speed(1.0).prefix("x0:"));
+                    ui.add(egui::DragValue::new(&mut self.params.hyperbola_center.y).speed(1.0).prefix("y0:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "hyperbola_center");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.params.hyperbola_a).speed(1.0).clamp_range(1.0..=10_000.0).prefix("a:"));
+                    ui.add(egui::DragValue::new(&mut self.params.hyperbola_b).speed(1.0).clamp_range(1.0..=10_000.0).prefix("b:"));
+                });
+                ui.checkbox(&mut self.params.hyperbola_both, "Обе ветви");
+                ui.label("Кривая строится в пределах видимой области");
+            }
+            Algorithm::CastlePitway | Algorithm::BezierSubdivision | Algorithm::BSpline | Algorithm::CatmullRom | Algorithm::RationalBezier => {
+                self.control_points_ui(ui);
+                Self::field_warning(ui, &self.param_errors, "castle_points");
+                if self.selected_algorithm == Algorithm::CatmullRom {
+                    ui.add(egui::Slider::new(&mut self.params.spline_tension, 0.0..=1.0).text("Натяжение"));
+                }
+                ui.checkbox(&mut self.params.curve_closed, "Замкнутая кривая")
+                    .on_hover_text("Список опорных точек зацикливается: сплайны становятся периодическими, кривая Безье возвращается в P0");
+                if self.selected_algorithm != Algorithm::BezierSubdivision {
+                    ui.checkbox(&mut self.params.castle_adaptive, "Авто: шаги по длине на экране")
+                        .on_hover_text("Число шагов - длина опорной ломаной, умноженная на масштаб: примерно один шаг на экранную точку");
+                    if self.params.castle_adaptive {
+                        ui.label(format!("Шагов: {}", self.adaptive_steps()));
+                    } else {
+                        ui.add(
+                            egui::DragValue::new(&mut self.params.castle_steps)
+                                .clamp_range(1..=algo::MAX_CURVE_STEPS)
+                                .prefix("Шагов: "),
+                        );
+                    }
+                } else {
+                    ui.add(
+                        egui::DragValue::new(&mut self.params.bezier_tolerance)
+                            .speed(0.01)
+                            .clamp_range(0.01..=20.0)
+                            .prefix("Допуск плоскости, пикс.: "),
+                    );
+                }
+                Self::pen_pattern_ui(ui, &mut self.params.curve_pen);
+            }
+            Algorithm::Hermite => {
+                let params = &mut self.params;
+                for (name, point, handle) in [("P0", &mut params.hermite_p0, &mut params.hermite_h0), ("P1", &mut params.hermite_p1, &mut params.hermite_h1)] {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", name));
+                        ui.add(egui::DragValue::new(&mut point.x).speed(1.0).prefix("x:"));
+                        ui.add(egui::DragValue::new(&mut point.y).speed(1.0).prefix("y:"));
+                    });
+                    // Касательная редактируется как вектор, хранится концом ручки
+                    let mut tangent = (*handle - *point) * HERMITE_HANDLE_SCALE;
+                    let changed = ui.horizontal(|ui| {
+                        ui.label(format!("  m{}:", &name[1..]));
+                        ui.add(egui::DragValue::new(&mut tangent.x).speed(1.0).prefix("x:")).changed()
+                            | ui.add(egui::DragValue::new(&mut tangent.y).speed(1.0).prefix("y:")).changed()
+                    }).inner;
+                    if changed {
+                        *handle = *point + tangent / HERMITE_HANDLE_SCALE;
+                    }
+                }
+                Self::field_warning(ui, &self.param_errors, "hermite");
+                ui.add(
+                    egui::DragValue::new(&mut params.hermite_steps)
+                        .clamp_range(1..=algo::MAX_CURVE_STEPS)
+                        .prefix("Шагов: "),
+                );
+                ui.label("Концы стрелок касательных можно перетаскивать на холсте");
+                Self::pen_pattern_ui(ui, &mut params.curve_pen);
+            }
+            Algorithm::PolygonFillAA => {
+                ui.label("Вершины:");
+                Self::point_list_ui(ui, &mut self.params.polygon_points, 3);
+                Self::field_warning(ui, &self.param_errors, "polygon_points");
+                ui.checkbox(&mut self.params.polygon_aliased, "Без сглаживания (по центрам пикселей)");
+                Self::fill_pattern_ui(ui, &mut self.params.fill_pattern);
+            }
+            Algorithm::TriangleFillAA => {
+                for (i, p) in self.params.triangle_points.iter_mut().enumerate() {
                     ui.horizontal(|ui| {
-                        ui.label(format!("P{}:", i));
+                        ui.label(format!("V{}:", i));
                         ui.add(egui::DragValue::new(&mut p.x).speed(1.0).prefix("x:"));
                         ui.add(egui::DragValue::new(&mut p.y).speed(1.0).prefix("y:"));
                     });
                 }
+                Self::field_warning(ui, &self.param_errors, "triangle_points");
+                Self::fill_pattern_ui(ui, &mut self.params.fill_pattern);
+            }
+            Algorithm::FloodFill | Algorithm::BoundaryFill => {
+                let params = &mut self.params;
+                ui.horizontal(|ui| {
+                    ui.label("Граница:");
+                    ui.selectable_value(&mut params.fill_boundary, FillBoundary::Circle, "Окружность");
+                    ui.selectable_value(&mut params.fill_boundary, FillBoundary::Polygon, "Многоугольник");
+                });
+                match params.fill_boundary {
+                    FillBoundary::Circle => {
+                        ui.horizontal(|ui| {
+                            ui.label("Центр:");
+                            ui.add(egui::DragValue::new(&mut params.circle_center.x).speed(1.0).prefix("x:"));
+                            ui.add(egui::DragValue::new(&mut params.circle_center.y).speed(1.0).prefix("y:"));
+                        });
+                        Self::field_warning(ui, &self.param_errors, "circle_center");
+                        ui.add(egui::DragValue::new(&mut params.circle_radius).speed(1.0).prefix("Радиус:"));
+                        Self::field_warning(ui, &self.param_errors, "circle_radius");
+                    }
+                    FillBoundary::Polygon => {
+                        ui.label("Вершины:");
+                        Self::point_list_ui(ui, &mut params.polygon_points, 3);
+                        Self::field_warning(ui, &self.param_errors, "polygon_points");
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Затравка:");
+                    ui.add(egui::DragValue::new(&mut params.fill_seed.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.fill_seed.y).speed(1.0).prefix("y:"));
+                });
+                Self::field_warning(ui, &self.param_errors, "fill_seed");
+                ui.horizontal(|ui| {
+                    ui.label("Связность:");
+                    ui.selectable_value(&mut params.fill_connectivity, Connectivity::Four, "4");
+                    ui.selectable_value(&mut params.fill_connectivity, Connectivity::Eight, "8");
+                });
+                Self::fill_pattern_ui(ui, &mut params.fill_pattern);
+            }
+            Algorithm::Line3D => {
+                let params = &mut self.params;
+                for (label, p) in [("P1:", &mut params.line3d_p1), ("P2:", &mut params.line3d_p2)] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.add(egui::DragValue::new(&mut p[0]).speed(1.0).prefix("x:"));
+                        ui.add(egui::DragValue::new(&mut p[1]).speed(1.0).prefix("y:"));
+                        ui.add(egui::DragValue::new(&mut p[2]).speed(1.0).prefix("z:"));
+                    });
+                }
+                Self::field_warning(ui, &self.param_errors, "line3d");
+                ui.horizontal(|ui| {
+                    ui.label("Проекция:");
+                    for projection in [Projection::Orthographic, Projection::Isometric] {
+                        ui.selectable_value(&mut params.projection, projection, projection.to_string());
+                    }
+                });
+                ui.label("Яркость показывает глубину: ближние вокселы ярче");
+            }
+            Algorithm::FunctionPlot => {
+                ui.horizontal(|ui| {
+                    ui.label("y(x) =");
+                    ui.text_edit_singleline(&mut self.params.function_expr);
+                });
+                Self::field_warning(ui, &self.param_errors, "function_expr");
+                ui.label("Функции: sin cos tan sqrt abs exp ln log floor ceil, константы pi, e");
+                ui.label("График строится в пределах видимой области");
+            }
+            Algorithm::CustomParametric => {
+                let params = &mut self.params;
+                ui.horizontal(|ui| {
+                    ui.label("Семейство:");
+                    for family in [CurveFamily::Custom, CurveFamily::Rose, CurveFamily::Lissajous, CurveFamily::Cycloid] {
+                        ui.selectable_value(&mut params.curve_family, family, family.label());
+                    }
+                });
+                let custom = params.curve_family == CurveFamily::Custom;
+                if !custom {
+                    let (a_label, b_label) = match params.curve_family {
+                        CurveFamily::Rose => ("k = ", " / "),
+                        CurveFamily::Cycloid => ("Арок: ", ""),
+                        _ => ("a: ", "b: "),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut params.family_amplitude).speed(1.0).prefix("A: "));
+                        ui.add(egui::DragValue::new(&mut params.family_a).speed(0.1).clamp_range(0.0..=50.0).prefix(a_label));
+                        if params.curve_family != CurveFamily::Cycloid {
+                            ui.add(egui::DragValue::new(&mut params.family_b).speed(0.1).clamp_range(0.1..=50.0).prefix(b_label));
+                        }
+                    });
+                    params.apply_curve_family();
+                }
+                ui.add_enabled_ui(custom, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("x(t) =");
+                        ui.text_edit_singleline(&mut params.custom_x);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("y(t) =");
+                        ui.text_edit_singleline(&mut params.custom_y);
+                    });
+                });
+                Self::field_warning(ui, &self.param_errors, "custom_expr");
+                ui.horizontal(|ui| {
+                    ui.label("t:");
+                    ui.add(egui::DragValue::new(&mut params.custom_t_from).speed(0.1));
+                    ui.label("..");
+                    ui.add(egui::DragValue::new(&mut params.custom_t_to).speed(0.1));
+                });
+                ui.add(
+                    egui::DragValue::new(&mut params.custom_samples)
+                        .clamp_range(2..=MAX_CUSTOM_SAMPLES)
+                        .prefix("Выборок: "),
+                );
+                ui.label("Функции: sin cos tan sqrt abs exp ln log floor ceil, константы pi, e");
+                Self::pen_pattern_ui(ui, &mut params.curve_pen);
             }
         }
     }
 
-    fn run_algorithm(&mut self) {
-        self.last_run_algorithm = Some(self.selected_algorithm);
-
-        if self.log_enabled {
-            let mut logger = StringLogger::new();
-            self.render_result = self.execute_and_get_result(&mut logger);
-
-            let log_content = logger.results(); // logger будет "потреблен" здесь
-            self.text_windows.push(TextWindow {
-                title: format!("Логи: {}", self.selected_algorithm),
-                content: log_content,
-                is_open: true,
+    // Общий список опорных точек кривых: у Безье степень фиксирована, у сплайнов точки можно добавлять
+    fn control_points_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Опорные точки:");
+        // Минимальное число точек и на сколько участков сплайна меньше, чем точек
+        let spline = match self.selected_algorithm {
+            Algorithm::BSpline => Some((4, 3)),
+            Algorithm::CatmullRom => Some((2, 1)),
+            _ => None,
+        };
+        if let Some((min_len, shortfall)) = spline {
+            self.hovered_row = Self::point_list_ui(ui, &mut self.params.castle_points, min_len);
+            ui.label(format!("Участков сплайна: {}", self.params.castle_points.len().saturating_sub(shortfall)));
+            return;
+        }
+        let rational = self.selected_algorithm == Algorithm::RationalBezier;
+        let params = &mut self.params;
+        params.castle_weights.resize(params.castle_points.len(), 1.0);
+        let len = params.castle_points.len();
+        let mut remove = None;
+        let mut swap = None;
+        let mut rows = Vec::with_capacity(len);
+        for (i, (p, w)) in params.castle_points.iter_mut().zip(params.castle_weights.iter_mut()).enumerate() {
+            let row = ui.horizontal(|ui| {
+                let handle = ui
+                    .add(egui::Label::new("☰").sense(Sense::drag()))
+                    .on_hover_cursor(egui::CursorIcon::Grab)
+                    .on_hover_text("Перетащите, чтобы изменить порядок точек");
+                if handle.drag_started() {
+                    self.dragged_row = Some(i);
+                }
+                ui.label(format!("P{}:", i));
+                ui.add(egui::DragValue::new(&mut p.x).speed(1.0).prefix("x:"));
+                ui.add(egui::DragValue::new(&mut p.y).speed(1.0).prefix("y:"));
+                if rational {
+                    ui.add(egui::DragValue::new(w).speed(0.01).clamp_range(0.01..=100.0).prefix("w:"));
+                }
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).on_hover_text("Поменять с предыдущей").clicked() {
+                    swap = Some(i - 1);
+                }
+                if ui.add_enabled(i + 1 < len, egui::Button::new("⬇")).on_hover_text("Поменять со следующей").clicked() {
+                    swap = Some(i);
+                }
+                if ui.add_enabled(len > 2, egui::Button::new("−")).clicked() {
+                    remove = Some(i);
+                }
             });
-        } else {
-            let mut logger = NoOpLogger;
-            self.render_result = self.execute_and_get_result(&mut logger);
+            if ui.rect_contains_pointer(row.response.rect) {
+                self.hovered_row = Some(i);
+            }
+            rows.push(row.response.rect);
         }
-    }
-
-    // Наша новая generic-функция-хелпер
-    fn execute_and_get_result<L: Logger>(&self, logger: &mut L) -> RenderResult {
-        match self.selected_algorithm {
-            Algorithm::StepByStep => {
-                RenderResult::Pixels(algo::step_by_step(self.params.p1, self.params.p2, logger))
-            }
-            Algorithm::DDA => {
-                RenderResult::Pixels(algo::dda(self.params.p1, self.params.p2, logger))
-            }
-            Algorithm::BresenhamLine => {
-                RenderResult::Pixels(algo::bresenham_line(self.params.p1, self.params.p2, logger))
-            }
-            Algorithm::BresenhamCircle => RenderResult::Pixels(algo::bresenham_circle(
-                self.params.circle_center,
-                self.params.circle_radius,
-                logger,
-            )),
-            Algorithm::StepByStepAA => RenderResult::Antialiased(algo::step_by_step_aa(
-                self.params.p1, self.params.p2, logger
-            )),
-            Algorithm::DdaAA => RenderResult::Antialiased(algo::dda_aa(
-                self.params.p1, self.params.p2, logger
-            )),
-            Algorithm::BresenhamAA => RenderResult::Antialiased(
-                algo::bresenham_aa(self.params.p1, self.params.p2, logger)
-            ),
-            Algorithm::CastlePitway => RenderResult::Pixels(algo::castle_pitway(
-                &self.params.castle_points,
-                logger,
-            )),
-            Algorithm::WuLine => {
-                RenderResult::Antialiased(algo::wu_line(self.params.p1, self.params.p2, logger))
+        // Перетаскиваемая строка встаёт туда, где отпущена мышь; до отпускания место вставки отмечено чертой
+        if let (Some(from), Some(pointer)) = (self.dragged_row.filter(|&i| i < len), ui.ctx().pointer_interact_pos()) {
+            let slot = rows.iter().filter(|r| r.center().y < pointer.y).count();
+            let y = rows.get(slot).map_or(rows[len - 1].bottom(), |r| r.top());
+            ui.painter().hline(rows[0].x_range(), y, Stroke::new(2.0, ui.visuals().selection.bg_fill));
+            self.hovered_row = Some(from);
+            if ui.input(|i| i.pointer.any_released()) {
+                let to = if slot > from { slot - 1 } else { slot };
+                let point = params.castle_points.remove(from);
+                params.castle_points.insert(to, point);
+                let weight = params.castle_weights.remove(from);
+                params.castle_weights.insert(to, weight);
+                self.dragged_row = None;
             }
         }
+        if !ui.input(|i| i.pointer.any_down()) {
+            self.dragged_row = None;
+        }
+        // Вес переставляется и удаляется вместе со своей точкой
+        if let Some(i) = swap {
+            params.castle_points.swap(i, i + 1);
+            params.castle_weights.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+            params.castle_points.remove(i);
+            params.castle_weights.remove(i);
+        }
+        if ui.button("+ Добавить точку").clicked() {
+            let next = params.castle_points.last().map_or(Pos2::ZERO, |p| *p + Vec2::new(10.0, 10.0));
+            params.castle_points.push(next);
+            params.castle_weights.push(1.0);
+        }
+        let degree = params.castle_points.len().saturating_sub(1);
+        let name = match degree {
+            1 => " (отрезок)",
+            2 => " (квадратичная)",
+            3 => " (кубическая)",
+            _ => "",
+        };
+        ui.label(format!("Степень кривой: {}{}", degree, name));
+        if rational && ui.button("Четверть окружности").on_hover_text("Квадратичная кривая с весами 1, √2/2, 1").clicked() {
+            let r = 60.0;
+            params.castle_points = vec![Pos2::new(r, 0.0), Pos2::new(r, r), Pos2::new(0.0, r)];
+            params.castle_weights = vec![1.0, std::f32::consts::FRAC_1_SQRT_2, 1.0];
+        }
     }
 
-    fn show_help(&mut self) {
-        let help_content = match self.selected_algorithm {
-            Algorithm::StepByStep => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует операции с плавающей точкой.",
-            Algorithm::DDA => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует операции с плавающей точкой, но более эффективен, чем пошаговый.",
-            Algorithm::BresenhamLine => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует только целочисленную арифметику, очень быстрый.",
-            Algorithm::BresenhamCircle => "Временная сложность: O(R), где R - радиус. Вычисляет одну восьмую часть окружности, используя только целочисленную арифметику.",
-            Algorithm::CastlePitway => "Временная сложность: O(S * P^2), где S - количество шагов, P - количество опорных точек. Сложность для генерации одной точки - O(P^2).",
-            Algorithm::WuLine => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Эталонный алгоритм сглаживания. Использует вычисления с плавающей точкой для определения интенсивности пикселей.",
-            Algorithm::StepByStepAA => "Временная сложность: O(N). Аналогичен обычному StepByStep, но вычисляет интенсивность для двух пикселей на каждом шаге вместо округления.",
-            Algorithm::DdaAA => "Временная сложность: O(N). Аналогичен обычному DDA, но использует идеальные координаты для вычисления интенсивности двух пикселей на каждом шаге.",
-            Algorithm::BresenhamAA => "Временная сложность: O(N). Модификация алгоритма Брезенхема. Сохраняет целочисленный итеративный процесс, но использует параметр ошибки для вычисления интенсивности пикселей (требует деления на каждом шаге).",
-        };
+    fn pen_pattern_ui(ui: &mut egui::Ui, pen: &mut PenPattern) {
+        egui::ComboBox::from_label("Перо")
+            .selected_text(match pen {
+                PenPattern::Solid => "Сплошное",
+                PenPattern::Dashed => "Штриховое",
+                PenPattern::Dotted => "Пунктир точками",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(pen, PenPattern::Solid, "Сплошное");
+                ui.selectable_value(pen, PenPattern::Dashed, "Штриховое");
+                ui.selectable_value(pen, PenPattern::Dotted, "Пунктир точками");
+            });
+    }
 
-        self.text_windows.push(TextWindow {
-            title: format!("Справка: {}", self.selected_algorithm),
-            content: help_content.to_string(),
-            is_open: true,
+    fn clip_window_ui(ui: &mut egui::Ui, params: &mut AppParameters) {
+        ui.horizontal(|ui| {
+            ui.label("Отсечение:");
+            ui.selectable_value(&mut params.clip_mode, ClipMode::Off, "нет");
+            ui.selectable_value(&mut params.clip_mode, ClipMode::Rectangle, "Коэн-Сазерленд");
+            ui.selectable_value(&mut params.clip_mode, ClipMode::Polygon, "Сайрус-Бек");
+        });
+        match params.clip_mode {
+            ClipMode::Off => return,
+            ClipMode::Polygon => {
+                ui.label("Вершины окна:");
+                Self::point_list_ui(ui, &mut params.clip_polygon, 3);
+                if !clip::is_convex(&params.clip_polygon) {
+                    ui.colored_label(Color32::RED, "Окно не выпуклое");
+                }
+                return;
+            }
+            ClipMode::Rectangle => {}
+        }
+        let window = &mut params.clip_window;
+        ui.horizontal(|ui| {
+            ui.label("x:");
+            ui.add(egui::DragValue::new(&mut window.min.x).speed(1.0));
+            ui.label("..");
+            ui.add(egui::DragValue::new(&mut window.max.x).speed(1.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("y:");
+            ui.add(egui::DragValue::new(&mut window.min.y).speed(1.0));
+            ui.label("..");
+            ui.add(egui::DragValue::new(&mut window.max.y).speed(1.0));
         });
+        // Границы, перепутанные при вводе, меняем местами
+        *window = Rect::from_two_pos(window.min, window.max);
     }
 
-    fn draw_text_windows(&mut self, ctx: &egui::Context) {
-        self.text_windows.retain_mut(|win| {
-            let mut is_open = win.is_open;
-            egui::Window::new(&win.title)
-                .open(&mut is_open)
-                .vscroll(true)
-                .show(ctx, |ui| {
-                    ui.label(&win.content);
+    fn show_line_endpoints_ui(&mut self, ui: &mut egui::Ui) {
+        let params = &mut self.params;
+        let mode_changed = ui.horizontal(|ui| {
+            ui.selectable_value(&mut params.line_input, LineInput::TwoPoints, "Две точки").changed()
+                | ui.selectable_value(&mut params.line_input, LineInput::SlopeIntercept, "y = kx + b").changed()
+                | ui.selectable_value(&mut params.line_input, LineInput::Vertical, "x = c").changed()
+        }).inner;
+        match params.line_input {
+            LineInput::TwoPoints => {
+                ui.horizontal(|ui| {
+                    ui.label("P1:");
+                    ui.add(egui::DragValue::new(&mut params.p1.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.p1.y).speed(1.0).prefix("y:"));
                 });
-            win.is_open = is_open;
-            win.is_open
-        });
+                ui.horizontal(|ui| {
+                    ui.label("P2:");
+                    ui.add(egui::DragValue::new(&mut params.p2.x).speed(1.0).prefix("x:"));
+                    ui.add(egui::DragValue::new(&mut params.p2.y).speed(1.0).prefix("y:"));
+                });
+            }
+            LineInput::SlopeIntercept => {
+                let changed = ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut params.line_k).speed(0.01).prefix("k: ")).changed()
+                        | ui.add(egui::DragValue::new(&mut params.line_b).speed(1.0).prefix("b: ")).changed()
+                }).inner | ui.horizontal(|ui| {
+                    ui.label("x от");
+                    ui.add(egui::DragValue::new(&mut params.line_from).speed(1.0)).changed()
+                        | ui.add(egui::DragValue::new(&mut params.line_to).speed(1.0).prefix("до ")).changed()
+                }).inner;
+                if changed || mode_changed {
+                    (params.p1, params.p2) = params.line_endpoints();
+                }
+            }
+            LineInput::Vertical => {
+                let changed = ui.add(egui::DragValue::new(&mut params.line_c).speed(1.0).prefix("c: ")).changed()
+                    | ui.horizontal(|ui| {
+                        ui.label("y от");
+                        ui.add(egui::DragValue::new(&mut params.line_from).speed(1.0)).changed()
+                            | ui.add(egui::DragValue::new(&mut params.line_to).speed(1.0).prefix("до ")).changed()
+                    }).inner;
+                if changed || mode_changed {
+                    (params.p1, params.p2) = params.line_endpoints();
+                }
+            }
+        }
+        Self::field_warning(ui, &self.param_errors, "line");
     }
 
-    fn get_transform(&self, rect: &Rect) -> egui::emath::RectTransform {
-        let center = rect.center();
-        egui::emath::RectTransform::from_to(
-            Rect::from_center_size(Pos2::ZERO, rect.size() / self.zoom),
-            Rect::from_center_size(center + self.pan, rect.size()),
-        )
+    // Предупреждения проверки для поля field, выводятся прямо под ним
+    fn field_warning(ui: &mut egui::Ui, errors: &[(&'static str, String)], field: &str) {
+        for (_, message) in errors.iter().filter(|(f, _)| *f == field) {
+            ui.colored_label(Color32::RED, format!("⚠ {}", message));
+        }
     }
 
-    fn handle_canvas_input(&mut self, response: &egui::Response) {
-        if response.dragged_by(egui::PointerButton::Primary) {
-            self.pan += response.drag_delta();
-        }
-        if response.hovered() {
-            let scroll = response.ctx.input(|i| i.scroll_delta);
-            if scroll.y != 0.0 {
-                let old_zoom = self.zoom;
-                self.zoom = (self.zoom * (1.0 + scroll.y * 0.01)).max(0.1);
-                if let Some(hover_pos) = response.hover_pos() {
-                    let from_screen = self.get_transform(&response.rect).inverse();
-                    let logic_pos_before = from_screen * hover_pos;
-                    let to_screen_after = self.get_transform(&response.rect);
-                    let screen_pos_after = to_screen_after * logic_pos_before;
-                    self.pan += hover_pos - screen_pos_after;
+    // Редактор списка точек с кнопками добавления и удаления
+    // Возвращает номер строки под мышью
+    fn point_list_ui(ui: &mut egui::Ui, points: &mut Vec<Pos2>, min_len: usize) -> Option<usize> {
+        let mut remove = None;
+        let mut hovered = None;
+        let len = points.len();
+        for (i, p) in points.iter_mut().enumerate() {
+            let row = ui.horizontal(|ui| {
+                ui.label(format!("P{}:", i));
+                ui.add(egui::DragValue::new(&mut p.x).speed(1.0).prefix("x:"));
+                ui.add(egui::DragValue::new(&mut p.y).speed(1.0).prefix("y:"));
+                if ui.add_enabled(len > min_len, egui::Button::new("−")).clicked() {
+                    remove = Some(i);
                 }
+            });
+            if ui.rect_contains_pointer(row.response.rect) {
+                hovered = Some(i);
             }
         }
+        if let Some(i) = remove {
+            points.remove(i);
+        }
+        if ui.button("+ Добавить точку").clicked() {
+            let next = points.last().map_or(Pos2::ZERO, |p| *p + Vec2::new(10.0, 10.0));
+            points.push(next);
+        }
+        hovered
     }
 
-    fn calculate_grid_step(&self) -> f32 {
-        // Цель: иметь линии сетки примерно каждые 80 пикселей
-        let target_step_pixels = 80.0;
-        let logical_step = target_step_pixels / self.zoom;
-
-        // Находим ближайшую степень 10
-        let power_of_10 = 10.0_f32.powf(logical_step.log10().floor());
+    // Редактор узора заливки: готовые узоры и маска 8x8, повторяющаяся по плоскости
+    fn fill_pattern_ui(ui: &mut egui::Ui, pattern: &mut FillPattern) {
+        ui.label("Узор заливки:");
+        ui.horizontal(|ui| {
+            if ui.button("Сплошной").clicked() {
+                *pattern = FillPattern::solid();
+            }
+            if ui.button("Штриховка").clicked() {
+                *pattern = FillPattern::hatch();
+            }
+            if ui.button("Сетка").clicked() {
+                *pattern = FillPattern::crosshatch();
+            }
+            if ui.button("Шахматы").clicked() {
+                *pattern = FillPattern::checker();
+            }
+        });
+        egui::Grid::new("fill_pattern_grid").spacing(Vec2::splat(2.0)).show(ui, |ui| {
+            for row in 0..8 {
+                for col in 0..8 {
+                    let mut value = pattern.get(col, row);
+                    if ui.checkbox(&mut value, "").changed() {
+                        pattern.set(col, row, value);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
 
-        // Нормализуем шаг к диапазону [1, 10)
+    fn show_stamp_editor(&mut self, ui: &mut egui::Ui) {
+        ui.label("Кисть:");
+        ui.horizontal(|ui| {
+            if ui.button("Крест 3x3").clicked() {
+                self.params.brush_stamp = Stamp::cross3();
+            }
+            if ui.button("Диск 5x5").clicked() {
+                self.params.brush_stamp = Stamp::disc5();
+            }
+        });
+        let mut size = self.params.brush_stamp.size;
+        if ui.add(egui::DragValue::new(&mut size).clamp_range(1..=9).prefix("Размер: ")).changed() {
+            self.params.brush_stamp.resize(size);
+        }
+        ui.add(egui::DragValue::new(&mut self.params.brush_spacing).clamp_range(1..=64).prefix("Шаг: "));
+
+        let stamp = &mut self.params.brush_stamp;
+        egui::Grid::new("stamp_grid").spacing(Vec2::splat(2.0)).show(ui, |ui| {
+            for row in 0..stamp.size {
+                for col in 0..stamp.size {
+                    let mut value = stamp.get(col, row);
+                    if ui.checkbox(&mut value, "").changed() {
+                        stamp.set(col, row, value);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    // Переносит текущий результат в "луковую кожу", вытесняя самый старый. Закрепления результатов
+    // в приложении нет, поэтому все записи вытесняются по очереди
+    fn remember_onion_skin(&mut self) {
+        let Some(algorithm) = self.last_run_algorithm else { return };
+        let result = std::mem::replace(&mut self.render_result, RenderResult::None);
+        let params = self.last_run_params.clone();
+        self.onion_skins.push_front(PastResult { algorithm, params, result, colors: self.result_colors });
+        self.onion_skins.truncate(self.onion_depth);
+    }
+
+    fn show_onion_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.onion_enabled, "Предыдущие результаты");
+        if !self.onion_enabled {
+            return;
+        }
+        if ui.add(egui::DragValue::new(&mut self.onion_depth).clamp_range(1..=10).prefix("Хранить: ")).changed() {
+            self.onion_skins.truncate(self.onion_depth);
+        }
+        for (i, past) in self.onion_skins.iter().enumerate() {
+            ui.label(format!("-{}: {}", i + 1, past.algorithm));
+        }
+        if !self.onion_skins.is_empty() && ui.button("Очистить").clicked() {
+            self.onion_skins.clear();
+        }
+    }
+
+    fn show_playback_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.playback_enabled, "Пошаговый просмотр");
+        if self.playback_enabled {
+            let total = self.render_result.len();
+            ui.add(egui::Slider::new(&mut self.playback_step, 0..=total).text("Пикселей"));
+            ui.horizontal(|ui| {
+                if ui.button("◀").clicked() {
+                    self.playback_step = self.playback_step.saturating_sub(1);
+                }
+                if ui.button("▶").clicked() {
+                    self.playback_step = (self.playback_step + 1).min(total);
+                }
+            });
+        }
+    }
+
+    // Запускает выбранный алгоритм в фоновом потоке; результат забирает poll_pending_run
+    fn run_algorithm(&mut self) {
+        self.start_run(self.log_enabled, false);
+    }
+
+    // Перезапуск без лога при перетаскивании точки; если предыдущий расчёт ещё идёт, перезапуск
+    // откладывается до его завершения, чтобы последнее положение точки не потерялось
+    fn drag_rerun(&mut self) {
+        if self.pending_run.is_some() {
+            self.drag_rerun_queued = true;
+        } else {
+            self.start_run(false, true);
+        }
+    }
+
+    // Запуск в фоне; без лога - для частых перезапусков, которые не должны открывать окна логов
+    fn start_run(&mut self, with_log: bool, drag_rerun: bool) {
+        if self.pending_run.is_some() || !self.params.validate(self.selected_algorithm).is_empty() {
+            return;
+        }
+        let algorithm = self.selected_algorithm;
+        let params = self.run_params();
+        let progress = Arc::new(AtomicU32::new(0));
+        let (min, max) = (self.log_filter_min, self.log_filter_max);
+        let handle = match (with_log, self.log_filter_enabled, self.log_as_table) {
+            (false, _, _) => self.spawn_run(algorithm, params.clone(), NoOpLogger, &progress),
+            (true, false, false) => self.spawn_run(algorithm, params.clone(), StringLogger::new(), &progress),
+            (true, false, true) => self.spawn_run(algorithm, params.clone(), TableLogger::new(), &progress),
+            (true, true, false) => self.spawn_run(algorithm, params.clone(), RangeFilterLogger::new(StringLogger::new(), min, max), &progress),
+            (true, true, true) => self.spawn_run(algorithm, params.clone(), RangeFilterLogger::new(TableLogger::new(), min, max), &progress),
+        };
+        self.pending_run = Some(PendingRun {
+            algorithm,
+            params,
+            colors: self.run_colors,
+            show_log: with_log,
+            table_log: self.log_as_table,
+            drag_rerun,
+            started: std::time::Instant::now(),
+            progress,
+            handle,
+        });
+    }
+
+    fn spawn_run<L: Logger + Send + 'static>(
+        &self,
+        algorithm: Algorithm,
+        params: AppParameters,
+        logger: L,
+        progress: &Arc<AtomicU32>,
+    ) -> std::thread::JoinHandle<RunOutput> {
+        let view = self.visible_bounds();
+        let rounding_mode = self.rounding_mode;
+        let fraction_mode = self.fraction_mode;
+        let mut logger = ProgressLogger::new(logger, progress.clone());
+        std::thread::spawn(move || {
+            if algorithm.uses_rounding() {
+                logger.log(format!("Режим округления: {}", rounding_mode));
+            }
+            let start = std::time::Instant::now();
+            let result = execute_algorithm(algorithm, &params, view, rounding_mode, fraction_mode, &mut logger);
+            let elapsed = start.elapsed();
+            let iterations = logger.iterations();
+            RunOutput { result, elapsed, log: logger.results(), iterations }
+        })
+    }
+
+    // Забирает результат фонового расчёта, если он завершился
+    fn poll_pending_run(&mut self, ctx: &egui::Context) {
+        let Some(run) = &self.pending_run else { return };
+        if !run.handle.is_finished() {
+            ctx.request_repaint();
+            return;
+        }
+        let Some(run) = self.pending_run.take() else { return };
+        // Отложенный перезапуск берёт параметры, какими они стали к концу расчёта
+        if std::mem::take(&mut self.drag_rerun_queued) {
+            self.start_run(false, true);
+        }
+        let Ok(output) = run.handle.join() else {
+            self.text_windows.push(TextWindow::new("Ошибка".to_string(), format!("Расчёт {} завершился аварийно", run.algorithm)));
+            return;
+        };
+
+        // Промежуточные результаты перетаскивания вытеснили бы из "луковой кожи" все настоящие запуски
+        if !run.drag_rerun {
+            self.remember_onion_skin();
+        }
+        self.last_run_algorithm = Some(run.algorithm);
+        self.last_run_params = run.params;
+        self.result_colors = run.colors;
+        self.render_result = output.result;
+        self.last_run_time = output.elapsed;
+        self.last_log = output.log;
+        self.last_iterations = output.iterations;
+        self.circle_coverage = None;
+        self.mirrored = None;
+        self.update_stash_delta();
+        self.update_diamond_delta();
+        if run.show_log {
+            let window = TextWindow::new(format!("Логи: {}", run.algorithm), self.last_log.clone()).kind("Логи");
+            self.text_windows.push(if run.table_log { window.monospace() } else { window });
+        }
+        self.playback_step = 0;
+
+        if self.pixel_table_auto && run.algorithm.is_line() {
+            self.show_pixel_count_table();
+        }
+    }
+
+    // Считает эталонное покрытие окружности один раз после запуска, пока включено наложение.
+    // Окружность берётся из параметров запуска, а не из текущих полей ввода
+    fn update_circle_coverage(&mut self) {
+        if !self.show_circle_coverage
+            || self.circle_coverage.is_some()
+            || self.last_run_algorithm != Some(Algorithm::BresenhamCircle)
+        {
+            return;
+        }
+        let reference = algo::circle_coverage(self.last_run_params.circle_center, self.last_run_params.circle_radius, 4);
+        let error = algo::coverage_error(self.render_result.records(), &reference);
+        self.circle_coverage = Some((reference, error));
+    }
+
+    fn show_stash_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Запомнить результат").clicked() {
+                self.stashed_result = Some(self.render_result.records().collect());
+                self.stash_delta = None;
+            }
+            if ui.add_enabled(self.stashed_result.is_some(), egui::Button::new("Забыть")).clicked() {
+                self.stashed_result = None;
+                self.stash_delta = None;
+            }
+        });
+        if let Some((added, removed)) = &self.stash_delta {
+            ui.label(format!("Относительно запомненного: +{} / -{}", added.len(), removed.len()));
+        }
+    }
+
+    // Разность текущего результата с запомненным, пересчитывается после каждого запуска
+    fn update_stash_delta(&mut self) {
+        let Some(stashed) = &self.stashed_result else { return };
+        let (added, removed) = algo::pixel_set_delta(self.render_result.records(), stashed.iter().copied());
+        self.stash_delta = Some((added, removed));
+    }
+
+    // Разность с Брезенхемом пересчитывается после запуска правила ромба и при включении наложения
+    fn update_diamond_delta(&mut self) {
+        self.diamond_delta = None;
+        if !self.diamond_diff || self.last_run_algorithm != Some(Algorithm::DiamondExit) {
+            return;
+        }
+        let baseline = self.execute_algorithm(Algorithm::BresenhamLine, &mut NoOpLogger);
+        self.diamond_delta = Some(algo::pixel_set_delta(self.render_result.records(), baseline.records()));
+    }
+
+    fn overlay_mut(&mut self, overlay: Overlay) -> &mut bool {
+        match overlay {
+            Overlay::Grid => &mut self.show_grid,
+            Overlay::Axes => &mut self.show_axes,
+            Overlay::AxisLabels => &mut self.show_axis_labels,
+            Overlay::Ideal => &mut self.show_ideal,
+            Overlay::Markers => &mut self.show_markers,
+            Overlay::ControlPolygon => &mut self.show_control_polygon,
+            Overlay::Checkerboard => &mut self.checkerboard_enabled,
+            Overlay::Minimap => &mut self.show_minimap,
+            Overlay::SubPixel => &mut self.show_subpixel,
+            Overlay::Crosshair => &mut self.show_crosshair,
+        }
+    }
+
+    // Настройки отображения: наложения на холсте и способ рисования пикселей
+    fn show_view_ui(&mut self, ui: &mut egui::Ui) {
+        self.show_overlays_ui(ui);
+        ui.separator();
+        ui.checkbox(&mut self.downsample_enabled, "Прореживать при малом масштабе");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.pixel_borders, "Рамки пикселей");
+            ui.add_enabled(self.pixel_borders, egui::DragValue::new(&mut self.pixel_border_zoom).speed(0.5).clamp_range(2.0..=100.0).prefix("с масштаба "));
+            egui::color_picker::color_edit_button_srgba(ui, &mut self.pixel_border_color, egui::color_picker::Alpha::OnlyBlend);
+        });
+        ui.checkbox(&mut self.color_by_tag, "Цвет по метке (октанту)");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.gamma_correct, "Гамма-коррекция")
+                .on_hover_text("Интенсивность считается линейной яркостью и переводится в непрозрачность с учётом гаммы экрана");
+            ui.add_enabled(self.gamma_correct, egui::DragValue::new(&mut self.gamma).speed(0.05).clamp_range(1.0..=3.0).prefix("γ = "));
+        });
+    }
+
+    fn show_overlays_ui(&mut self, ui: &mut egui::Ui) {
+        for overlay in Overlay::ALL {
+            ui.checkbox(self.overlay_mut(overlay), overlay.label())
+                .on_hover_text(format!("Клавиша: {:?}", overlay.key()));
+        }
+    }
+
+    fn show_progress_ui(&self, ui: &mut egui::Ui) {
+        let Some(run) = &self.pending_run else { return };
+        if run.started.elapsed() < PROGRESS_DELAY {
+            return;
+        }
+        let fraction = f32::from_bits(run.progress.load(Ordering::Relaxed));
+        ui.add(egui::ProgressBar::new(fraction).show_percentage());
+    }
+
+    // Число пикселей у каждого алгоритма отрезка из меню на текущих P1/P2 в сравнении с теоретическим N
+    fn pixel_count_table(&self) -> Table {
+        let mut table = Table::new("pixel_counts", &["Метод", "Пикселей", "Уникальных", "Теоретически N"]);
+        let n = algo::theoretical_line_pixels(self.params.p1, self.params.p2);
+        let lines = ALGORITHM_MENU.iter().flat_map(|group| group.iter().map(|&(algorithm, _)| algorithm)).filter(Algorithm::is_line);
+        for algorithm in lines {
+            let result = self.execute_algorithm(algorithm, &mut NoOpLogger);
+            table.push_row(vec![
+                format!("{}", algorithm),
+                format!("{}", result.len()),
+                format!("{}", algo::unique_count(result.records())),
+                format!("{}", n),
+            ]);
+        }
+        table
+    }
+
+    fn show_pixel_count_table(&mut self) {
+        let title = "Число пикселей по алгоритмам".to_string();
+        self.text_windows.retain(|w| w.title != title);
+        let table = self.pixel_count_table();
+        self.text_windows.push(TextWindow::with_table(title, table));
+    }
+
+    fn show_log_filter_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.log_filter_enabled, "Логировать только область");
+        if !self.log_filter_enabled {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("X:");
+            ui.add(egui::DragValue::new(&mut self.log_filter_min.0));
+            ui.add(egui::DragValue::new(&mut self.log_filter_max.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Y:");
+            ui.add(egui::DragValue::new(&mut self.log_filter_min.1));
+            ui.add(egui::DragValue::new(&mut self.log_filter_max.1));
+        });
+        if let Some(sel) = self.selection {
+            if ui.button("Взять из выделения").clicked() {
+                self.log_filter_min = (sel.min.x.ceil() as i32, sel.min.y.ceil() as i32);
+                self.log_filter_max = (sel.max.x.floor() as i32, sel.max.y.floor() as i32);
+            }
+        }
+    }
+
+    fn execute_algorithm<L: Logger>(&self, algorithm: Algorithm, logger: &mut L) -> RenderResult {
+        execute_algorithm(algorithm, &self.run_params(), self.visible_bounds(), self.rounding_mode, self.fraction_mode, logger)
+    }
+
+    // Видимая область первого вида задаёт границы графика y = f(x), гиперболы и случайных фигур
+    fn visible_bounds(&self) -> Rect {
+        self.viewports[0].bounds()
+    }
+
+    // Параметры запуска: в адаптивном режиме число шагов кривой считается по текущему масштабу
+    // (в самих параметрах остаётся ручное значение, поэтому масштабирование не попадает в историю)
+    fn run_params(&self) -> AppParameters {
+        let mut params = self.params.clone();
+        // Отрезок, заданный уравнением, пересчитывается при каждом запуске: P1/P2 могли устареть после отмены
+        (params.p1, params.p2) = params.line_endpoints();
+        if params.castle_adaptive {
+            params.castle_steps = self.adaptive_steps();
+        }
+        params
+    }
+
+    fn adaptive_steps(&self) -> usize {
+        let zoom = self.viewport().zoom_x.max(self.viewport().zoom_y);
+        algo::adaptive_curve_steps(&self.params.control_polygon(), zoom)
+    }
+
+    // Параметры алгоритма в виде пар "имя - значение" для отчёта
+    fn parameter_rows(&self, algo: Algorithm) -> Vec<(String, String)> {
+        let point = |p: Pos2| format!("({:.1}, {:.1})", p.x, p.y);
+        let steps = if self.params.castle_adaptive {
+            format!("{} (авто)", self.adaptive_steps())
+        } else {
+            self.params.castle_steps.to_string()
+        };
+        let mut rows = Vec::new();
+        if algo.is_line() {
+            rows.push(("P1".to_string(), point(self.params.p1)));
+            rows.push(("P2".to_string(), point(self.params.p2)));
+            match self.params.clip_mode {
+                ClipMode::Off => {}
+                ClipMode::Rectangle => {
+                    let window = self.params.clip_window;
+                    rows.push(("Окно отсечения".to_string(), format!("{} - {}", point(window.min), point(window.max))));
+                }
+                ClipMode::Polygon => {
+                    let vertices: Vec<String> = self.params.clip_polygon.iter().map(|p| point(*p)).collect();
+                    rows.push(("Окно отсечения".to_string(), vertices.join(" ")));
+                }
+            }
+        }
+        match algo {
+            Algorithm::BresenhamCircle => {
+                rows.push(("Центр".to_string(), point(self.params.circle_center)));
+                rows.push(("Радиус".to_string(), format!("{:.1}", self.params.circle_radius)));
+                rows.push(("Октанты".to_string(), format!("{:08b}", self.params.circle_octants)));
+            }
+            Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle => {
+                rows.push(("Центр".to_string(), point(self.params.circle_center)));
+                rows.push(("Радиус".to_string(), format!("{:.1}", self.params.circle_radius)));
+            }
+            Algorithm::RoundedRect => {
+                rows.push(("Центр".to_string(), point(self.params.rrect_center)));
+                rows.push(("Размер".to_string(), format!("{:.0} x {:.0}", self.params.rrect_width, self.params.rrect_height)));
+                rows.push(("Радиус углов".to_string(), format!("{:.0}", self.params.rrect_radius)));
+            }
+            Algorithm::ThickPolyline => {
+                for (i, p) in self.params.thick_points.iter().enumerate() {
+                    rows.push((format!("P{}", i), point(*p)));
+                }
+                rows.push(("Толщина".to_string(), format!("{:.1}", self.params.thick_width)));
+                rows.push(("Концы".to_string(), self.params.line_cap.to_string()));
+                rows.push(("Соединения".to_string(), self.params.line_join.to_string()));
+            }
+            Algorithm::PieSlice => {
+                rows.push(("Центр".to_string(), point(self.params.circle_center)));
+                rows.push(("Радиус".to_string(), format!("{:.1}", self.params.circle_radius)));
+                rows.push(("Углы".to_string(), format!("{:.1}° .. {:.1}°", self.params.sector_start, self.params.sector_end)));
+            }
+            Algorithm::Annulus => {
+                rows.push(("Центр".to_string(), point(self.params.circle_center)));
+                rows.push(("Радиусы".to_string(), format!("{:.1} .. {:.1}", self.params.ring_inner, self.params.ring_outer)));
+                let method = if self.params.ring_concentric { "концентрические окружности" } else { "пролёты строк" };
+                rows.push(("Способ".to_string(), method.to_string()));
+            }
+            Algorithm::ThreePointCircle => {
+                for (i, p) in self.params.circle_through.iter().enumerate() {
+                    rows.push((format!("A{}", i), point(*p)));
+                }
+                let [a, b, c] = self.params.circle_through;
+                if let Some((center, radius)) = algo::circumcircle(a, b, c) {
+                    rows.push(("Центр".to_string(), point(center)));
+                    rows.push(("Радиус".to_string(), format!("{:.2}", radius)));
+                }
+            }
+            Algorithm::EllipseAA => {
+                rows.push(("Центр".to_string(), point(self.params.ellipse_center)));
+                rows.push(("Полуоси".to_string(), format!("{:.1} x {:.1}", self.params.ellipse_rx, self.params.ellipse_ry)));
+            }
+            Algorithm::MidpointParabola => {
+                rows.push(("Вершина".to_string(), point(self.params.parabola_vertex)));
+                rows.push(("a".to_string(), format!("{}", self.params.parabola_a)));
+                rows.push(("|x - x0| ≤".to_string(), format!("{:.1}", self.params.parabola_extent)));
+            }
+            Algorithm::Hyperbola => {
+                rows.push(("Центр".to_string(), point(self.params.hyperbola_center)));
+                rows.push(("Полуоси".to_string(), format!("{:.0} x {:.0}", self.params.hyperbola_a, self.params.hyperbola_b)));
+                rows.push(("Ветви".to_string(), if self.params.hyperbola_both { "обе" } else { "правая" }.to_string()));
+            }
+            Algorithm::CastlePitway => {
+                for (i, p) in self.params.castle_points.iter().enumerate() {
+                    rows.push((format!("P{}", i), point(*p)));
+                }
+                rows.push(("Шагов".to_string(), steps.clone()));
+            }
+            Algorithm::BezierSubdivision => {
+                for (i, p) in self.params.castle_points.iter().enumerate() {
+                    rows.push((format!("P{}", i), point(*p)));
+                }
+                rows.push(("Допуск плоскости".to_string(), format!("{}", self.params.bezier_tolerance)));
+            }
+            Algorithm::RationalBezier => {
+                for (i, p) in self.params.castle_points.iter().enumerate() {
+                    let w = self.params.castle_weights.get(i).copied().unwrap_or(1.0);
+                    rows.push((format!("P{}", i), format!("{}, w = {:.3}", point(*p), w)));
+                }
+                rows.push(("Шагов".to_string(), steps.clone()));
+            }
+            Algorithm::BSpline | Algorithm::CatmullRom => {
+                for (i, p) in self.params.castle_points.iter().enumerate() {
+                    rows.push((format!("P{}", i), point(*p)));
+                }
+                rows.push(("Шагов".to_string(), steps.clone()));
+                if algo == Algorithm::CatmullRom {
+                    rows.push(("Натяжение".to_string(), format!("{:.2}", self.params.spline_tension)));
+                }
+            }
+            Algorithm::Hermite => {
+                let (m0, m1) = self.params.hermite_tangents();
+                rows.push(("P0".to_string(), point(self.params.hermite_p0)));
+                rows.push(("m0".to_string(), point(m0.to_pos2())));
+                rows.push(("P1".to_string(), point(self.params.hermite_p1)));
+                rows.push(("m1".to_string(), point(m1.to_pos2())));
+                rows.push(("Шагов".to_string(), format!("{}", self.params.hermite_steps)));
+            }
+            Algorithm::PolygonFillAA => {
+                for (i, p) in self.params.polygon_points.iter().enumerate() {
+                    rows.push((format!("V{}", i), point(*p)));
+                }
+                let edges = if self.params.polygon_aliased { "по центрам пикселей" } else { "по площади покрытия" };
+                rows.push(("Края".to_string(), edges.to_string()));
+            }
+            Algorithm::TriangleFillAA => {
+                for (i, p) in self.params.triangle_points.iter().enumerate() {
+                    rows.push((format!("V{}", i), point(*p)));
+                }
+            }
+            Algorithm::FloodFill | Algorithm::BoundaryFill => {
+                match self.params.fill_boundary {
+                    FillBoundary::Circle => {
+                        rows.push(("Центр".to_string(), point(self.params.circle_center)));
+                        rows.push(("Радиус".to_string(), format!("{:.1}", self.params.circle_radius)));
+                    }
+                    FillBoundary::Polygon => {
+                        for (i, p) in self.params.polygon_points.iter().enumerate() {
+                            rows.push((format!("V{}", i), point(*p)));
+                        }
+                    }
+                }
+                rows.push(("Затравка".to_string(), point(self.params.fill_seed)));
+                rows.push(("Связность".to_string(), self.params.fill_connectivity.to_string()));
+            }
+            Algorithm::SsaaLine => {
+                rows.push(("Выборок на ось".to_string(), format!("{}", self.params.ssaa_factor)));
+            }
+            Algorithm::BrushLine => {
+                rows.push(("Размер кисти".to_string(), format!("{}", self.params.brush_stamp.size)));
+                rows.push(("Шаг штампа".to_string(), format!("{}", self.params.brush_spacing)));
+            }
+            Algorithm::Line3D => {
+                let point3 = |p: [f32; 3]| format!("({:.1}, {:.1}, {:.1})", p[0], p[1], p[2]);
+                rows.push(("P1".to_string(), point3(self.params.line3d_p1)));
+                rows.push(("P2".to_string(), point3(self.params.line3d_p2)));
+                rows.push(("Проекция".to_string(), self.params.projection.to_string()));
+            }
+            Algorithm::FunctionPlot => {
+                rows.push(("y(x)".to_string(), self.params.function_expr.clone()));
+                let bounds = self.visible_bounds();
+                rows.push(("x".to_string(), format!("[{:.0}, {:.0}]", bounds.min.x, bounds.max.x)));
+            }
+            Algorithm::CustomParametric => {
+                rows.push(("Семейство".to_string(), self.params.curve_family.label().to_string()));
+                rows.push(("x(t)".to_string(), self.params.custom_x.clone()));
+                rows.push(("y(t)".to_string(), self.params.custom_y.clone()));
+                rows.push(("t".to_string(), format!("[{}, {}]", self.params.custom_t_from, self.params.custom_t_to)));
+                rows.push(("Выборок".to_string(), format!("{}", self.params.custom_samples)));
+            }
+            _ => {}
+        }
+        if matches!(algo, Algorithm::PolygonFillAA | Algorithm::TriangleFillAA | Algorithm::FloodFill | Algorithm::BoundaryFill | Algorithm::PieSlice)
+            && !self.params.fill_pattern.is_solid()
+        {
+            let hex: Vec<String> = self.params.fill_pattern.rows.iter().map(|r| format!("{:02X}", r)).collect();
+            rows.push(("Узор".to_string(), hex.join(" ")));
+        }
+        if algo.is_control_curve() && self.params.curve_closed {
+            rows.push(("Кривая".to_string(), "замкнутая".to_string()));
+        }
+        if self.params.supersample > 1 {
+            rows.push(("Суперсэмплинг".to_string(), format!("{}x{}", self.params.supersample, self.params.supersample)));
+        }
+        rows.push(("Округление".to_string(), format!("{}", self.rounding_mode)));
+        rows
+    }
+
+    // Метрики последнего результата: число пикселей, время, отклонение от идеальной фигуры
+    fn result_metrics(&self, algo: Algorithm) -> Vec<(String, String)> {
+        let mut metrics = vec![
+            ("Пикселей".to_string(), format!("{}", self.render_result.len())),
+            ("Время".to_string(), format!("{:.3} мс", self.last_run_time.as_secs_f64() * 1000.0)),
+        ];
+        let params = &self.last_run_params;
+        let rms = if algo.is_line() {
+            Some(algo::line_rms_error(self.render_result.records(), params.p1, params.p2))
+        } else if Algorithm::CIRCLES.contains(&algo) {
+            Some(algo::radial_error(self.render_result.records(), params.circle_center, params.circle_radius).1)
+        } else {
+            None
+        };
+        metrics.push(("СКО от идеала".to_string(), rms.map_or("—".to_string(), |v| format!("{:.4}", v))));
+        if algo.is_line() {
+            let density = algo::pixels_per_unit_length(self.render_result.records(), params.p1, params.p2);
+            metrics.push(("Пикселей на единицу длины".to_string(), format!("{:.3}", density)));
+        }
+        metrics
+    }
+
+    // Запуск всех алгоритмов окружности на текущих центре и радиусе со сводной таблицей
+    fn compare_circles(&mut self) {
+        let mut table = Table::new(
+            "circle_comparison",
+            &["Метод", "Пикселей", "Дубликатов", "Макс. ошибка", "СКО", "Время, мс"],
+        );
+        for &algorithm in Algorithm::CIRCLES {
+            let start = std::time::Instant::now();
+            let result = self.execute_algorithm(algorithm, &mut NoOpLogger);
+            let elapsed = start.elapsed();
+            let (max_error, rms_error) = algo::radial_error(result.records(), self.params.circle_center, self.params.circle_radius);
+            table.push_row(vec![
+                format!("{}", algorithm),
+                format!("{}", result.len()),
+                format!("{}", algo::duplicate_count(result.records())),
+                format!("{:.4}", max_error),
+                format!("{:.4}", rms_error),
+                format!("{:.3}", elapsed.as_secs_f64() * 1000.0),
+            ]);
+        }
+        self.text_windows.push(TextWindow::with_table("Сравнение окружностей".to_string(), table));
+    }
+
+    fn verify_circle_octants(&mut self) {
+        let pixels = algo::bresenham_circle(
+            self.params.circle_center,
+            self.params.circle_radius,
+            self.params.circle_octants,
+            &mut NoOpLogger,
+        );
+        let counts = algo::octant_counts(&pixels, self.params.circle_center);
+        let duplicates = algo::duplicate_count(pixels.iter().copied());
+        let mut content = if algo::verify_octant_balance(&pixels, self.params.circle_center) {
+            format!("Баланс соблюдён: по {} пикселей в каждом октанте.\n", counts[0])
+        } else {
+            let lines: Vec<String> = counts.iter().enumerate().map(|(i, c)| format!("  октант {}: {}", i, c)).collect();
+            format!("Баланс нарушен, пикселей по октантам:\n{}\n", lines.join("\n"))
+        };
+        content.push_str(&format!("Повторно выведенных пикселей (на швах): {}", duplicates));
+        self.text_windows.push(TextWindow::new("Проверка октантов".to_string(), content));
+    }
+
+    // Запуск всех алгоритмов отрезка на текущих P1/P2; эталон для разности - Брезенхем
+    fn run_race(&mut self) {
+        let baseline = self.execute_algorithm(Algorithm::BresenhamLine, &mut NoOpLogger);
+        let mut algorithms = Algorithm::LINES.to_vec();
+        if self.race_include_aa {
+            algorithms.extend_from_slice(Algorithm::AA_LINES);
+        }
+        let rows = algorithms
+            .into_iter()
+            .enumerate()
+            .map(|(i, algorithm)| {
+                let start = std::time::Instant::now();
+                let result = self.execute_algorithm(algorithm, &mut NoOpLogger);
+                let time = start.elapsed();
+                let (only_here, only_baseline) = algo::pixel_set_diff(result.records(), baseline.records());
+                RaceRow {
+                    algorithm,
+                    result,
+                    time,
+                    only_here,
+                    only_baseline,
+                    color: TAG_COLORS[i % TAG_COLORS.len()],
+                    visible: false,
+                }
+            })
+            .collect();
+        self.race = Some(Race { rows, sort: RaceSort::Name, ascending: true, is_open: true });
+    }
+
+    fn race_table(race: &Race) -> Table {
+        let mut table = Table::new("race", &["Метод", "Пикселей", "Время, мс", "Только здесь", "Только у Брезенхема"]);
+        for row in &race.rows {
+            table.push_row(vec![
+                format!("{}", row.algorithm),
+                format!("{}", row.result.len()),
+                format!("{:.3}", row.time.as_secs_f64() * 1000.0),
+                format!("{}", row.only_here),
+                format!("{}", row.only_baseline),
+            ]);
+        }
+        table
+    }
+
+    fn draw_race_window(&mut self, ctx: &egui::Context) {
+        let Some(race) = &mut self.race else { return };
+        let mut export = None;
+        let mut is_open = race.is_open;
+        egui::Window::new("Все алгоритмы").open(&mut is_open).show(ctx, |ui| {
+            egui::Grid::new("race_grid").striped(true).show(ui, |ui| {
+                ui.label("");
+                for (title, sort) in [("Метод", RaceSort::Name), ("Пикселей", RaceSort::Pixels), ("Время, мс", RaceSort::Time), ("Разность", RaceSort::Diff)] {
+                    if ui.button(title).clicked() {
+                        race.ascending = if race.sort == sort { !race.ascending } else { true };
+                        race.sort = sort;
+                    }
+                }
+                ui.end_row();
+
+                let (sort, ascending) = (race.sort, race.ascending);
+                race.rows.sort_by(|a, b| {
+                    let order = match sort {
+                        RaceSort::Name => format!("{}", a.algorithm).cmp(&format!("{}", b.algorithm)),
+                        RaceSort::Pixels => a.result.len().cmp(&b.result.len()),
+                        RaceSort::Time => a.time.cmp(&b.time),
+                        RaceSort::Diff => (a.only_here + a.only_baseline).cmp(&(b.only_here + b.only_baseline)),
+                    };
+                    if ascending { order } else { order.reverse() }
+                });
+
+                for row in &mut race.rows {
+                    ui.checkbox(&mut row.visible, "").on_hover_text("Показать на холсте");
+                    ui.colored_label(row.color, format!("{}", row.algorithm));
+                    ui.label(format!("{}", row.result.len()));
+                    ui.label(format!("{:.3}", row.time.as_secs_f64() * 1000.0));
+                    ui.label(format!("+{} / −{}", row.only_here, row.only_baseline));
+                    ui.end_row();
+                }
+            });
+            if ui.button("Экспорт Markdown").clicked() {
+                export = Some(Self::race_table(race));
+            }
+        });
+        race.is_open = is_open;
+        if !race.is_open {
+            self.race = None;
+        }
+        if let Some(table) = export {
+            let message = Self::write_file(&format!("{}.md", table.name), table.to_markdown());
+            self.text_windows.push(TextWindow::new("Экспорт".to_string(), message));
+        }
+    }
+
+    fn save_report(&mut self) {
+        let Some(algo) = self.last_run_algorithm else { return };
+        let report = Report {
+            algorithm: format!("{}", algo),
+            parameters: self.parameter_rows(algo),
+            metrics: self.result_metrics(algo),
+            log: self.last_log.clone(),
+            svg: report::pixels_to_svg(self.render_result.records()),
+        };
+        let path = format!("report_{}.{}", algo, self.report_format.extension());
+        let content = Self::write_file(&path, report.render(self.report_format));
+        self.text_windows.push(TextWindow::new("Отчёт".to_string(), content));
+    }
+
+    fn export_pixel_log(&mut self) {
+        let Some(algo) = self.last_run_algorithm else { return };
+        let path = format!("pixels_{}.json", algo);
+        let content = Self::write_file(&path, report::pixel_log_json(&algo.to_string(), self.render_result.records()));
+        self.pixel_log_path = path;
+        self.text_windows.push(TextWindow::new("Лог пикселей".to_string(), content));
+    }
+
+    fn export_grid(&mut self) {
+        let Some(algo) = self.last_run_algorithm else { return };
+        let path = format!("grid_{}.txt", algo);
+        let content = match report::pixels_to_grid_text(self.render_result.records()) {
+            Ok(text) => Self::write_file(&path, text),
+            Err(e) => e,
+        };
+        self.text_windows.push(TextWindow::new("Экспорт матрицы".to_string(), content));
+    }
+
+    fn export_png(&mut self) {
+        let Some(algo) = self.last_run_algorithm else { return };
+        let path = format!("result_{}.png", algo);
+        let content = match PixelGrid::from_records(self.render_result.records()) {
+            Ok(grid) => Self::write_file(&path, report::grid_to_png(&grid)),
+            Err(e) => e,
+        };
+        self.text_windows.push(TextWindow::new("Экспорт PNG".to_string(), content));
+    }
+
+    // Показывает пиксели из сохранённого лога без повторного запуска алгоритма
+    fn replay_pixel_log(&mut self) {
+        let parsed = std::fs::read_to_string(&self.pixel_log_path)
+            .map_err(|e| format!("Не удалось прочитать {}: {}", self.pixel_log_path, e))
+            .and_then(|text| report::parse_pixel_log(&text));
+        match parsed {
+            Ok(records) => {
+                self.remember_onion_skin();
+                self.last_run_algorithm = None;
+                self.result_colors = self.run_colors;
+                self.last_log = format!("Загружено {} пикселей из {}", records.len(), self.pixel_log_path);
+                self.last_iterations = None;
+                self.render_result = RenderResult::Rich(records);
+                self.circle_coverage = None;
+                self.mirrored = None;
+                self.update_stash_delta();
+                self.playback_step = 0;
+            }
+            Err(e) => self.text_windows.push(TextWindow::new("Лог пикселей".to_string(), e)),
+        }
+    }
+
+    fn show_help(&mut self) {
+        let help_content = match self.selected_algorithm {
+            Algorithm::StepByStep => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует операции с плавающей точкой.",
+            Algorithm::DDA => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует операции с плавающей точкой, но более эффективен, чем пошаговый.",
+            Algorithm::BresenhamLine => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Использует только целочисленную арифметику, очень быстрый.",
+            Algorithm::DoubleStepLine => "Временная сложность: O(N/2), где N - длина отрезка по доминантной оси. Двухшаговый алгоритм Ву выбирает за итерацию сразу два пикселя. Для двух шагов по основной оси есть всего четыре образца: 1 - оба пикселя прямо, 2 - прямо, затем вверх, 3 - вверх, затем прямо, 4 - оба вверх. При наклоне меньше 1/2 подняться дважды нельзя (нет образца 4), при наклоне от 1/2 нельзя ни разу не подняться (нет образца 1). Поэтому переменная решения d сначала отделяет крайний образец от пары 2/3, а второе сравнение - с 2dy для пологих, с 2dy - 2dx для крутых - выбирает между 2 и 3; d растёт на 4dy - 2dx на каждый подъём. Получаются те же пиксели, что у Брезенхема, за вдвое меньшее число итераций; номер образца виден в режиме цвета по метке.",
+            Algorithm::RunSliceLine => "Временная сложность: O(min(|dx|, |dy|)) решений и O(N) записей пикселей. У пологого отрезка пиксели лежат горизонтальными сериями длиной floor(dx/dy) или на единицу больше, так что решение нужно принимать не для каждого пикселя, а для каждой серии: целочисленная ошибка растёт на 2 (dx mod dy) и при переполнении удлиняет серию на пиксель. Первая и последняя серии - половинки одной, чтобы отрезок был симметричен. Итераций (см. счётчик) dy + 1 вместо dx + 1: чем положе отрезок, тем больше выигрыш, а у диагонали его нет. Результат совпадает с Брезенхемом; серии видны в режиме цвета по метке.",
+            Algorithm::SymmetricBresenham => "Временная сложность: O(N/2), где N - длина отрезка по доминантной оси. Отрезок центрально-симметричен относительно середины, поэтому пиксель, выбранный на i-м шаге от P1, отражённый через середину, - это пиксель i-го шага от P2. Один цикл ведёт оба курсора навстречу друг другу: ошибка вычисляется один раз, а пикселей за итерацию выводится два, и итераций (см. счётчик) вдвое меньше. Выигрыш - в числе проверок и обновлений ошибки, запись пикселей не уменьшается. При ничьих (прямая ровно посередине между пикселями) зеркальное решение может отличаться от однонаправленного Брезенхема, но отрезок от P1 к P2 и от P2 к P1 совпадает. В режиме цвета по метке видны оба курсора.",
+            Algorithm::DiamondExit => "Временная сложность: O(N), где N - длина отрезка по доминантной оси (по три кандидата на шаг). Правило выхода из ромба, которым отрезки растеризует графический процессор: вокруг центра пикселя строится ромб |dx| + |dy| < 1/2, пиксель закрашивается, если отрезок входит в ромб и выходит из него. Ромб, в котором отрезок заканчивается, не закрашивается, поэтому у ломаной общие вершины выводятся один раз. Концы не округляются, так что при дробных концах пиксели могут отличаться от Брезенхема; при целых концах отличия - последний пиксель и ничьи, когда прямая проходит ровно посередине между пикселями. Разность с Брезенхемом показывается рамками: синие - только у ромба, оранжевые - только у Брезенхема.",
+            Algorithm::MidpointLine => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Формулировка через неявное уравнение прямой F(x, y) = dy x - dx y + c: на каждом шаге знак F в средней точке между двумя кандидатами показывает, с какой стороны от неё проходит прямая. Переменная решения d = 2F обновляется на 2dy или 2(dy - dx), поэтому вычисления целочисленные, а выбранные пиксели совпадают с Брезенхемом; отличается только вывод формулы.",
+            Algorithm::BresenhamCircle => "Временная сложность: O(R), где R - радиус. Вычисляет одну восьмую часть окружности, используя только целочисленную арифметику.",
+            Algorithm::RoundedRect => "Временная сложность: O(W + H), где W и H - ширина и высота. Составная фигура: четыре стороны строятся отрезками Брезенхема, а каждый угол - двумя октантами окружности Брезенхема с центром, отступающим от угла на радиус скругления. Стороны касаются дуг в их крайних точках, поэтому контур получается непрерывным; пиксели на стыках выводятся один раз.",
+            Algorithm::ThickPolyline => "Временная сложность: O(L * W), где L - длина ломаной, W - толщина (число закрашенных пикселей). Каждое звено - прямоугольник вокруг оси, залитый построчно по центрам пикселей. На изломе с внешней стороны между прямоугольниками остаётся клиновидная выемка, которую закрывает соединение: острое продолжает внешние кромки до пересечения (при очень остром угле оно слишком длинное и заменяется срезанным), срезанное добавляет треугольник между кромками, скруглённое - круг в вершине. Концы: плоский обрывается в конечной точке, квадратный продлевает линию на полтолщины, круглый добавляет круг из октантов окружности Брезенхема. В режиме цвета по метке звенья, концы и соединения различаются.",
+            Algorithm::PieSlice => "Временная сложность: O(R^2), где R - радиус (число закрашенных пикселей). Составной алгоритм: граница сектора - дуга окружности Брезенхема, из которой оставлены пиксели с углом между начальным и конечным, и два радиальных отрезка Брезенхема от центра до концов дуги. Внутренность заливается построчно: каждая строка пересекается с многоугольником, аппроксимирующим сектор, и пиксели между парами пересечений, не попавшие на границу, закрашиваются. Сектор больше 180° невыпуклый, и в строке может быть два пролёта.",
+            Algorithm::Annulus => "Временная сложность: O(R^2), где R - внешний радиус (число закрашенных пикселей). Закрашиваются все пиксели, центры которых лежат между окружностями: в каждой строке это один или два горизонтальных пролёта, границы которых находятся целочисленным корнем, поэтому кольцо получается без дыр. Для сравнения можно построить кольцо наивно - окружностями Брезенхема для каждого целого радиуса: соседние окружности местами расходятся по диагонали, и остаются дыры, складывающиеся в муаровый узор.",
+            Algorithm::ThreePointCircle => "Временная сложность: O(R), где R - радиус. Центр описанной окружности - пересечение серединных перпендикуляров к сторонам треугольника (решение системы 2x2), радиус - расстояние от центра до любой из точек. Затем окружность строится алгоритмом Брезенхема, который округляет центр и радиус до целых. Если точки лежат на одной прямой, окружности не существует.",
+            Algorithm::PolarCircle => "Временная сложность: O(R), где R - радиус, но с двумя вызовами sin/cos на точку. Угол меняется с шагом 1/R, что соответствует дуге длиной около одного пикселя; координаты R cos θ и R sin θ округляются. Симметрия не используется, соседние точки часто попадают в один пиксель (см. дубликаты в сравнении окружностей), а при крупном шаге появились бы разрывы. Простая базовая линия для сравнения с целочисленным алгоритмом Брезенхема.",
+            Algorithm::SqrtCircle => "Временная сложность: O(R), где R - радиус, с вычислением квадратного корня в каждом столбце. Для x от 0 до R вычисляется y = sqrt(R^2 - x^2) и округляется, остальные четверти получаются отражением. Шаг всегда идёт по x, поэтому там, где касательная круче 45° (x близко к R), соседние столбцы отличаются по y больше чем на пиксель и в окружности появляются разрывы. Корень к тому же намного дороже сложений алгоритма Брезенхема.",
+            Algorithm::RotationCircle => "Временная сложность: O(R), где R - радиус. Точка (R, 0) поворачивается на угол Δ = 1/R по рекуррентным формулам x' = x cos Δ - y sin Δ, y' = x sin Δ + y cos Δ. Синус и косинус вычисляются один раз, а на каждом шаге остаются четыре умножения и два сложения. Ошибки округления накапливаются от шага к шагу, и радиус точки медленно дрейфует; величина дрейфа видна в журнале.",
+            Algorithm::WuCircle => "Временная сложность: O(R), где R - радиус. Сглаженная окружность в духе алгоритма Ву: во втором октанте для каждого x вычисляется точное y = sqrt(R^2 - x^2), и интенсивность делится между двумя соседними по y пикселями пропорционально дробной части. Остальные октанты получаются отражением. Радиус может быть дробным.",
+            Algorithm::EllipseAA => "Временная сложность: O(a + b), где a, b - полуоси. Обходится только полоса ±1.5 пикселя вокруг границы; интенсивность пикселя равна 1 - |d|, где d - расстояние от его центра до идеального эллипса, оценённое как F / |grad F|.",
+            Algorithm::MidpointParabola => "Временная сложность: O(W + a * W^2), где W - полуширина промежутка по x. Метод средней точки: в области 1 (наклон меньше 1) шаг по x, в области 2 - по y; знак F(x, y) = a x^2 - y в средней точке между двумя кандидатами выбирает следующий пиксель. Левая ветвь - отражение правой.",
+            Algorithm::Hyperbola => "Временная сложность: O(W + H), где W, H - размеры видимой области. Метод средней точки для x²/a² - y²/b² = 1: от вершины (a, 0) в области 1 (наклон больше 1) шаг по y, после b²x ≤ a²y - шаг по x. Решающая переменная d = 4F в средней точке целочисленная. Остальные четверти - отражения.",
+            Algorithm::BezierSubdivision => "Временная сложность: O(K * P^2 + L), где K - число плоских участков, P - количество опорных точек, L - длина ломаной в пикселях. Опорная ломаная делится пополам схемой де Кастельжо, пока её отклонение от хорды больше допуска; вершины плоских участков соединяются отрезками Брезенхема. В отличие от равномерного шага по t, на почти прямых участках точек мало, на изгибах - много.",
+            Algorithm::BSpline => "Временная сложность: O(S + L), где S - количество шагов, L - длина ломаной в пикселях. Равномерный кубический B-сплайн: каждые четыре соседние опорные точки задают участок, вычисляемый по базисным функциям. Кривая не проходит через опорные точки, зато гладкая на стыках, а сдвиг одной точки меняет только четыре соседних участка.",
+            Algorithm::CatmullRom => "Временная сложность: O(S + L), где S - количество шагов, L - длина ломаной в пикселях. Интерполяционный сплайн: кривая проходит через все опорные точки, каждый участок - кубический полином Эрмита с касательными (1 - натяжение) * (P[i+1] - P[i-1]) / 2. При натяжении 1 кривая вырождается в ломаную.",
+            Algorithm::Hermite => "Временная сложность: O(S + L), где S - количество шагов, L - длина ломаной в пикселях. Кривая Эрмита задаётся концами P0, P1 и касательными m0, m1: C(t) = h00 P0 + h10 m0 + h01 P1 + h11 m1, где h - кубические базисные функции. Ручки касательных на холсте находятся в P + m/3 - там же, где опорные точки эквивалентной кривой Безье.",
+            Algorithm::RationalBezier => "Временная сложность: O(S * P^2 + L), где S - количество шагов, P - количество опорных точек, L - длина ломаной в пикселях. Схема де Кастельжо в однородных координатах (w x, w y, w) с делением на вес в конце. Веса притягивают кривую к точкам; с весами 1, √2/2, 1 квадратичная кривая точно описывает четверть окружности, чего не может обычная кривая Безье.",
+            Algorithm::CastlePitway => "Временная сложность: O(S * P^2), где S - количество шагов, P - количество опорных точек. Сложность для генерации одной точки - O(P^2). Степень кривой равна P - 1: две точки дают отрезок, три - квадратичную кривую, четыре - кубическую; точки можно добавлять, удалять и переставлять.",
+            Algorithm::WuLine => "Временная сложность: O(N), где N - длина отрезка по доминантной оси. Эталонный алгоритм сглаживания. Использует вычисления с плавающей точкой для определения интенсивности пикселей.",
+            Algorithm::StepByStepAA => "Временная сложность: O(N). Аналогичен обычному StepByStep, но вычисляет интенсивность для двух пикселей на каждом шаге вместо округления.",
+            Algorithm::DdaAA => "Временная сложность: O(N). Аналогичен обычному DDA, но использует идеальные координаты для вычисления интенсивности двух пикселей на каждом шаге.",
+            Algorithm::BresenhamAA => "Временная сложность: O(N). Упрощённая модификация алгоритма Брезенхема. Сохраняет целочисленный итеративный процесс, но делит интенсивность между двумя пикселями по параметру ошибки (требует деления на каждом шаге). Расстояние до прямой и форма фильтра не учитываются.",
+            Algorithm::GuptaSproull => "Временная сложность: O(N). Алгоритм Гупты-Спроулла на основе отрезка средней точки: в каждом столбце закрашиваются три пикселя. Перпендикулярное расстояние от центра пикселя до прямой получается из переменной решения одним сложением и умножением на постоянную 1 / (2 sqrt(dx^2 + dy^2)). Интенсивность берётся из таблицы свёртки прямой толщиной в один пиксель с конусным фильтром радиуса 1, поэтому яркость не зависит от наклона так сильно, как у упрощённого варианта.",
+            Algorithm::BoundaryFill => "Временная сложность: O(N * C), где N - число закрашенных пикселей, C - связность (4 или 8). Классическая заливка до границы: пиксель закрашивается, если он не граничный и ещё не закрашен, после чего все его соседи кладутся на стек. Вместо рекурсии используется явный стек, поэтому большие области не переполняют стек вызовов; обход идёт в глубину, и в журнале видна глубина стека. Виртуальный растр тот же, что у FloodFill.",
+            Algorithm::FloodFill => "Временная сложность: O(N), где N - число закрашенных пикселей. Граница растеризуется заранее (окружность Брезенхема или замкнутая ломаная) и служит виртуальным растром. От затравки заливка расходится волнами в ширину: каждая волна закрашивает незакрашенных соседей предыдущей. Граница Брезенхема 8-связна, поэтому 8-связная заливка просачивается через её диагональные стыки; заливка ограничена габаритами границы с запасом в один пиксель.",
+            Algorithm::TriangleFillAA => "Временная сложность: O(W * H), где W и H - размеры описанного прямоугольника. Для каждого ребра задаётся функция E(x, y) = a x + b y + c, положительная по внутреннюю сторону; пиксель внутри, если все три функции положительны. Вдоль строки функции меняются на постоянную a, поэтому обход прямоугольника требует только сложений (так же устроены растеризаторы видеокарт). Нормированная E - расстояние до ребра, из него получается покрытие краевых пикселей. В отличие от построчной заливки не нужны пересечения и сортировка, но перебираются и пустые пиксели прямоугольника.",
+            Algorithm::PolygonFillAA => "Временная сложность: O(H * S * E), где H - высота многоугольника в пикселях, S - число подстрок развёртки (16), E - число рёбер. Вдоль каждой подстроки доля покрытия пикселя считается по длине пролёта, а площадь оценивается их средним: интенсивность граничных пикселей приближает долю площади внутри многоугольника, внутренние пиксели непрозрачны. Без сглаживания пиксель либо закрашен целиком (центр внутри), либо нет: наклонные края превращаются в лесенку, а узкие углы рассыпаются на отдельные пиксели - та же проблема, что у отрезков, только на каждой границе заливки.",
+            Algorithm::SsaaLine => "Временная сложность: O(N * F^2), где N - длина отрезка, F - число выборок на ось. Эталонное сглаживание: покрытие пикселя отрезком шириной 1 оценивается долей попавших в него выборок. Точность растёт с F, но стоимость растёт квадратично.",
+            Algorithm::Line3D => "Временная сложность: O(max(|dx|, |dy|, |dz|)). Трёхмерный алгоритм Брезенхема: по ведущей оси с наибольшим приращением делается шаг на каждой итерации, а по двум другим ведутся две независимые ошибки - ровно как в плоском алгоритме для проекций отрезка на плоскости, содержащие ведущую ось. Вокселы проецируются на холст ортогонально (z отбрасывается) или изометрически (оси X и Z под 30° к горизонтали); глубина передаётся яркостью, а при наложении вокселов в одном пикселе виден ближайший.",
+            Algorithm::FunctionPlot => "Временная сложность: O(W + P), где W - ширина видимой области, P - число закрашенных пикселей. Выражение разбирается в дерево и вычисляется в каждом целом x видимой области. В столбце x закрашивается вертикальный пролёт между серединами соседних значений, поэтому круто растущая функция остаётся связной. Если соседнее значение не определено (корень из отрицательного числа) или отличается больше чем на высоту области (асимптота tan), столбцы не соединяются и график честно разрывается.",
+            Algorithm::CustomParametric => "Временная сложность: O(S + L), где S - число выборок, L - суммарная длина ломаной в пикселях. Кривая x(t), y(t) вычисляется в S равноотстоящих значениях t, соседние точки соединяются отрезками Брезенхема. Готовые семейства подставляют выражения сами: роза r = A cos(k t) с k = a / b имеет a лепестков при нечётном a (2a при чётном) и замыкается за b оборотов; фигура Лиссажу с частотами a и b; циклоида - траектория точки катящегося круга радиуса A.",
+            Algorithm::BrushLine => "Временная сложность: O(N * S^2 / K), где N - длина отрезка, S - размер кисти, K - шаг штампа. Центры штампов берутся из линии Брезенхема, перекрывающиеся пиксели не дублируются.",
+        };
+
+        let mut content = help_content.to_string();
+        content.push_str("\n\nКлавиши:");
+        for action in Action::ALL {
+            content.push_str(&format!("\n  {} - {}", shortcuts::format_shortcut(self.shortcuts.get(action)), action.label()));
+        }
+        content.push_str("\n  Стрелки - сдвиг холста\n  +/- - масштаб относительно центра\n  Ctrl+Z - отменить\n  Ctrl+Y, Ctrl+Shift+Z - повторить\n  Esc - снять выделение");
+        for overlay in Overlay::ALL {
+            content.push_str(&format!("\n  {:?} - {}", overlay.key(), overlay.label()));
+        }
+        self.text_windows.push(TextWindow::new(format!("Справка: {}", self.selected_algorithm), content).kind("Справка"));
+    }
+
+    // Меню "Окна": все текстовые окна сеанса, включая закрытые, и действия над ними разом
+    fn windows_menu_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.checkbox(&mut self.layout.windows_docked, "Вкладками в нижней панели").changed() {
+            ui.close_menu();
+        }
+        ui.separator();
+        if self.text_windows.is_empty() {
+            ui.label("Окон пока нет");
+            return;
+        }
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for win in &mut self.text_windows {
+                ui.checkbox(&mut win.is_open, &win.title);
+            }
+        });
+        ui.separator();
+        if ui.button("Открыть все").clicked() {
+            self.text_windows.iter_mut().for_each(|w| w.is_open = true);
+            ui.close_menu();
+        }
+        if ui.button("Закрыть все").clicked() {
+            self.text_windows.iter_mut().for_each(|w| w.is_open = false);
+            ui.close_menu();
+        }
+        if !self.layout.windows_docked && ui.button("Расположить плиткой").clicked() {
+            self.tile_windows = true;
+            ui.close_menu();
+        }
+        if ui.button("Забыть закрытые").clicked() {
+            self.text_windows.retain(|w| w.is_open);
+            ui.close_menu();
+        }
+    }
+
+    // Ключи сохранённых положений открытых окон: вид и номер среди открытых окон того же вида,
+    // иначе положения одинаковых окон затирали бы друг друга
+    fn window_position_keys(windows: &[TextWindow]) -> Vec<Option<String>> {
+        let mut ordinals = std::collections::HashMap::new();
+        windows
+            .iter()
+            .map(|win| {
+                if !win.is_open {
+                    return None;
+                }
+                let ordinal = ordinals.entry(win.kind.as_str()).or_insert(0);
+                *ordinal += 1;
+                Some(format!("{}#{}", win.kind, *ordinal - 1))
+            })
+            .collect()
+    }
+
+    // Забывает самые старые закрытые окна сверх MAX_CLOSED_WINDOWS
+    fn trim_closed_windows(&mut self) {
+        let closed = self.text_windows.iter().filter(|w| !w.is_open).count();
+        let mut excess = closed.saturating_sub(MAX_CLOSED_WINDOWS);
+        self.text_windows.retain(|w| {
+            let forget = excess > 0 && !w.is_open;
+            if forget {
+                excess -= 1;
+            }
+            !forget
+        });
+    }
+
+    fn draw_text_windows(&mut self, ctx: &egui::Context) {
+        if self.layout.windows_docked {
+            self.draw_results_panel(ctx);
+            return;
+        }
+        // Плитка: открытые окна по сетке почти квадратной формы в свободной части экрана
+        let open_count = self.text_windows.iter().filter(|w| w.is_open).count();
+        let area = ctx.available_rect().shrink(8.0);
+        let columns = (open_count as f32).sqrt().ceil().max(1.0);
+        let rows = (open_count as f32 / columns).ceil().max(1.0);
+        let cell = Vec2::new(area.width() / columns, area.height() / rows);
+        let mut tile_index = 0.0;
+
+        let mut messages = Vec::new();
+        let position_keys = Self::window_position_keys(&self.text_windows);
+        for (win, position_key) in self.text_windows.iter_mut().zip(position_keys) {
+            let Some(position_key) = position_key else { continue };
+            let mut is_open = win.is_open;
+            let mut window = egui::Window::new(&win.title)
+                .id(egui::Id::new(("text_window", win.id)))
+                .open(&mut is_open)
+                .vscroll(true);
+            if let Some(&position) = self.layout.window_positions.get(&position_key) {
+                window = window.default_pos(position);
+            }
+            if self.tile_windows {
+                let min = area.min + Vec2::new(cell.x * (tile_index % columns), cell.y * (tile_index / columns).floor());
+                // Заголовок окна не входит в размер содержимого
+                window = window.current_pos(min).fixed_size(cell - Vec2::new(16.0, 40.0));
+                tile_index += 1.0;
+            }
+            let shown = window.show(ctx, |ui| Self::text_window_ui(ui, win, &mut messages));
+            if let Some(shown) = shown {
+                self.layout.window_positions.insert(position_key, shown.response.rect.min.round());
+            }
+            win.is_open = is_open;
+        }
+        self.tile_windows = false;
+        for message in messages {
+            self.text_windows.push(TextWindow::new("Экспорт".to_string(), message));
+        }
+    }
+
+    // Нижняя панель с вкладками открытых окон; только что созданное окно становится выбранной вкладкой
+    fn draw_results_panel(&mut self, ctx: &egui::Context) {
+        let newest = self.text_windows.iter().filter(|w| w.is_open).map(|w| w.id).max();
+        if newest > self.newest_tab {
+            self.active_tab = newest;
+        }
+        self.newest_tab = self.newest_tab.max(newest);
+        if !self.text_windows.iter().any(|w| w.is_open && Some(w.id) == self.active_tab) {
+            self.active_tab = self.text_windows.iter().rev().find(|w| w.is_open).map(|w| w.id);
+        }
+        let Some(active) = self.active_tab else { return };
+
+        let mut messages = Vec::new();
+        let mut clear = false;
+        egui::TopBottomPanel::bottom("results_panel").resizable(true).default_height(200.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Очистить все").clicked() {
+                    clear = true;
+                }
+                ui.separator();
+                egui::ScrollArea::horizontal().show(ui, |ui| {
+                    for win in self.text_windows.iter_mut().filter(|w| w.is_open) {
+                        if ui.selectable_label(win.id == active, &win.title).clicked() {
+                            self.active_tab = Some(win.id);
+                        }
+                        if ui.small_button("✖").on_hover_text("Закрыть вкладку").clicked() {
+                            win.is_open = false;
+                        }
+                    }
+                });
+            });
+            ui.separator();
+            if let Some(win) = self.text_windows.iter().find(|w| w.id == active) {
+                egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| Self::text_window_ui(ui, win, &mut messages));
+            }
+        });
+        if clear {
+            self.text_windows.clear();
+        }
+        for message in messages {
+            self.text_windows.push(TextWindow::new("Экспорт".to_string(), message));
+        }
+    }
+
+    // Содержимое текстового окна: кнопки экспорта таблицы и сам текст
+    fn text_window_ui(ui: &mut egui::Ui, win: &TextWindow, messages: &mut Vec<String>) {
+        if let Some(table) = &win.table {
+            ui.horizontal(|ui| {
+                if ui.button("Экспорт CSV").clicked() {
+                    messages.push(Self::write_file(&format!("{}.csv", table.name), table.to_csv()));
+                }
+                if ui.button("Экспорт Markdown").clicked() {
+                    messages.push(Self::write_file(&format!("{}.md", table.name), table.to_markdown()));
+                }
+            });
+            ui.separator();
+        }
+        if win.monospace {
+            ui.monospace(&win.content);
+        } else {
+            ui.label(&win.content);
+        }
+    }
+
+    // Запись файла с текстовым сообщением о результате для пользователя
+    fn write_file(path: &str, content: impl AsRef<[u8]>) -> String {
+        match std::fs::write(path, content) {
+            Ok(()) => format!("Сохранено в файл {}", path),
+            Err(e) => format!("Не удалось сохранить {}: {}", path, e),
+        }
+    }
+
+    fn viewport(&self) -> &Viewport {
+        &self.viewports[self.view]
+    }
+
+    fn viewport_mut(&mut self) -> &mut Viewport {
+        &mut self.viewports[self.view]
+    }
+
+    fn zoom(&self) -> Vec2 {
+        self.viewport().zoom()
+    }
+
+    fn show_zoom_ui(&mut self, ui: &mut egui::Ui) {
+        let keep_aspect = self.keep_aspect;
+        let range = self.zoom_min..=self.zoom_max;
+        let view = &mut self.viewports[self.view];
+        ui.horizontal(|ui| {
+            let mut zoom = view.zoom();
+            let changed_x = ui.add(egui::DragValue::new(&mut zoom.x).speed(0.05).clamp_range(range.clone()).prefix("Масштаб X: ")).changed();
+            let changed_y = ui.add(egui::DragValue::new(&mut zoom.y).speed(0.05).clamp_range(range).prefix("Y: ")).changed();
+            if changed_x || changed_y {
+                view.set_axis_zoom(zoom, keep_aspect);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.keep_aspect, "Сохранять пропорции").changed() && self.keep_aspect {
+                let view = self.viewport_mut();
+                view.zoom_y = view.zoom_x;
+            }
+            ui.checkbox(&mut self.smooth_zoom, "Плавно");
+            ui.checkbox(&mut self.split_view, "Два вида")
+                .on_hover_text("Холст делится пополам; у каждой половины свои сдвиг и масштаб, клавиши действуют на вид под курсором");
+            if ui.add_enabled(self.last_run_algorithm.is_some(), egui::Button::new("Вписать результат"))
+                .on_hover_text("Клавиша F")
+                .clicked()
+            {
+                self.fit_view_to_result();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Пределы масштаба:");
+            ui.add(egui::DragValue::new(&mut self.zoom_min).speed(0.01).clamp_range(0.01..=1.0));
+            ui.label("…");
+            ui.add(egui::DragValue::new(&mut self.zoom_max).speed(1.0).clamp_range(10.0..=10000.0));
+        });
+    }
+
+    fn zoom_limits(&self) -> (f32, f32) {
+        (self.zoom_min, self.zoom_max)
+    }
+
+    fn get_transform(&self, rect: &Rect) -> egui::emath::RectTransform {
+        self.viewport().transform(rect)
+    }
+
+    fn center_view_on(&mut self, point: Pos2) {
+        self.viewport_mut().center_on(point);
+    }
+
+    fn fit_view_to(&mut self, target: Rect) {
+        let (keep_aspect, limits) = (self.keep_aspect, self.zoom_limits());
+        self.viewport_mut().fit_to(target, keep_aspect, limits);
+    }
+
+    fn fit_view_to_clip_window(&mut self) {
+        match self.params.clip_mode {
+            ClipMode::Off => {}
+            ClipMode::Rectangle => self.fit_view_to(self.params.clip_window),
+            ClipMode::Polygon => self.fit_view_to(Rect::from_points(&self.params.clip_polygon)),
+        }
+    }
+
+    // Вписывает в холст пиксели последнего результата вместе с точками идеальной фигуры
+    fn fit_view_to_result(&mut self) {
+        let mut bounds = Rect::NOTHING;
+        for record in self.render_result.records() {
+            bounds = bounds.union(Rect::from_center_size(record.pos(), Vec2::splat(1.0)));
+        }
+        if self.show_ideal && self.last_run_algorithm == Some(self.selected_algorithm) {
+            for point in self.editable_points() {
+                bounds.extend_with(point);
+            }
+        }
+        if bounds.is_positive() {
+            self.fit_view_to(bounds);
+        }
+    }
+
+    // Окно назначения клавиш: после нажатия на кнопку действия следующее нажатие клавиши становится его сочетанием
+    fn draw_shortcuts_window(&mut self, ctx: &egui::Context) {
+        if let Some(action) = self.rebinding {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                ctx.input_mut(|i| i.consume_key(modifiers, key));
+                if key == egui::Key::Escape {
+                    self.rebinding = None;
+                } else if let Some(shortcut) = shortcuts::capture_shortcut(modifiers, key) {
+                    self.rebinding = None;
+                    self.shortcuts_message = Some(match self.shortcuts.conflict(shortcut, action) {
+                        Some(other) => format!("{} уже назначено действию \"{}\"", shortcuts::format_shortcut(shortcut), other.label()),
+                        None => {
+                            self.shortcuts.set(action, shortcut);
+                            self.save_shortcuts()
+                        }
+                    });
+                } else {
+                    self.shortcuts_message = Some(format!("Клавишу {} назначить нельзя", key.name()));
+                }
+            }
+        }
+
+        let mut open = self.shortcuts_open;
+        egui::Window::new("Клавиши").open(&mut open).resizable(false).show(ctx, |ui| {
+            egui::Grid::new("shortcuts_grid").num_columns(2).striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.label());
+                    let text = if self.rebinding == Some(action) {
+                        "нажмите клавишу...".to_string()
+                    } else {
+                        shortcuts::format_shortcut(self.shortcuts.get(action))
+                    };
+                    if ui.button(text).clicked() {
+                        self.rebinding = Some(action);
+                        self.shortcuts_message = None;
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.button("По умолчанию").clicked() {
+                self.shortcuts = Shortcuts::default();
+                self.rebinding = None;
+                self.shortcuts_message = Some(self.save_shortcuts());
+            }
+            if let Some(message) = &self.shortcuts_message {
+                ui.label(message);
+            }
+        });
+        if !open {
+            self.rebinding = None;
+        }
+        self.shortcuts_open = open;
+    }
+
+    fn save_shortcuts(&self) -> String {
+        match self.shortcuts.save(shortcuts::SHORTCUTS_PATH) {
+            Ok(()) => format!("Сохранено в файл {}", shortcuts::SHORTCUTS_PATH),
+            Err(e) => e,
+        }
+    }
+
+    // Состояние панели записывается, когда пользователь отпустил мышь, а не на каждом кадре перетаскивания границы
+    fn save_layout(&mut self, ctx: &egui::Context) {
+        if self.layout == self.saved_layout || ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        self.saved_layout = self.layout.clone();
+        // Не удалось записать - при следующем запуске просто будет раскладка по умолчанию
+        let _ = std::fs::write(LAYOUT_PATH, self.layout.to_text());
+    }
+
+    fn draw_goto_window(&mut self, ctx: &egui::Context) {
+        let Some(goto) = &mut self.goto else { return };
+        let mut submit = false;
+        egui::Window::new("Перейти к точке")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 40.0))
+            .show(ctx, |ui| {
+                ui.label("Координаты: \"x, y\" или \"x y\"");
+                let edit = ui.text_edit_singleline(&mut goto.text);
+                if goto.request_focus {
+                    edit.request_focus();
+                    goto.request_focus = false;
+                }
+                submit = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.checkbox(&mut goto.place_point, "Поставить сюда активную точку");
+                if let Some(error) = &goto.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                submit |= ui.button("Перейти").clicked();
+            });
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.goto = None;
+            return;
+        }
+        if !submit {
+            return;
+        }
+        let Some(target) = parse_point(&goto.text) else {
+            goto.error = Some(format!("Не удалось разобрать \"{}\"", goto.text.trim()));
+            goto.request_focus = true;
+            return;
+        };
+        let place_point = goto.place_point;
+        self.goto = None;
+
+        self.center_view_on(target);
+        let target = self.snap(target);
+        if place_point {
+            let active = self.active_point;
+            let points = self.editable_points_mut();
+            let index = active.min(points.len().saturating_sub(1));
+            if let Some(point) = points.into_iter().nth(index) {
+                *point = target;
+            }
+        }
+    }
+
+    // Точки, которые можно перетаскивать на холсте для выбранного алгоритма
+    fn editable_points_mut(&mut self) -> Vec<&mut Pos2> {
+        let params = &mut self.params;
+        match self.selected_algorithm {
+            algo if algo.is_line() => {
+                let mut points = vec![&mut params.p1, &mut params.p2];
+                if params.clip_mode == ClipMode::Polygon {
+                    points.extend(params.clip_polygon.iter_mut());
+                }
+                points
+            }
+            Algorithm::BresenhamCircle | Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle => vec![&mut params.circle_center],
+            Algorithm::ThreePointCircle => params.circle_through.iter_mut().collect(),
+            Algorithm::Annulus | Algorithm::PieSlice => vec![&mut params.circle_center],
+            Algorithm::RoundedRect => vec![&mut params.rrect_center],
+            Algorithm::EllipseAA => vec![&mut params.ellipse_center],
+            Algorithm::MidpointParabola => vec![&mut params.parabola_vertex],
+            Algorithm::Hyperbola => vec![&mut params.hyperbola_center],
+            Algorithm::Hermite => vec![&mut params.hermite_p0, &mut params.hermite_h0, &mut params.hermite_p1, &mut params.hermite_h1],
+            algo if algo.is_control_curve() => params.castle_points.iter_mut().collect(),
+            Algorithm::PolygonFillAA => params.polygon_points.iter_mut().collect(),
+            Algorithm::ThickPolyline => params.thick_points.iter_mut().collect(),
+            Algorithm::TriangleFillAA => params.triangle_points.iter_mut().collect(),
+            Algorithm::FloodFill | Algorithm::BoundaryFill => {
+                let mut points = vec![&mut params.fill_seed];
+                match params.fill_boundary {
+                    FillBoundary::Circle => points.push(&mut params.circle_center),
+                    FillBoundary::Polygon => points.extend(params.polygon_points.iter_mut()),
+                }
+                points
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn editable_points(&mut self) -> Vec<Pos2> {
+        self.editable_points_mut().into_iter().map(|p| *p).collect()
+    }
+
+    // Индекс ближайшей к курсору точки в пределах радиуса захвата
+    fn hit_test_point(&mut self, to_screen: egui::emath::RectTransform, screen_pos: Pos2) -> Option<usize> {
+        self.editable_points()
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i, (to_screen * *p).distance(screen_pos)))
+            .filter(|&(_, d)| d <= POINT_GRAB_RADIUS)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    // Края выделения, которые находятся под курсором (для изменения размера)
+    fn selection_edges_at(&self, to_screen: egui::emath::RectTransform, screen_pos: Pos2) -> Option<CanvasDrag> {
+        let sel = to_screen.transform_rect(self.selection?);
+        let near = |a: f32, b: f32| (a - b).abs() <= POINT_GRAB_RADIUS;
+        let inside_x = screen_pos.x >= sel.min.x - POINT_GRAB_RADIUS && screen_pos.x <= sel.max.x + POINT_GRAB_RADIUS;
+        let inside_y = screen_pos.y >= sel.min.y - POINT_GRAB_RADIUS && screen_pos.y <= sel.max.y + POINT_GRAB_RADIUS;
+        let left = inside_y && near(screen_pos.x, sel.min.x);
+        let right = inside_y && near(screen_pos.x, sel.max.x);
+        let top = inside_x && near(screen_pos.y, sel.min.y);
+        let bottom = inside_x && near(screen_pos.y, sel.max.y);
+        (left || right || top || bottom).then_some(CanvasDrag::SelectionEdges { left, right, top, bottom })
+    }
+
+    // Пиксели результата, центры которых попадают в выделение
+    fn selected_records(&self) -> Vec<PixelRecord> {
+        let Some(sel) = self.selection else { return Vec::new() };
+        self.render_result.records().filter(|r| sel.contains(r.pos())).collect()
+    }
+
+    // Привязка к сетке; целочисленный ввод включает её для точек на холсте
+    fn snapping(&self) -> bool {
+        self.snap_to_grid || self.integer_input
+    }
+
+    // Точка, поставленная на холсте, с учётом привязки к сетке
+    fn snap(&self, point: Pos2) -> Pos2 {
+        if self.snapping() { point.round() } else { point }
+    }
+
+    fn show_ruler_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.ruler_mode, "Линейка").on_hover_text("Перетаскивание на холсте измеряет отрезок. Esc - убрать").changed() && self.ruler_mode {
+                self.selection_mode = false;
+            }
+            if ui.add_enabled(self.ruler.is_some(), egui::Button::new("Убрать")).clicked() {
+                self.ruler = None;
+            }
+        });
+        if let Some((start, end)) = self.ruler {
+            ui.label(ruler_text(start, end));
+        }
+    }
+
+    // Габариты текущего результата и прошлых результатов (onion skin) в логических координатах
+    fn result_bounds(&self) -> Vec<Rect> {
+        std::iter::once(&self.render_result)
+            .chain(self.onion_skins.iter().map(|past| &past.result))
+            .filter_map(|result| {
+                let bounds = result.records().fold(Rect::NOTHING, |acc, r| acc.union(Rect::from_center_size(r.pos(), Vec2::splat(1.0))));
+                bounds.is_positive().then_some(bounds)
+            })
+            .collect()
+    }
+
+    // Перевод логических координат в миникарту в правом нижнем углу холста. Карта охватывает
+    // все результаты и видимую область с запасом; пропорции логической области совпадают с картой
+    fn minimap_transform(&self, canvas: Rect) -> Option<egui::emath::RectTransform> {
+        if !self.show_minimap {
+            return None;
+        }
+        let screen = Rect::from_min_size(canvas.right_bottom() - MINIMAP_SIZE - Vec2::splat(MINIMAP_MARGIN), MINIMAP_SIZE);
+        let world = match self.canvas_drag {
+            CanvasDrag::Minimap(world) => world,
+            _ => {
+                let bounds = self.result_bounds().into_iter().fold(self.viewport().bounds(), Rect::union);
+                let scale = (bounds.width() / screen.width()).max(bounds.height() / screen.height()) * 1.1;
+                Rect::from_center_size(bounds.center(), screen.size() * scale)
+            }
+        };
+        Some(egui::emath::RectTransform::from_to(world, screen))
+    }
+
+    // Миникарта: габариты результатов (серые) и видимая область (синяя рамка)
+    fn draw_minimap(&self, painter: &Painter, canvas: Rect) {
+        let Some(minimap) = self.minimap_transform(canvas) else { return };
+        let painter = painter.with_clip_rect(*minimap.to());
+        painter.rect_filled(*minimap.to(), Rounding::none(), Color32::from_rgba_unmultiplied(255, 255, 255, 220));
+        for bounds in self.result_bounds() {
+            // Даже маленький результат остаётся заметен на карте
+            let rect = minimap.transform_rect(bounds);
+            let rect = Rect::from_center_size(rect.center(), rect.size().max(Vec2::splat(3.0)));
+            painter.rect_filled(rect, Rounding::none(), Color32::from_gray(150));
+        }
+        painter.rect_stroke(minimap.transform_rect(self.viewport().bounds()), Rounding::none(), Stroke::new(1.5, Color32::from_rgb(0, 120, 255)));
+        painter.rect_stroke(*minimap.to(), Rounding::none(), Stroke::new(1.0, Color32::GRAY));
+    }
+
+    // Горизонтальная и вертикальная направляющие через курсор (с учётом привязки) и его координаты
+    fn draw_crosshair(&self, painter: &Painter, canvas: Rect, to_screen: egui::emath::RectTransform, hover_pos: Pos2) {
+        let logic = self.snap(to_screen.inverse() * hover_pos);
+        let pos = to_screen * logic;
+        let stroke = Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 120, 255, 140));
+        painter.line_segment([Pos2::new(canvas.min.x, pos.y), Pos2::new(canvas.max.x, pos.y)], stroke);
+        painter.line_segment([Pos2::new(pos.x, canvas.min.y), Pos2::new(pos.x, canvas.max.y)], stroke);
+        let text = if self.snapping() {
+            format!("({}, {})", logic.x as i32, logic.y as i32)
+        } else {
+            format!("({:.1}, {:.1})", logic.x, logic.y)
+        };
+        painter.text(pos + Vec2::new(6.0, -6.0), Align2::LEFT_BOTTOM, text, egui::FontId::monospace(12.0), Color32::from_rgb(0, 90, 200));
+    }
+
+    // Измеряемый отрезок с концами и подписью у конца
+    fn draw_ruler(&self, painter: &Painter, to_screen: egui::emath::RectTransform) {
+        let Some((start, end)) = self.ruler else { return };
+        let color = Color32::from_rgb(0, 140, 140);
+        let (a, b) = (to_screen * start, to_screen * end);
+        painter.line_segment([a, b], Stroke::new(1.5, color));
+        painter.circle_filled(a, 3.0, color);
+        painter.circle_filled(b, 3.0, color);
+        painter.text(b + Vec2::new(8.0, 8.0), Align2::LEFT_TOP, ruler_text(start, end), egui::FontId::proportional(13.0), color);
+    }
+
+    fn show_selection_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.checkbox(&mut self.selection_mode, "Режим выделения").on_hover_text("Также: перетаскивание с Shift. Esc - снять выделение").changed() && self.selection_mode {
+            self.ruler_mode = false;
+        }
+        let Some(sel) = self.selection else { return };
+        let records = self.selected_records();
+        ui.label(format!(
+            "Выделение X: [{:.1}:{:.1}], Y: [{:.1}:{:.1}], пикселей: {}",
+            sel.min.x, sel.max.x, sel.min.y, sel.max.y, records.len()
+        ));
+        let mut table = Table::new("selection", &["x", "y", "Интенсивность"]);
+        for r in &records {
+            table.push_row(vec![format!("{}", r.x), format!("{}", r.y), format!("{:.3}", r.intensity)]);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Список пикселей").clicked() {
+                self.text_windows.push(TextWindow::with_table("Выделенные пиксели".to_string(), table.clone()));
+            }
+            // Изображения обрезаются по выделению: в них попадают все пиксели, центры которых внутри
+            if ui.button("Экспортировать выделение").on_hover_text("selection.csv, selection.png и selection.svg").clicked() {
+                let min = (sel.min.x.ceil() as i32, sel.min.y.ceil() as i32);
+                let max = (sel.max.x.floor() as i32, sel.max.y.floor() as i32);
+                let messages = [
+                    Self::write_file("selection.csv", table.to_csv()),
+                    match PixelGrid::cropped(records.iter().copied(), min, max) {
+                        Ok(grid) => Self::write_file("selection.png", report::grid_to_png(&grid)),
+                        Err(e) => e,
+                    },
+                    Self::write_file("selection.svg", report::pixels_to_svg(records.into_iter())),
+                ];
+                self.text_windows.push(TextWindow::new("Экспорт".to_string(), messages.join("\n")));
+            }
+            if ui.button("Вписать в окно").clicked() {
+                self.fit_view_to(sel);
+            }
+            if ui.button("Снять").clicked() {
+                self.selection = None;
+            }
+        });
+    }
+
+    // Кольцо вокруг точки под курсором или перетаскиваемой точки
+    fn draw_point_highlight(&mut self, painter: &Painter, to_screen: egui::emath::RectTransform) {
+        let index = match self.canvas_drag {
+            CanvasDrag::Point(index) => Some(index),
+            CanvasDrag::None => self.hovered_point.or(self.hovered_row),
+            _ => None,
+        };
+        if let Some(point) = index.and_then(|i| self.editable_points().get(i).copied()) {
+            let center = to_screen * point;
+            painter.circle_filled(center, POINT_GRAB_RADIUS, Color32::from_rgba_unmultiplied(255, 0, 0, 40));
+            painter.circle_stroke(center, POINT_GRAB_RADIUS, Stroke::new(1.5, Color32::RED));
+        }
+    }
+
+    fn draw_selection(&self, painter: &Painter, to_screen: egui::emath::RectTransform) {
+        if let Some(sel) = self.selection {
+            let rect = to_screen.transform_rect(sel);
+            painter.rect_filled(rect, Rounding::none(), Color32::from_rgba_unmultiplied(0, 120, 255, 30));
+            painter.rect_stroke(rect, Rounding::none(), Stroke::new(1.0, Color32::from_rgb(0, 120, 255)));
+        }
+    }
+
+    // Правка параметров или выбор алгоритма становятся одним действием в истории, когда мышь отпущена
+    // и никакое поле не редактируется: перетаскивание значения или точки не дробится на кадры
+    // Целочисленный ввод округляет правку тогда же - один раз, когда она закончена
+    fn record_history(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|i| i.pointer.any_down()) && ctx.memory(|m| m.focus().is_none()) {
+            if self.integer_input && self.params != self.history_base.params {
+                self.params.snap_to_integers();
+            }
+            self.commit_history();
+        }
+    }
+
+    fn commit_history(&mut self) {
+        if self.history_base.algorithm != self.selected_algorithm || self.history_base.params != self.params {
+            let current = Snapshot { algorithm: self.selected_algorithm, params: self.params.clone() };
+            let previous = std::mem::replace(&mut self.history_base, current);
+            self.history.push(previous);
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.selected_algorithm = snapshot.algorithm;
+        self.params = snapshot.params.clone();
+        self.history_base = snapshot;
+    }
+
+    // Незаписанная правка (например, в поле, которое ещё в фокусе) сначала записывается, и отмена откатывает её
+    fn undo(&mut self) {
+        self.commit_history();
+        if let Some(previous) = self.history.undo(self.history_base.clone()) {
+            self.restore(previous);
+        }
+    }
+
+    fn redo(&mut self) {
+        self.commit_history();
+        if let Some(next) = self.history.redo(self.history_base.clone()) {
+            self.restore(next);
+        }
+    }
+
+    // focused - вид под курсором: только он получает клавиши
+    fn handle_canvas_input(&mut self, response: &egui::Response, focused: bool) {
+        let to_screen = self.get_transform(&response.rect);
+
+        // Пока на холсте два и более пальца, первый палец не тащит холст как мышь
+        let pinching = self.handle_pinch(response);
+
+        // Начало перетаскивания: новый замер линейкой, край выделения, новое выделение (Shift или режим выделения),
+        // точка под курсором или, по умолчанию, сдвиг холста.
+        // Всё перетаскивание точки от нажатия до отпускания - одно действие в истории.
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            let press_pos = response.ctx.input(|i| i.pointer.press_origin()).unwrap_or_default();
+            let shift = response.ctx.input(|i| i.modifiers.shift);
+            let minimap = self.minimap_transform(response.rect).filter(|m| m.to().contains(press_pos));
+            self.canvas_drag = if let Some(minimap) = minimap {
+                self.center_view_on(minimap.inverse() * press_pos);
+                CanvasDrag::Minimap(*minimap.from())
+            } else if self.ruler_mode {
+                let start = self.snap(to_screen.inverse() * press_pos);
+                self.ruler = Some((start, start));
+                CanvasDrag::Measure
+            } else if let Some(edges) = self.selection_edges_at(to_screen, press_pos) {
+                edges
+            } else if shift || self.selection_mode {
+                CanvasDrag::SelectionNew(to_screen.inverse() * press_pos)
+            } else if let Some(index) = self.hit_test_point(to_screen, press_pos) {
+                self.active_point = index;
+                CanvasDrag::Point(index)
+            } else {
+                CanvasDrag::Pan
+            };
+        }
+        if response.dragged_by(egui::PointerButton::Primary) && !pinching {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let logic_pos = to_screen.inverse() * pointer;
+                match self.canvas_drag {
+                    CanvasDrag::Point(index) => {
+                        let target = self.snap(logic_pos);
+                        if let Some(point) = self.editable_points_mut().into_iter().nth(index) {
+                            *point = target;
+                        }
+                    }
+                    CanvasDrag::SelectionNew(anchor) => {
+                        self.selection = Some(Rect::from_two_pos(anchor, logic_pos));
+                    }
+                    CanvasDrag::Minimap(_) => {
+                        if let Some(minimap) = self.minimap_transform(response.rect) {
+                            self.center_view_on(minimap.inverse() * pointer);
+                        }
+                    }
+                    CanvasDrag::Measure => {
+                        let end = self.snap(logic_pos);
+                        if let Some((_, ruler_end)) = &mut self.ruler {
+                            *ruler_end = end;
+                        }
+                    }
+                    CanvasDrag::SelectionEdges { left, right, top, bottom } => {
+                        if let Some(sel) = &mut self.selection {
+                            if left { sel.min.x = logic_pos.x; }
+                            if right { sel.max.x = logic_pos.x; }
+                            if top { sel.min.y = logic_pos.y; }
+                            if bottom { sel.max.y = logic_pos.y; }
+                            *sel = Rect::from_two_pos(sel.min, sel.max);
+                        }
+                    }
+                    CanvasDrag::Pan | CanvasDrag::None => self.viewport_mut().pan_by(response.drag_delta()),
+                }
+            }
+        }
+        if response.drag_released() {
+            self.canvas_drag = CanvasDrag::None;
+        }
+        if response.clicked_by(egui::PointerButton::Primary) {
+            if let (Some(minimap), Some(pointer)) = (self.minimap_transform(response.rect), response.interact_pointer_pos()) {
+                if minimap.to().contains(pointer) {
+                    self.center_view_on(minimap.inverse() * pointer);
+                }
+            }
+        }
+
+        // Подсветка точки под курсором, пока ничего не перетаскивается
+        let over_minimap = response.hover_pos().zip(self.minimap_transform(response.rect)).is_some_and(|(pos, m)| m.to().contains(pos));
+        self.hovered_point = match (self.canvas_drag, response.hover_pos().filter(|_| !over_minimap)) {
+            (CanvasDrag::None, Some(pos)) if !self.selection_mode && !self.ruler_mode => self.hit_test_point(to_screen, pos),
+            _ => None,
+        };
+        if matches!(self.canvas_drag, CanvasDrag::Point(_)) {
+            response.ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+        } else if self.hovered_point.is_some() {
+            response.ctx.set_cursor_icon(egui::CursorIcon::Grab);
+        }
+        if focused {
+            self.handle_canvas_keys(&response.ctx);
+        }
+
+        if response.hovered() {
+            let scroll = response.ctx.input(|i| i.scroll_delta);
+            if scroll.y != 0.0 {
+                // Колесо масштабирует обе оси, сохраняя их текущее соотношение; точка под курсором остаётся на месте
+                let anchor = response.hover_pos().map_or(Vec2::ZERO, |pos| pos - response.rect.center());
+                let limits = self.zoom_limits();
+                self.viewport_mut().zoom_by((scroll.y * WHEEL_ZOOM_RATE).exp(), anchor, limits);
+            }
+        }
+    }
+
+    // Сдвиг двумя пальцами и масштаб щипком: логическая точка под центром жеста остаётся под ним,
+    // как под курсором при масштабе колесом. Один палец двигает холст обычным перетаскиванием
+    fn handle_pinch(&mut self, response: &egui::Response) -> bool {
+        let Some(touch) = response.ctx.multi_touch().filter(|t| t.num_touches >= 2) else {
+            self.touch_center = None;
+            return false;
+        };
+        // При разделённом экране жест относится к виду, в котором он начался
+        if !response.rect.contains(touch.start_pos) {
+            return false;
+        }
+        let center = match self.touch_center {
+            Some((start, center)) if start == touch.start_time => center,
+            _ => touch.start_pos,
+        };
+        let logic = self.get_transform(&response.rect).inverse() * center;
+        let new_center = center + touch.translation_delta;
+        let limits = self.zoom_limits();
+        let view = self.viewport_mut();
+        view.animation = None;
+        let factor = touch.zoom_delta.min(limits.1 / view.zoom().max_elem()).max(limits.0 / view.min_zoom());
+        view.zoom_x *= factor;
+        view.zoom_y *= factor;
+        view.pan += new_center - view.transform(&response.rect) * logic;
+        self.touch_center = Some((touch.start_time, new_center));
+        true
+    }
+
+    fn handle_canvas_keys(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.selection = None;
+            self.ruler = None;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+            self.undo();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y) || i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)) {
+            self.redo();
+        }
+        // Назначаемые действия проверяются раньше наложений, чтобы переназначенная клавиша не переключала наложение
+        if !ctx.wants_keyboard_input() && self.rebinding.is_none() {
+            for action in Action::ALL {
+                let shortcut = self.shortcuts.get(action);
+                if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                    self.perform(action);
+                }
+            }
+            self.handle_view_keys(ctx);
+            for overlay in Overlay::ALL {
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, overlay.key())) {
+                    let flag = self.overlay_mut(overlay);
+                    *flag = !*flag;
+                }
+            }
+        }
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Run => self.run_algorithm(),
+            Action::ToggleLog => self.log_enabled = !self.log_enabled,
+            Action::Help => self.show_help(),
+            Action::NextAlgorithm => self.cycle_algorithm(1),
+            Action::PreviousAlgorithm => self.cycle_algorithm(-1),
+            Action::ResetView => self.reset_view(),
+            Action::FitView => self.fit_view_to_result(),
+            Action::Goto => {
+                if self.goto.is_none() {
+                    self.goto = Some(GotoPopup { text: String::new(), place_point: false, error: None, request_focus: true });
+                }
+            }
+        }
+    }
+
+    // Переход по списку алгоритмов в порядке ALGORITHM_MENU, по кругу
+    fn cycle_algorithm(&mut self, step: isize) {
+        let order: Vec<Algorithm> = ALGORITHM_MENU.iter().flat_map(|group| group.iter().map(|&(algorithm, _)| algorithm)).collect();
+        let current = order.iter().position(|&a| a == self.selected_algorithm).unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(order.len() as isize) as usize;
+        self.selected_algorithm = order[next];
+    }
+
+    fn reset_view(&mut self) {
+        let view = self.viewport_mut();
+        *view = Viewport { size: view.size, ..Viewport::default() };
+    }
+
+    // Стрелки сдвигают холст, +/- масштабируют относительно центра холста
+    fn handle_view_keys(&mut self, ctx: &egui::Context) {
+        let arrows = [
+            (egui::Key::ArrowLeft, Vec2::new(KEY_PAN_STEP, 0.0)),
+            (egui::Key::ArrowRight, Vec2::new(-KEY_PAN_STEP, 0.0)),
+            (egui::Key::ArrowUp, Vec2::new(0.0, KEY_PAN_STEP)),
+            (egui::Key::ArrowDown, Vec2::new(0.0, -KEY_PAN_STEP)),
+        ];
+        for (key, delta) in arrows {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key)) {
+                self.viewport_mut().pan_by(delta);
+            }
+        }
+
+        let mut factor = 1.0;
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::PlusEquals)) {
+            factor *= KEY_ZOOM_FACTOR;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Minus)) {
+            factor /= KEY_ZOOM_FACTOR;
+        }
+        if factor != 1.0 {
+            let limits = self.zoom_limits();
+            self.viewport_mut().zoom_by(factor, Vec2::ZERO, limits);
+        }
+    }
+
+    fn calculate_grid_step(zoom: f32) -> GridSteps {
+        // Цель: иметь линии сетки примерно каждые 80 пикселей
+        let target_step_pixels = 80.0;
+        let logical_step = target_step_pixels / zoom;
+
+        // Находим ближайшую степень 10
+        let power_of_10 = 10.0_f32.powf(logical_step.log10().floor());
+
+        // Нормализуем шаг к диапазону [1, 10)
         let normalized_step = logical_step / power_of_10;
 
         // Выбираем "красивый" шаг из 1, 2, 5
@@ -328,46 +4035,104 @@ impl GraphicsLabApp {
             10.0
         };
 
-        beautiful_step * power_of_10
-    }
-
-    fn draw_grid(&self, painter: &Painter, rect: &Rect, from_screen: egui::emath::RectTransform) {
-        let to_screen = self.get_transform(rect);
+        let major = beautiful_step * power_of_10;
+        // Шаг 2 делим пополам, шаги 1, 5 и 10 - на пять частей
+        let minor = if beautiful_step == 2.0 { major / 2.0 } else { major / 5.0 };
 
-        // 1. Фон
-        painter.rect_filled(rect.clone(), Rounding::none(), Color32::WHITE);
+        // Дополнительные линии плавно проявляются по мере роста их экранного шага от 4 до 16 точек
+        let minor_pixels = minor * zoom;
+        let minor_alpha = if minor < 1.0 { 0.0 } else { ((minor_pixels - 4.0) / 12.0).clamp(0.0, 1.0) };
 
-        // Получаем видимые границы в логических координатах
-        let top_left = from_screen * rect.min;
-        let bottom_right = from_screen * rect.max;
+        GridSteps { major, minor, minor_alpha }
+    }
 
-        // 2. Сетка
-        let grid_step = self.calculate_grid_step();
-        let grid_color = Color32::from_rgb(230, 230, 250); // Очень светло-синий
-        let grid_stroke = Stroke::new(1.0, grid_color);
+    // Линии сетки с шагом step_x/step_y, проходящие по границам пикселей (со смещением 0.5)
+    fn draw_grid_lines(painter: &Painter, to_screen: egui::emath::RectTransform, top_left: Pos2, bottom_right: Pos2, step_x: f32, step_y: f32, stroke: Stroke) {
+        // Слишком плотную сетку не рисуем вовсе
+        if (bottom_right.x - top_left.x) / step_x > MAX_GRID_LINES || (bottom_right.y - top_left.y) / step_y > MAX_GRID_LINES {
+            return;
+        }
 
         // Вертикальные линии (со смещением 0.5)
-        let x_start = ((top_left.x - 0.5) / grid_step).floor() * grid_step + 0.5;
+        let x_start = ((top_left.x - 0.5) / step_x).floor() * step_x + 0.5;
         let mut x = x_start;
         while x < bottom_right.x {
             let line_start = to_screen * Pos2::new(x, top_left.y);
             let line_end = to_screen * Pos2::new(x, bottom_right.y);
-            painter.line_segment([line_start, line_end], grid_stroke);
-            x += grid_step;
+            painter.line_segment([line_start, line_end], stroke);
+            x += step_x;
         }
 
         // Горизонтальные линии (со смещением 0.5)
-        let y_start = ((top_left.y - 0.5) / grid_step).floor() * grid_step + 0.5;
+        let y_start = ((top_left.y - 0.5) / step_y).floor() * step_y + 0.5;
         let mut y = y_start;
         while y < bottom_right.y {
             let line_start = to_screen * Pos2::new(top_left.x, y);
             let line_end = to_screen * Pos2::new(bottom_right.x, y);
-            painter.line_segment([line_start, line_end], grid_stroke);
-            y += grid_step;
+            painter.line_segment([line_start, line_end], stroke);
+            y += step_y;
+        }
+    }
+
+    // Шахматный фон: клетки совпадают с логическими пикселями, при малом масштабе укрупняются
+    // в 2^k раз, чтобы клетка оставалась не меньше CHECKER_MIN_CELL экранных точек
+    fn draw_checkerboard(&self, painter: &Painter, to_screen: egui::emath::RectTransform, top_left: Pos2, bottom_right: Pos2) {
+        let min_zoom = self.viewport().min_zoom();
+        let mut cell = 1;
+        while (cell as f32) * min_zoom < CHECKER_MIN_CELL {
+            cell *= 2;
+        }
+        let dark = Color32::from_gray(204);
+        let first_x = ((top_left.x + 0.5) / cell as f32).floor() as i32;
+        let last_x = ((bottom_right.x + 0.5) / cell as f32).floor() as i32;
+        let first_y = ((top_left.y + 0.5) / cell as f32).floor() as i32;
+        let last_y = ((bottom_right.y + 0.5) / cell as f32).floor() as i32;
+        for cy in first_y..=last_y {
+            for cx in first_x..=last_x {
+                if (cx + cy).rem_euclid(2) == 0 {
+                    continue;
+                }
+                let min = Pos2::new((cx * cell) as f32 - 0.5, (cy * cell) as f32 - 0.5);
+                let logic = Rect::from_min_size(min, Vec2::splat(cell as f32));
+                painter.rect_filled(to_screen.transform_rect(logic), Rounding::none(), dark);
+            }
+        }
+    }
+
+    fn draw_grid(&self, painter: &Painter, rect: &Rect, from_screen: egui::emath::RectTransform) {
+        let to_screen = self.get_transform(rect);
+
+        // 1. Фон
+        painter.rect_filled(*rect, Rounding::none(), Color32::WHITE);
+
+        // Получаем видимые границы в логических координатах
+        let top_left = from_screen * rect.min;
+        let bottom_right = from_screen * rect.max;
+
+        if self.checkerboard_enabled {
+            self.draw_checkerboard(painter, to_screen, top_left, bottom_right);
+        }
+
+        // 2. Сетка: сначала дополнительные линии, поверх - основные
+        if self.show_grid {
+            let steps_x = Self::calculate_grid_step(self.viewport().zoom_x);
+            let steps_y = Self::calculate_grid_step(self.viewport().zoom_y);
+            let minor_alpha = steps_x.minor_alpha.min(steps_y.minor_alpha);
+            if minor_alpha > 0.0 {
+                let minor_color = Color32::from_rgb(240, 240, 252).gamma_multiply(minor_alpha);
+                Self::draw_grid_lines(painter, to_screen, top_left, bottom_right, steps_x.minor, steps_y.minor, Stroke::new(1.0, minor_color));
+            }
+            let grid_color = Color32::from_rgb(225, 225, 248); // Очень светло-синий
+            Self::draw_grid_lines(painter, to_screen, top_left, bottom_right, steps_x.major, steps_y.major, Stroke::new(1.0, grid_color));
+        }
+
+        if !self.show_axes {
+            return;
         }
 
         // 3. Оси координат
-        let axes_color = Color32::from_rgb(200, 200, 255);
+        // Оси темнее и толще основных линий сетки при любом масштабе
+        let axes_color = Color32::from_rgb(150, 150, 230);
         let axes_stroke = Stroke::new(1.5, axes_color);
 
         // Ось Y (линия x = 0)
@@ -375,84 +4140,474 @@ impl GraphicsLabApp {
         let y_axis_end = to_screen * Pos2::new(0.0, bottom_right.y);
         painter.line_segment([y_axis_start, y_axis_end], axes_stroke);
 
-        // Ось X (линия y = 0)
-        let x_axis_start = to_screen * Pos2::new(top_left.x, 0.0);
-        let x_axis_end = to_screen * Pos2::new(bottom_right.x, 0.0);
-        painter.line_segment([x_axis_start, x_axis_end], axes_stroke);
+        // Ось X (линия y = 0)
+        let x_axis_start = to_screen * Pos2::new(top_left.x, 0.0);
+        let x_axis_end = to_screen * Pos2::new(bottom_right.x, 0.0);
+        painter.line_segment([x_axis_start, x_axis_end], axes_stroke);
+
+        if !self.show_axis_labels {
+            return;
+        }
+
+        // --- НОВЫЙ БЛОК ---
+        // 4. Подписи осей
+        let text_color = Color32::DARK_GRAY;
+        let font_id = egui::FontId::proportional(16.0);
+
+        // Подпись оси Y
+        // Позиция: x=0, y - вверху экрана. Смещаем немного вправо и вниз для читаемости.
+        let y_label_pos = to_screen * Pos2::new(0.0, top_left.y);
+        painter.text(
+            y_label_pos + Vec2::new(10.0, 10.0), // Отступ в экранных координатах
+            Align2::LEFT_TOP, // Якорь - левый верхний угол текста
+            "Y",
+            font_id.clone(),
+            text_color,
+        );
+
+        // Подпись оси X
+        // Позиция: y=0, x - справа на экране. Смещаем немного влево и вверх для читаемости.
+        let x_label_pos = to_screen * Pos2::new(bottom_right.x, 0.0);
+        painter.text(
+            x_label_pos + Vec2::new(-10.0, -10.0), // Отступ в экранных координатах
+            Align2::RIGHT_BOTTOM, // Якорь - правый нижний угол текста
+            "X",
+            font_id,
+            text_color,
+        );
+    }
+
+    // В impl GraphicsLabApp в src/app.rs
+
+    fn draw_results(&self, painter: &Painter, to_screen: egui::emath::RectTransform) {
+        let visible = if self.playback_enabled { self.playback_step } else { usize::MAX };
+
+        // Предыдущие результаты рисуются под текущим, от старых к новым
+        if self.onion_enabled {
+            for (i, past) in self.onion_skins.iter().enumerate().rev() {
+                let opacity = ONION_OPACITY[i.min(ONION_OPACITY.len() - 1)];
+                self.draw_render_result(painter, to_screen, &past.result, past.colors.pixel, usize::MAX, opacity);
+                let (shape, markers) = self.overlay_colors(past.colors.ideal.gamma_multiply(opacity));
+                Self::draw_ideal_overlay(painter, to_screen, past.algorithm, &past.params, shape, markers);
+            }
+        }
+
+        self.draw_render_result(painter, to_screen, &self.render_result, self.result_colors.pixel, visible, 1.0);
+        if let (true, Some((reflections, _))) = (self.mirror_quadrants, &self.mirrored) {
+            self.draw_render_result(painter, to_screen, reflections, self.result_colors.pixel, usize::MAX, 1.0);
+        }
+
+        // Наложение результатов "гонки" цветами участников
+        if let Some(race) = &self.race {
+            for row in race.rows.iter().filter(|r| r.visible) {
+                for record in row.result.records() {
+                    let rect = Rect::from_center_size(to_screen * record.pos(), self.zoom());
+                    painter.rect_filled(rect, Rounding::none(), row.color.gamma_multiply(0.6 * record.intensity));
+                }
+            }
+        }
+
+        if let Some(algo) = self.last_run_algorithm {
+            let (shape, markers) = self.overlay_colors(self.result_colors.ideal);
+            Self::draw_ideal_overlay(painter, to_screen, algo, &self.last_run_params, shape, markers);
+        }
+
+        if let Some(algo) = self.last_run_algorithm.filter(|_| self.show_subpixel) {
+            self.draw_subpixel_overlay(painter, to_screen, algo, visible);
+        }
+
+        // Опорная ломаная кривой (Безье или B-сплайна)
+        if self.show_control_polygon && self.selected_algorithm.is_control_curve() {
+            let points = self.params.castle_points.iter().map(|p| to_screen * *p).collect();
+            let stroke = Stroke::new(1.0, Color32::GRAY);
+            painter.add(if self.params.curve_closed { egui::Shape::closed_line(points, stroke) } else { egui::Shape::line(points, stroke) });
+        }
+
+        // Разность с запомненным результатом: добавленные - зелёная рамка, исчезнувшие - красная
+        if let Some((added, removed)) = &self.stash_delta {
+            let cells = added.iter().map(|p| (p, Color32::from_rgb(0, 170, 0))).chain(removed.iter().map(|p| (p, Color32::RED)));
+            for (&(x, y), color) in cells {
+                let rect = Rect::from_center_size(to_screen * Pos2::new(x as f32, y as f32), self.zoom());
+                painter.rect_stroke(rect, Rounding::none(), Stroke::new(2.0, color));
+            }
+        }
+
+        // Разность правила ромба с Брезенхемом: только ромб - синяя рамка, только Брезенхем - оранжевая
+        if let Some((only_diamond, only_bresenham)) = &self.diamond_delta {
+            let cells = only_diamond.iter().map(|p| (p, Color32::from_rgb(40, 110, 255))).chain(only_bresenham.iter().map(|p| (p, Color32::from_rgb(255, 140, 0))));
+            for (&(x, y), color) in cells {
+                let rect = Rect::from_center_size(to_screen * Pos2::new(x as f32, y as f32), self.zoom() * 0.8);
+                painter.rect_stroke(rect, Rounding::none(), Stroke::new(2.0, color));
+            }
+        }
+
+        // Эталонное покрытие - уменьшенные квадраты внутри пикселей, чтобы были видны и растровые пиксели
+        if let (true, Some((coverage, _))) = (self.show_circle_coverage, &self.circle_coverage) {
+            for &(x, y, c) in coverage {
+                let rect = Rect::from_center_size(to_screen * Pos2::new(x as f32, y as f32), self.zoom() * 0.6);
+                painter.rect_filled(rect, Rounding::none(), Color32::from_rgb(0, 160, 80).gamma_multiply(c));
+            }
+        }
+
+        if self.show_dominant_axis && self.last_run_algorithm.is_some_and(|algo| algo.is_line()) {
+            self.draw_dominant_axis(painter, to_screen);
+        }
+    }
+
+    // Результат и его отражения относительно осей: сначала исходные пиксели, затем x -> -x, y -> -y и оба сразу
+    fn mirrored_records(&self) -> Vec<PixelRecord> {
+        [(1, 1), (-1, 1), (1, -1), (-1, -1)]
+            .into_iter()
+            .flat_map(|(sx, sy)| self.render_result.records().map(move |r| PixelRecord { x: r.x * sx, y: r.y * sy, ..r }))
+            .collect()
+    }
+
+    fn update_mirrored(&mut self) {
+        if !self.mirror_quadrants || self.mirrored.is_some() {
+            return;
+        }
+        let mut records = self.mirrored_records();
+        let unique = algo::unique_count(records.iter().copied());
+        let reflections = records.split_off(self.render_result.len());
+        self.mirrored = Some((RenderResult::Rich(reflections), unique));
+    }
+
+    fn draw_render_result(&self, painter: &Painter, to_screen: egui::emath::RectTransform, result: &RenderResult, color: Color32, visible: usize, opacity: f32) {
+        if self.downsample_enabled && self.viewport().min_zoom() < DOWNSAMPLE_ZOOM_THRESHOLD {
+            let cells = result.records().take(visible).map(|r| (r.pos(), self.display_alpha(r.intensity) * opacity));
+            Self::draw_downsampled(painter, to_screen, cells, color);
+        } else {
+            self.draw_pixels(painter, to_screen, result, color, visible, opacity);
+        }
+    }
+
+    // Центр каждого пикселя результата и точка идеальной фигуры в его столбце или строке, соединённые
+    // отрезком: длина отрезка - ошибка, которую алгоритм округлил (или перевёл в интенсивность у сглаженных)
+    fn draw_subpixel_overlay(&self, painter: &Painter, to_screen: egui::emath::RectTransform, algo: Algorithm, visible: usize) {
+        if self.viewport().min_zoom() < SUBPIXEL_MIN_ZOOM {
+            return;
+        }
+        // Идеальная фигура - та, по которой получен результат, а не текущие поля ввода
+        let params = &self.last_run_params;
+        let sample = |pixel: Pos2| {
+            if algo.is_line() {
+                algo::line_sample(params.p1, params.p2, pixel)
+            } else if Algorithm::CIRCLES.contains(&algo) {
+                algo::circle_sample(params.circle_center, params.circle_radius, pixel)
+            } else {
+                None
+            }
+        };
+        let center_color = Color32::from_rgb(0, 120, 255);
+        let sample_color = self.result_colors.ideal;
+        for record in self.render_result.records().take(visible) {
+            let center = to_screen * record.pos();
+            painter.circle_filled(center, 2.0, center_color);
+            if let Some(ideal) = sample(record.pos()) {
+                let ideal = to_screen * ideal;
+                painter.line_segment([center, ideal], Stroke::new(1.0, center_color.gamma_multiply(0.6)));
+                painter.circle_stroke(ideal, 2.5, Stroke::new(1.0, sample_color));
+            }
+        }
+    }
+
+    // Группирует пиксели по ячейкам экрана и рисует по одному прямоугольнику на занятую ячейку
+    fn draw_downsampled(painter: &Painter, to_screen: egui::emath::RectTransform, pixels: impl Iterator<Item = (Pos2, f32)>, color: Color32) {
+        let mut cells: std::collections::HashMap<(i32, i32), f32> = std::collections::HashMap::new();
+        for (p, intensity) in pixels {
+            let screen_pos = to_screen * p;
+            let cell = (screen_pos.x.floor() as i32, screen_pos.y.floor() as i32);
+            let acc = cells.entry(cell).or_insert(0.0);
+            *acc = (*acc + intensity).min(1.0);
+        }
+        for ((cx, cy), intensity) in cells {
+            let min = Pos2::new(cx as f32, cy as f32);
+            painter.rect_filled(Rect::from_min_size(min, Vec2::splat(1.0)), Rounding::none(), color.gamma_multiply(intensity));
+        }
+    }
+
+    // Интенсивность (доля покрытия) -> непрозрачность. Цвета смешиваются в гамма-пространстве, поэтому
+    // при alpha = I пиксель с покрытием 0.5 выглядит заметно темнее половины. С коррекцией линейная
+    // яркость 1 - I кодируется как (1 - I)^(1/γ), и перепады яркости вдоль сглаженной линии выравниваются
+    fn display_alpha(&self, intensity: f32) -> f32 {
+        if self.gamma_correct {
+            1.0 - (1.0 - intensity.clamp(0.0, 1.0)).powf(1.0 / self.gamma)
+        } else {
+            intensity
+        }
+    }
+
+    fn draw_pixels(&self, painter: &Painter, to_screen: egui::emath::RectTransform, result: &RenderResult, pixel_color: Color32, visible: usize, opacity: f32) {
+        let pixel_size = Vec2::splat(1.0);
+        let rich = matches!(result, RenderResult::Rich(_));
 
-        // --- НОВЫЙ БЛОК ---
-        // 4. Подписи осей
-        let text_color = Color32::DARK_GRAY;
-        let font_id = egui::FontId::proportional(16.0);
+        // Диапазон value для градиента: минимум - синий, максимум - красный
+        let value_range = (self.color_by_value && rich).then(|| {
+            result.records().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), r| (lo.min(r.value), hi.max(r.value)))
+        });
 
-        // Подпись оси Y
-        // Позиция: x=0, y - вверху экрана. Смещаем немного вправо и вниз для читаемости.
-        let y_label_pos = to_screen * Pos2::new(0.0, top_left.y);
-        painter.text(
-            y_label_pos + Vec2::new(10.0, 10.0), // Отступ в экранных координатах
-            Align2::LEFT_TOP, // Якорь - левый верхний угол текста
-            "Y",
-            font_id.clone(),
-            text_color,
-        );
+        let border = (self.pixel_borders && self.viewport().min_zoom() >= self.pixel_border_zoom)
+            .then(|| Stroke::new(1.0, self.pixel_border_color.gamma_multiply(opacity)));
 
-        // Подпись оси X
-        // Позиция: y=0, x - справа на экране. Смещаем немного влево и вверх для читаемости.
-        let x_label_pos = to_screen * Pos2::new(bottom_right.x, 0.0);
-        painter.text(
-            x_label_pos + Vec2::new(-10.0, -10.0), // Отступ в экранных координатах
-            Align2::RIGHT_BOTTOM, // Якорь - правый нижний угол текста
-            "X",
-            font_id,
-            text_color,
-        );
+        // Отрисовка растеризованных пикселей
+        for record in result.records().take(visible) {
+            let screen_pos = to_screen * record.pos();
+            let alpha = self.display_alpha(record.intensity) * opacity;
+            let color = if let Some((lo, hi)) = value_range {
+                let t = if hi > lo { (record.value - lo) / (hi - lo) } else { 0.5 };
+                let gradient = Color32::from_rgb((255.0 * t) as u8, 0, (255.0 * (1.0 - t)) as u8);
+                gradient.gamma_multiply(alpha)
+            } else if self.color_by_tag && rich {
+                TAG_COLORS[record.tag as usize % TAG_COLORS.len()].gamma_multiply(alpha)
+            } else {
+                // Градиент от прозрачного до выбранного цвета
+                pixel_color.gamma_multiply(alpha)
+            };
+            let cell = Rect::from_center_size(screen_pos, pixel_size * self.zoom());
+            painter.rect_filled(cell, Rounding::none(), color);
+            if let Some(border) = border {
+                painter.rect_stroke(cell.shrink(0.5), Rounding::none(), border);
+            }
+        }
     }
 
-    // В impl GraphicsLabApp в src/app.rs
+    // Стрелка вдоль оси, по которой идёт цикл в step_by_step/dda: выбор по условию |dx| > |dy|
+    // для отрезка последнего запуска
+    fn draw_dominant_axis(&self, painter: &Painter, to_screen: egui::emath::RectTransform) {
+        let (p1, p2) = (self.last_run_params.p1, self.last_run_params.p2);
+        let d = p2 - p1;
+        let color = Color32::from_rgb(0, 150, 80);
+        let (start, end, label) = if d.x.abs() > d.y.abs() {
+            let (from, to) = if p1.x < p2.x { (p1, p2) } else { (p2, p1) };
+            (from, Pos2::new(to.x, from.y), "Ось X: |dx| > |dy|")
+        } else {
+            let (from, to) = if p1.y < p2.y { (p1, p2) } else { (p2, p1) };
+            (from, Pos2::new(from.x, to.y), "Ось Y: |dy| ≥ |dx|")
+        };
+        let start = to_screen * start;
+        let end = to_screen * end;
+        painter.arrow(start, end - start, Stroke::new(2.0, color));
+        painter.text(end + Vec2::new(6.0, 6.0), Align2::LEFT_TOP, label, egui::FontId::proportional(14.0), color);
+    }
 
-    fn draw_results(&self, painter: &Painter, to_screen: egui::emath::RectTransform) {
-        let pixel_size = Vec2::splat(1.0);
+    // Идеальный эллипс (окружность) в логических координатах, аппроксимированный ломаной
+    fn draw_ideal_ellipse(painter: &Painter, to_screen: egui::emath::RectTransform, center: Pos2, rx: f32, ry: f32, color: Color32) {
+        let segments = 128;
+        let points: Vec<Pos2> = (0..segments)
+            .map(|i| {
+                let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+                to_screen * (center + Vec2::new(rx * angle.cos(), ry * angle.sin()))
+            })
+            .collect();
+        painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, color)));
+    }
 
-        // Отрисовка растеризованных пикселей
-        match &self.render_result {
-            RenderResult::Pixels(pixels) => {
-                for &p in pixels {
-                    let screen_pos = to_screen * p;
-                    painter.rect_filled(Rect::from_center_size(screen_pos, pixel_size * self.zoom), Rounding::none(), Color32::BLACK); // БЫЛО WHITE
+    // Цвета идеальной фигуры и маркеров; выключенное наложение рисуется прозрачным
+    fn overlay_colors(&self, color: Color32) -> (Color32, Color32) {
+        let visible = |on: bool| if on { color } else { Color32::TRANSPARENT };
+        (visible(self.show_ideal), visible(self.show_markers))
+    }
+
+    fn draw_ideal_overlay(painter: &Painter, to_screen: egui::emath::RectTransform, algo: Algorithm, params: &AppParameters, color: Color32, marker_color: Color32) {
+        if color == Color32::TRANSPARENT && marker_color == Color32::TRANSPARENT {
+            return;
+        }
+        // Отрисовка "идеальных" линий и маркеров
+        match algo {
+            Algorithm::StepByStep | Algorithm::DDA | Algorithm::BresenhamLine | Algorithm::WuLine | Algorithm::BresenhamAA | Algorithm::DdaAA | Algorithm::StepByStepAA | Algorithm::BrushLine | Algorithm::SsaaLine | Algorithm::GuptaSproull | Algorithm::MidpointLine | Algorithm::DiamondExit | Algorithm::SymmetricBresenham | Algorithm::RunSliceLine | Algorithm::DoubleStepLine => {
+                let p1 = to_screen * params.p1;
+                let p2 = to_screen * params.p2;
+                // Линию делаем темно-серой, чтобы она отличалась от черных пикселей
+                painter.line_segment([p1, p2], Stroke::new(1.0, color)); // БЫЛ синий
+                painter.circle_filled(p1, 4.0, marker_color);
+                painter.circle_filled(p2, 4.0, marker_color);
+                match params.clip_mode {
+                    ClipMode::Off => {}
+                    ClipMode::Rectangle => {
+                        let window = Rect::from_two_pos(to_screen * params.clip_window.min, to_screen * params.clip_window.max);
+                        painter.rect_stroke(window, Rounding::none(), Stroke::new(1.5, color));
+                    }
+                    ClipMode::Polygon => {
+                        let points = params.clip_polygon.iter().map(|p| to_screen * *p).collect();
+                        painter.add(egui::Shape::closed_line(points, Stroke::new(1.5, color)));
+                    }
                 }
             }
-            RenderResult::Antialiased(pixels) => {
-                for &(x, y, intensity) in pixels {
-                    let p = Pos2::new(x as f32, y as f32);
-                    let screen_pos = to_screen * p;
-                    // Используем from_black_alpha для градиента от прозрачного до черного
-                    let color = Color32::from_black_alpha((intensity * 255.0) as u8); // БЫЛО from_white_alpha
-                    painter.rect_filled(Rect::from_center_size(screen_pos, pixel_size * self.zoom), Rounding::none(), color);
+            Algorithm::BresenhamCircle | Algorithm::WuCircle | Algorithm::PolarCircle | Algorithm::SqrtCircle | Algorithm::RotationCircle => {
+                // При разных масштабах по осям окружность на экране становится эллипсом
+                let radius = params.circle_radius;
+                Self::draw_ideal_ellipse(painter, to_screen, params.circle_center, radius, radius, color);
+            }
+            Algorithm::RoundedRect => {
+                let half = Vec2::new(params.rrect_width.abs(), params.rrect_height.abs()) / 2.0;
+                let r = params.rrect_radius.abs().min(half.x).min(half.y);
+                let inner = half - Vec2::splat(r);
+                // Четыре дуги по четверти окружности против часовой стрелки, начиная с правого верхнего угла
+                let segments = 16;
+                let mut points = Vec::new();
+                for (corner, (sx, sy)) in [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)].into_iter().enumerate() {
+                    let arc_center = params.rrect_center + Vec2::new(sx * inner.x, sy * inner.y);
+                    for i in 0..=segments {
+                        let angle = (corner as f32 + i as f32 / segments as f32) * std::f32::consts::FRAC_PI_2;
+                        points.push(to_screen * (arc_center + r * Vec2::angled(angle)));
+                    }
                 }
+                painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, color)));
             }
-            RenderResult::None => {}
-        }
-
-        // Отрисовка "идеальных" линий и маркеров
-        if let Some(algo) = self.last_run_algorithm {
-            match algo {
-                Algorithm::StepByStep | Algorithm::DDA | Algorithm::BresenhamLine | Algorithm::WuLine | Algorithm::BresenhamAA | Algorithm::DdaAA | Algorithm::StepByStepAA => {
-                    let p1 = to_screen * self.params.p1;
-                    let p2 = to_screen * self.params.p2;
-                    // Линию делаем темно-серой, чтобы она отличалась от черных пикселей
-                    painter.line_segment([p1, p2], Stroke::new(1.0, Color32::RED)); // БЫЛ синий
-                    painter.circle_filled(p1, 4.0, Color32::RED);
-                    painter.circle_filled(p2, 4.0, Color32::RED);
+            Algorithm::ThickPolyline => {
+                let points: Vec<Pos2> = params.thick_points.iter().map(|p| to_screen * *p).collect();
+                painter.add(egui::Shape::line(points.clone(), Stroke::new(1.0, color)));
+                for p in points {
+                    painter.circle_filled(p, 3.0, marker_color);
+                }
+            }
+            Algorithm::PieSlice => {
+                let (center, r) = (params.circle_center, params.circle_radius.abs());
+                let mut sweep = (params.sector_end - params.sector_start).rem_euclid(360.0);
+                if sweep == 0.0 {
+                    sweep = 360.0;
+                }
+                let segments = 64;
+                let mut points = vec![to_screen * center];
+                points.extend((0..=segments).map(|i| {
+                    let angle = (params.sector_start + sweep * i as f32 / segments as f32).to_radians();
+                    to_screen * (center + r * Vec2::angled(angle))
+                }));
+                painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, color)));
+            }
+            Algorithm::Annulus => {
+                for radius in [params.ring_inner, params.ring_outer] {
+                    Self::draw_ideal_ellipse(painter, to_screen, params.circle_center, radius, radius, color);
+                }
+            }
+            Algorithm::ThreePointCircle => {
+                let [a, b, c] = params.circle_through;
+                if let Some((center, radius)) = algo::circumcircle(a, b, c) {
+                    Self::draw_ideal_ellipse(painter, to_screen, center, radius, radius, color);
+                    painter.circle_stroke(to_screen * center, 3.0, Stroke::new(1.0, marker_color));
+                }
+                for p in params.circle_through {
+                    painter.circle_filled(to_screen * p, 4.0, marker_color);
+                }
+            }
+            Algorithm::EllipseAA => {
+                Self::draw_ideal_ellipse(painter, to_screen, params.ellipse_center, params.ellipse_rx, params.ellipse_ry, color);
+            }
+            Algorithm::MidpointParabola => {
+                let (vertex, a, extent) = (params.parabola_vertex, params.parabola_a, params.parabola_extent.abs());
+                let segments = 128;
+                let points = (0..=segments)
+                    .map(|i| {
+                        let x = -extent + 2.0 * extent * i as f32 / segments as f32;
+                        to_screen * Pos2::new(vertex.x + x, vertex.y + a * x * x)
+                    })
+                    .collect();
+                painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+            }
+            Algorithm::Hyperbola => {
+                // x = a ch t, y = b sh t; t ограничено видимой высотой
+                let (center, a, b) = (params.hyperbola_center, params.hyperbola_a, params.hyperbola_b);
+                let bounds = visible_rect(painter, to_screen);
+                let y_max = (bounds.min.y - center.y).abs().max((bounds.max.y - center.y).abs());
+                let t_max = (y_max / b).asinh();
+                let segments = 128;
+                let branches: &[f32] = if params.hyperbola_both { &[1.0, -1.0] } else { &[1.0] };
+                for &side in branches {
+                    let points = (0..=segments)
+                        .map(|i| {
+                            let t = -t_max + 2.0 * t_max * i as f32 / segments as f32;
+                            to_screen * (center + Vec2::new(side * a * t.cosh(), b * t.sinh()))
+                        })
+                        .collect();
+                    painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+                }
+            }
+            Algorithm::CastlePitway | Algorithm::BezierSubdivision | Algorithm::BSpline | Algorithm::CatmullRom | Algorithm::RationalBezier => {
+                // Порядок точек задаёт форму кривой, поэтому они подписаны номерами
+                for (i, p) in params.castle_points.iter().enumerate() {
+                    let center = to_screen * *p;
+                    painter.circle_filled(center, 4.0, marker_color);
+                    painter.text(center + Vec2::new(6.0, -6.0), Align2::LEFT_BOTTOM, format!("P{}", i), egui::FontId::proportional(12.0), marker_color);
+                }
+                if params.curve_closed {
+                    let points = params.castle_points.iter().map(|p| to_screen * *p).collect();
+                    painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, color.linear_multiply(0.4))));
+                }
+            }
+            Algorithm::Hermite => {
+                let (m0, m1) = params.hermite_tangents();
+                let samples = 128;
+                let points = (0..=samples)
+                    .map(|i| to_screen * algo::hermite_point(params.hermite_p0, m0, params.hermite_p1, m1, i as f32 / samples as f32))
+                    .collect();
+                painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+                for (point, handle) in [(params.hermite_p0, params.hermite_h0), (params.hermite_p1, params.hermite_h1)] {
+                    let (start, end) = (to_screen * point, to_screen * handle);
+                    painter.arrow(start, end - start, Stroke::new(1.0, color));
+                    painter.circle_filled(start, 4.0, marker_color);
+                    painter.circle_stroke(end, 4.0, Stroke::new(1.0, marker_color));
+                }
+            }
+            Algorithm::PolygonFillAA => {
+                let points: Vec<Pos2> = params.polygon_points.iter().map(|p| to_screen * *p).collect();
+                painter.add(egui::Shape::closed_line(points.clone(), Stroke::new(1.0, color)));
+                for p in points {
+                    painter.circle_filled(p, 4.0, marker_color);
                 }
-                Algorithm::BresenhamCircle => {
-                    let center = to_screen * self.params.circle_center;
-                    let radius = self.params.circle_radius * self.zoom;
-                    painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::RED)); // БЫЛ синий
+            }
+            Algorithm::TriangleFillAA => {
+                let points: Vec<Pos2> = params.triangle_points.iter().map(|p| to_screen * *p).collect();
+                painter.add(egui::Shape::closed_line(points.clone(), Stroke::new(1.0, color)));
+                for p in points {
+                    painter.circle_filled(p, 4.0, marker_color);
+                }
+            }
+            Algorithm::FloodFill | Algorithm::BoundaryFill => {
+                match params.fill_boundary {
+                    FillBoundary::Circle => {
+                        let radius = params.circle_radius;
+                        Self::draw_ideal_ellipse(painter, to_screen, params.circle_center, radius, radius, color);
+                    }
+                    FillBoundary::Polygon => {
+                        let points = params.polygon_points.iter().map(|p| to_screen * *p).collect();
+                        painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, color)));
+                    }
                 }
-                Algorithm::CastlePitway => {
-                    for p in &self.params.castle_points {
-                        painter.circle_filled(to_screen * *p, 4.0, Color32::RED);
+                let seed = to_screen * params.fill_seed;
+                painter.circle_stroke(seed, 5.0, Stroke::new(1.5, marker_color));
+                painter.circle_filled(seed, 2.0, marker_color);
+            }
+            Algorithm::Line3D => {
+                let (a, b) = (params.projection.project(params.line3d_p1).0, params.projection.project(params.line3d_p2).0);
+                painter.line_segment([to_screen * a, to_screen * b], Stroke::new(1.0, color));
+                painter.circle_filled(to_screen * a, 3.0, marker_color);
+                painter.circle_filled(to_screen * b, 3.0, marker_color);
+            }
+            Algorithm::FunctionPlot => {
+                if let Ok(f) = Expr::parse_in(&params.function_expr, "x") {
+                    // Ломаная по четыре выборки на единицу, разорванная там же, где и растровый график
+                    let bounds = visible_rect(painter, to_screen);
+                    let samples = (bounds.width() * 4.0).ceil().max(1.0) as usize;
+                    let mut segment: Vec<Pos2> = Vec::new();
+                    let mut previous = f32::NAN;
+                    for i in 0..=samples {
+                        let x = bounds.min.x + bounds.width() * i as f32 / samples as f32;
+                        let y = f.eval(x);
+                        if !y.is_finite() || (y - previous).abs() > bounds.height() {
+                            painter.add(egui::Shape::line(std::mem::take(&mut segment), Stroke::new(1.0, color)));
+                        }
+                        if y.is_finite() {
+                            segment.push(to_screen * Pos2::new(x, y));
+                        }
+                        previous = y;
                     }
+                    painter.add(egui::Shape::line(segment, Stroke::new(1.0, color)));
+                }
+            }
+            Algorithm::CustomParametric => {
+                if let Ok(samples) = sample_custom_curve(params) {
+                    let points = samples.into_iter().filter(|p| p.x.is_finite() && p.y.is_finite()).map(|p| to_screen * p).collect();
+                    painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
                 }
             }
         }
@@ -463,13 +4618,41 @@ impl GraphicsLabApp {
             // Координаты мыши
             if let Some(pos) = hover_pos {
                 let logic_pos = from_screen * pos;
-                ui.label(format!("Курсор: ({:.1}, {:.1})", logic_pos.x, logic_pos.y));
+                if self.snapping() {
+                    let snapped = logic_pos.round();
+                    ui.label(format!("Курсор: ({:.1}, {:.1}) → ({}, {})", logic_pos.x, logic_pos.y, snapped.x as i32, snapped.y as i32));
+                } else {
+                    ui.label(format!("Курсор: ({:.1}, {:.1})", logic_pos.x, logic_pos.y));
+                }
             } else {
                 ui.label("Курсор: (N/A)");
             }
 
             ui.separator();
 
+            // Масштаб в процентах: 100% - логический пиксель равен экранной точке
+            let view = self.viewport();
+            if view.zoom_x == view.zoom_y {
+                ui.label(format!("Масштаб: {:.0}%", view.zoom_x * 100.0));
+            } else {
+                ui.label(format!("Масштаб: {:.0}% × {:.0}%", view.zoom_x * 100.0, view.zoom_y * 100.0));
+            }
+            if self.show_grid {
+                let (step_x, step_y) = (Self::calculate_grid_step(view.zoom_x).major, Self::calculate_grid_step(view.zoom_y).major);
+                if step_x == step_y {
+                    ui.label(format!("Шаг сетки: {}", step_x));
+                } else {
+                    ui.label(format!("Шаг сетки: {} × {}", step_x, step_y));
+                }
+            }
+
+            ui.separator();
+
+            if let Some((start, end)) = self.ruler {
+                ui.label(format!("Линейка: {}", ruler_text(start, end)));
+                ui.separator();
+            }
+
             // Последний метод
             if let Some(algo) = self.last_run_algorithm {
                 ui.label(format!("Метод: {}", algo));
@@ -477,10 +4660,839 @@ impl GraphicsLabApp {
 
             ui.separator();
 
+            ui.label(format!("Округление: {}", self.rounding_mode));
+
+            ui.separator();
+
+            ui.label(format!("Отрисовка: {:.2} мс", self.last_draw_time.as_secs_f64() * 1000.0));
+
+            ui.separator();
+
+            if let Some(iterations) = self.last_iterations {
+                ui.label(format!("Итераций: {}", iterations));
+            }
+            if !matches!(self.render_result, RenderResult::None) {
+                ui.label(format!("Пикселей: {}", self.render_result.len()));
+                // У сглаженного результата сумма интенсивностей - "площадь" фигуры в пикселях
+                if self.render_result.records().any(|r| r.intensity < 1.0) {
+                    let total: f32 = self.render_result.records().map(|r| r.intensity).sum();
+                    ui.label(format!("Σ интенсивности: {:.2}", total));
+                }
+            }
+            if self.last_iterations.is_some() || !matches!(self.render_result, RenderResult::None) {
+                ui.separator();
+            }
+
+            if let Some(algo) = self.last_run_algorithm.filter(|a| a.is_line()) {
+                let density = algo::pixels_per_unit_length(self.render_result.records(), self.last_run_params.p1, self.last_run_params.p2);
+                ui.label(format!("Пикселей на единицу длины ({}): {:.3}", algo, density));
+                ui.separator();
+            }
+
+            if self.selected_algorithm == Algorithm::CustomParametric {
+                if let Err(e) = sample_custom_curve(&self.params) {
+                    ui.colored_label(Color32::RED, e);
+                    ui.separator();
+                }
+            }
+            if self.selected_algorithm == Algorithm::FunctionPlot {
+                if let Err(e) = Expr::parse_in(&self.params.function_expr, "x") {
+                    ui.colored_label(Color32::RED, format!("y(x): {}", e));
+                    ui.separator();
+                }
+            }
+
+            if self.selected_algorithm == Algorithm::ThreePointCircle {
+                let [a, b, c] = self.params.circle_through;
+                match algo::circumcircle(a, b, c) {
+                    Some((center, radius)) => ui.label(format!("Центр: ({:.2}, {:.2}), R = {:.2}", center.x, center.y, radius)),
+                    None => ui.colored_label(Color32::RED, "Точки на одной прямой"),
+                };
+                ui.separator();
+            }
+
+            if let (true, Some((_, error))) = (self.show_circle_coverage, &self.circle_coverage) {
+                ui.label(format!("Ошибка покрытия: {:.3}", error));
+                ui.separator();
+            }
+
             // Видимый диапазон
             let top_left = from_screen * rect.min;
             let bottom_right = from_screen * rect.max;
             ui.label(format!("X: [{:.1}:{:.1}], Y: [{:.1}:{:.1}]", top_left.x, bottom_right.x, top_left.y, bottom_right.y));
         });
     }
-}
\ No newline at end of file
+}
+
+// Разбор логической координаты: "x, y", "x; y" или "x y"
+fn parse_point(text: &str) -> Option<Pos2> {
+    let mut parts = text
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f32>().ok().filter(|v| v.is_finite()));
+    let x = parts.next()??;
+    let y = parts.next()??;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Pos2::new(x, y))
+}
+
+// Наибольший угол между соседними вершинами звёздного многоугольника. Пока он меньше π, центр
+// лежит внутри; запас до π не даёт рёбрам пройти вплотную к центру
+const MAX_STAR_GAP: f32 = 0.9 * std::f32::consts::PI;
+const STAR_ATTEMPTS: usize = 1000;
+
+// Многоугольник, звёздный относительно центра area (округлённого до пикселя): вершины по возрастанию
+// угла на случайном расстоянии от центра, поэтому рёбра не пересекаются. Наборы углов с промежутком
+// больше MAX_STAR_GAP и многоугольники, граница которых задевает пиксель центра, отбрасываются -
+// центр остаётся внутренней точкой, пригодной для затравки
+fn random_star_polygon(rng: &mut Rng, area: Rect, count: usize) -> Vec<Pos2> {
+    let center = area.center().round();
+    let outer = area.width().min(area.height()) / 2.0;
+    let count = count.max(3);
+    let mut vertices = Vec::new();
+    for _ in 0..STAR_ATTEMPTS {
+        let mut angles: Vec<f32> = (0..count).map(|_| rng.range(0.0, std::f32::consts::TAU)).collect();
+        angles.sort_by(f32::total_cmp);
+        let wrap = angles[0] + std::f32::consts::TAU - angles[count - 1];
+        let gap = angles.windows(2).map(|w| w[1] - w[0]).fold(wrap, f32::max);
+        if gap > MAX_STAR_GAP {
+            continue;
+        }
+        vertices = angles.into_iter().map(|angle| (center + Vec2::angled(angle) * rng.range(0.4, 1.0) * outer).round()).collect();
+        let mut closed = vertices.clone();
+        closed.push(vertices[0]);
+        if !algo::polyline(&closed, &mut NoOpLogger).contains(&center) {
+            break;
+        }
+    }
+    vertices
+}
+
+// Длина, приращения и угол к оси X измеряемого отрезка (ось Y холста направлена вниз)
+fn ruler_text(start: Pos2, end: Pos2) -> String {
+    let d = end - start;
+    format!("L = {:.2}, Δx = {:.2}, Δy = {:.2}, угол = {:.1}°", d.length(), d.x, d.y, d.y.atan2(d.x).to_degrees())
+}
+
+// Точки пользовательской кривой в равноотстоящих значениях t
+fn sample_custom_curve(params: &AppParameters) -> Result<Vec<Pos2>, String> {
+    let x = Expr::parse(&params.custom_x).map_err(|e| format!("x(t): {}", e))?;
+    let y = Expr::parse(&params.custom_y).map_err(|e| format!("y(t): {}", e))?;
+    let n = params.custom_samples.clamp(2, MAX_CUSTOM_SAMPLES);
+    let (from, to) = (params.custom_t_from, params.custom_t_to);
+    Ok((0..n)
+        .map(|i| {
+            let t = from + (to - from) * i as f32 / (n - 1) as f32;
+            Pos2::new(x.eval(t), y.eval(t))
+        })
+        .collect())
+}
+
+// Выполнение алгоритма на снимке параметров: не зависит от приложения и может идти в фоновом потоке
+// Растеризованная граница для затравочной заливки; её построение не логируется,
+// чтобы журнал показывал только рост фронта
+fn fill_boundary_pixels(params: &AppParameters) -> Vec<Pos2> {
+    match params.fill_boundary {
+        FillBoundary::Circle => algo::bresenham_circle(params.circle_center, params.circle_radius, algo::ALL_OCTANTS, &mut NoOpLogger)
+            .iter()
+            .map(PixelRecord::pos)
+            .collect(),
+        FillBoundary::Polygon => {
+            let mut vertices = params.polygon_points.clone();
+            vertices.extend(params.polygon_points.first().copied());
+            algo::polyline(&vertices, &mut NoOpLogger)
+        }
+    }
+}
+
+// Точка на растре в factor раз мельче (см. AppParameters::supersampled)
+fn supersampled_point(p: Pos2, factor: u32) -> Pos2 {
+    let k = factor as f32;
+    Pos2::new(k * p.x + (k - 1.0) / 2.0, k * p.y + (k - 1.0) / 2.0)
+}
+
+// Логическая область, видимая в холсте, на котором рисует painter
+fn visible_rect(painter: &Painter, to_screen: egui::emath::RectTransform) -> Rect {
+    let from_screen = to_screen.inverse();
+    Rect::from_two_pos(from_screen * painter.clip_rect().min, from_screen * painter.clip_rect().max)
+}
+
+// Суперсэмплинг: алгоритм выполняется на растре в supersample раз мельче, результат усредняется
+// по подпикселям. Журнал мелкого растра не ведётся, логируются итоговые интенсивности
+fn supersample<L: Logger>(
+    algorithm: Algorithm,
+    params: &AppParameters,
+    view: Rect,
+    rounding_mode: RoundingMode,
+    fraction_mode: FractionMode,
+    logger: &mut L,
+) -> RenderResult {
+    let factor = params.supersample;
+    let fine_view = Rect::from_min_max(supersampled_point(view.min, factor), supersampled_point(view.max, factor));
+    let fine = execute_algorithm(algorithm, &params.supersampled(factor), fine_view, rounding_mode, fraction_mode, &mut NoOpLogger);
+    if logger.can_write() {
+        logger.log(format!("Суперсэмплинг {}x{}: на мелком растре {} пикс.", factor, factor, fine.len()));
+    }
+    RenderResult::Antialiased(algo::downsample(fine.records(), factor, !algorithm.is_area_fill(), logger))
+}
+
+// view - видимая часть плоскости в логических координатах: ограничивает бесконечные кривые
+fn execute_algorithm<L: Logger>(
+    algorithm: Algorithm,
+    params: &AppParameters,
+    view: Rect,
+    rounding_mode: RoundingMode,
+    fraction_mode: FractionMode,
+    logger: &mut L,
+) -> RenderResult {
+    if params.supersample > 1 {
+        if matches!(algorithm, Algorithm::CustomParametric | Algorithm::FunctionPlot | Algorithm::Line3D) {
+            // Выражения и проекцию нельзя пересчитать на мелкий растр, строим как обычно
+            logger.log(format!("Суперсэмплинг для {} не поддерживается", algorithm));
+        } else {
+            return supersample(algorithm, params, view, rounding_mode, fraction_mode, logger);
+        }
+    }
+    // Отрезок отсекается окном до растеризации, алгоритм получает уже укороченные концы
+    let clipped;
+    let params = if algorithm.is_line() && params.clip_mode != ClipMode::Off {
+        let visible = match params.clip_mode {
+            ClipMode::Off => Some((params.p1, params.p2)),
+            ClipMode::Rectangle => clip::cohen_sutherland(params.p1, params.p2, params.clip_window, logger),
+            ClipMode::Polygon if !clip::is_convex(&params.clip_polygon) => {
+                logger.log("Окно отсечения не выпуклое - алгоритм Сайруса-Бека к нему неприменим".to_string());
+                None
+            }
+            ClipMode::Polygon => clip::cyrus_beck(params.p1, params.p2, &params.clip_polygon, logger),
+        };
+        match visible {
+            Some((p1, p2)) => {
+                clipped = AppParameters { p1, p2, ..params.clone() };
+                &clipped
+            }
+            None => return RenderResult::None,
+        }
+    } else {
+        params
+    };
+    match algorithm {
+        Algorithm::StepByStep => {
+            RenderResult::Pixels(algo::step_by_step(params.p1, params.p2, rounding_mode, logger))
+        }
+        Algorithm::DDA => {
+            RenderResult::Pixels(algo::dda(params.p1, params.p2, rounding_mode, logger))
+        }
+        Algorithm::BresenhamLine => {
+            RenderResult::Rich(algo::bresenham_line_rich(params.p1, params.p2, logger))
+        }
+        Algorithm::MidpointLine => {
+            RenderResult::Rich(algo::midpoint_line(params.p1, params.p2, logger))
+        }
+        Algorithm::DoubleStepLine => {
+            RenderResult::Rich(algo::double_step_line(params.p1, params.p2, logger))
+        }
+        Algorithm::RunSliceLine => {
+            RenderResult::Rich(algo::run_slice_line(params.p1, params.p2, logger))
+        }
+        Algorithm::SymmetricBresenham => {
+            RenderResult::Rich(algo::symmetric_bresenham(params.p1, params.p2, logger))
+        }
+        Algorithm::DiamondExit => {
+            RenderResult::Rich(algo::diamond_exit_line(params.p1, params.p2, logger))
+        }
+        Algorithm::BresenhamCircle => RenderResult::Rich(algo::group_by_octant(algo::bresenham_circle(
+            params.circle_center,
+            params.circle_radius,
+            params.circle_octants,
+            logger,
+        ))),
+        Algorithm::PolarCircle => RenderResult::Pixels(algo::polar_circle(
+            params.circle_center,
+            params.circle_radius,
+            logger,
+        )),
+        Algorithm::SqrtCircle => RenderResult::Pixels(algo::sqrt_circle(
+            params.circle_center,
+            params.circle_radius,
+            logger,
+        )),
+        Algorithm::RotationCircle => RenderResult::Pixels(algo::rotation_circle(
+            params.circle_center,
+            params.circle_radius,
+            logger,
+        )),
+        Algorithm::WuCircle => RenderResult::Antialiased(algo::wu_circle(
+            params.circle_center,
+            params.circle_radius,
+            logger,
+        )),
+        Algorithm::RoundedRect => RenderResult::Rich(algo::rounded_rect(
+            params.rrect_center,
+            params.rrect_width,
+            params.rrect_height,
+            params.rrect_radius,
+            logger,
+        )),
+        Algorithm::ThickPolyline => RenderResult::Rich(algo::thick_polyline(
+            &params.thick_points,
+            params.thick_width,
+            params.line_cap,
+            params.line_join,
+            logger,
+        )),
+        Algorithm::PieSlice => RenderResult::Rich(algo::pie_slice(
+            params.circle_center,
+            params.circle_radius,
+            params.sector_start,
+            params.sector_end,
+            &params.fill_pattern,
+            logger,
+        )),
+        Algorithm::Annulus => {
+            let ring = if params.ring_concentric { algo::concentric_circles } else { algo::annulus };
+            RenderResult::Pixels(ring(params.circle_center, params.ring_inner, params.ring_outer, logger))
+        }
+        Algorithm::ThreePointCircle => {
+            let [a, b, c] = params.circle_through;
+            match algo::circumcircle(a, b, c) {
+                Some((center, radius)) => {
+                    if logger.can_write() {
+                        logger.log(format!("Центр описанной окружности: ({:.3}, {:.3}), радиус: {:.3}", center.x, center.y, radius));
+                    }
+                    RenderResult::Rich(algo::bresenham_circle(center, radius, algo::ALL_OCTANTS, logger))
+                }
+                None => {
+                    logger.log("Точки лежат на одной прямой - окружность через них не проходит".to_string());
+                    RenderResult::None
+                }
+            }
+        }
+        Algorithm::EllipseAA => RenderResult::Antialiased(algo::ellipse_aa(
+            params.ellipse_center,
+            params.ellipse_rx,
+            params.ellipse_ry,
+            logger,
+        )),
+        Algorithm::MidpointParabola => RenderResult::Rich(algo::midpoint_parabola(
+            params.parabola_vertex,
+            params.parabola_a,
+            params.parabola_extent,
+            logger,
+        )),
+        Algorithm::Hyperbola => RenderResult::Rich(algo::midpoint_hyperbola(
+            params.hyperbola_center,
+            params.hyperbola_a,
+            params.hyperbola_b,
+            params.hyperbola_both,
+            view,
+            logger,
+        )),
+        Algorithm::StepByStepAA => RenderResult::Antialiased(algo::step_by_step_aa(
+            params.p1, params.p2, rounding_mode, fraction_mode, logger
+        )),
+        Algorithm::DdaAA => RenderResult::Antialiased(algo::dda_aa(
+            params.p1, params.p2, rounding_mode, fraction_mode, logger
+        )),
+        Algorithm::GuptaSproull => RenderResult::Antialiased(
+            algo::gupta_sproull(params.p1, params.p2, logger)
+        ),
+        Algorithm::BresenhamAA => RenderResult::Antialiased(
+            algo::bresenham_aa(params.p1, params.p2, logger)
+        ),
+        Algorithm::CastlePitway => RenderResult::Pixels(algo::apply_pen_pattern(
+            algo::castle_pitway(&params.bezier_control().0, params.castle_steps, rounding_mode, logger),
+            params.curve_pen,
+        )),
+        Algorithm::BezierSubdivision => RenderResult::Pixels(algo::apply_pen_pattern(
+            algo::bezier_subdivision(&params.bezier_control().0, params.bezier_tolerance, logger),
+            params.curve_pen,
+        )),
+        Algorithm::BSpline => RenderResult::Pixels(algo::apply_pen_pattern(
+            algo::bspline(&params.castle_points, params.castle_steps, params.curve_closed, logger),
+            params.curve_pen,
+        )),
+        Algorithm::CatmullRom => RenderResult::Pixels(algo::apply_pen_pattern(
+            algo::catmull_rom(&params.castle_points, params.spline_tension, params.castle_steps, params.curve_closed, logger),
+            params.curve_pen,
+        )),
+        Algorithm::Hermite => {
+            let (m0, m1) = params.hermite_tangents();
+            RenderResult::Pixels(algo::apply_pen_pattern(
+                algo::hermite_curve(params.hermite_p0, m0, params.hermite_p1, m1, params.hermite_steps, logger),
+                params.curve_pen,
+            ))
+        }
+        Algorithm::RationalBezier => {
+            let (points, weights) = params.bezier_control();
+            RenderResult::Pixels(algo::apply_pen_pattern(
+                algo::rational_bezier(&points, &weights, params.castle_steps, logger),
+                params.curve_pen,
+            ))
+        }
+        Algorithm::PolygonFillAA if params.polygon_aliased => RenderResult::Pixels(algo::scanline_fill(
+            &params.polygon_points,
+            &params.fill_pattern,
+            logger,
+        )),
+        Algorithm::PolygonFillAA => RenderResult::Antialiased(algo::scanline_fill_aa(
+            &params.polygon_points,
+            &params.fill_pattern,
+            logger,
+        )),
+        Algorithm::TriangleFillAA => RenderResult::Antialiased(algo::edge_function_triangle(
+            params.triangle_points,
+            &params.fill_pattern,
+            logger,
+        )),
+        Algorithm::FloodFill | Algorithm::BoundaryFill => {
+            // Сначала граница, затем закрашенные пиксели в порядке обхода
+            let mut pixels = fill_boundary_pixels(params);
+            let fill = if algorithm == Algorithm::FloodFill { algo::flood_fill } else { algo::boundary_fill };
+            let filled = fill(&pixels, params.fill_seed, params.fill_connectivity, &params.fill_pattern, logger);
+            pixels.extend(filled);
+            RenderResult::Pixels(pixels)
+        }
+        Algorithm::Line3D => {
+            let voxels = algo::bresenham_3d(params.line3d_p1, params.line3d_p2, logger);
+            RenderResult::Antialiased(algo::project_voxels(&voxels, params.projection))
+        }
+        Algorithm::FunctionPlot => match Expr::parse_in(&params.function_expr, "x") {
+            Ok(f) => RenderResult::Rich(algo::function_plot(|x| f.eval(x), view, logger)),
+            Err(e) => {
+                logger.log(format!("y(x): {}", e));
+                RenderResult::None
+            }
+        },
+        Algorithm::CustomParametric => match sample_custom_curve(params) {
+            Ok(samples) => RenderResult::Pixels(algo::apply_pen_pattern(algo::polyline(&samples, logger), params.curve_pen)),
+            Err(e) => {
+                logger.log(e);
+                RenderResult::None
+            }
+        },
+        Algorithm::SsaaLine => RenderResult::Antialiased(algo::ssaa_line(
+            params.p1,
+            params.p2,
+            params.ssaa_factor,
+            logger,
+        )),
+        Algorithm::BrushLine => RenderResult::Pixels(algo::brush_line(
+            params.p1,
+            params.p2,
+            &params.brush_stamp,
+            params.brush_spacing,
+            logger,
+        )),
+        Algorithm::WuLine => {
+            RenderResult::Antialiased(algo::wu_line(params.p1, params.p2, rounding_mode, fraction_mode, logger))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Кадр, в котором кнопка мыши нажата (pressed) или отпущена, и запись истории в его конце
+    fn frame(ctx: &egui::Context, app: &mut GraphicsLabApp, pressed: Option<bool>) {
+        let pos = Pos2::new(10.0, 10.0);
+        let mut input = egui::RawInput { events: vec![egui::Event::PointerMoved(pos)], ..Default::default() };
+        if let Some(pressed) = pressed {
+            input.events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: Default::default(),
+            });
+        }
+        let _ = ctx.run(input, |ctx| app.record_history(ctx));
+    }
+
+    // Записывает долю выполненной работы, о которой сообщает алгоритм
+    struct ProgressRecorder(Vec<f32>);
+
+    impl Logger for ProgressRecorder {
+        fn log(&mut self, _message: String) {}
+
+        fn progress(&mut self, fraction: f32) {
+            self.0.push(fraction);
+        }
+
+        fn can_write(&self) -> bool {
+            false
+        }
+
+        fn results(self) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn line_progress_is_monotonic() {
+        let algorithms = Algorithm::LINES.iter().chain(Algorithm::AA_LINES).chain(&[Algorithm::BrushLine]);
+        for &algorithm in algorithms {
+            for p2 in [Pos2::new(17.0, 6.0), Pos2::new(-4.0, -13.0)] {
+                let params = AppParameters { p1: Pos2::new(1.0, 2.0), p2, ..Default::default() };
+                let mut recorder = ProgressRecorder(Vec::new());
+                execute_algorithm(algorithm, &params, Rect::EVERYTHING, RoundingMode::HalfUp, FractionMode::Floor, &mut recorder);
+                let progress = recorder.0;
+                assert!(!progress.is_empty(), "{}: нет прогресса", algorithm);
+                assert!(progress.windows(2).all(|w| w[0] <= w[1]), "{}: {:?}", algorithm, progress);
+                assert!((progress[progress.len() - 1] - 1.0).abs() < 1e-6, "{}: {:?}", algorithm, progress);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_point_formats() {
+        assert_eq!(parse_point("3, -4.5"), Some(Pos2::new(3.0, -4.5)));
+        assert_eq!(parse_point("3 -4.5"), Some(Pos2::new(3.0, -4.5)));
+        assert_eq!(parse_point("3;-4.5"), Some(Pos2::new(3.0, -4.5)));
+        assert_eq!(parse_point("  3 ,\t -4.5  "), Some(Pos2::new(3.0, -4.5)));
+        assert_eq!(parse_point("1e2  7"), Some(Pos2::new(100.0, 7.0)));
+    }
+
+    #[test]
+    fn parse_point_rejects_invalid() {
+        for text in ["", "   ", "3", "3,", "3, 4, 5", "x, 4", "3, y", "inf, 0", "0 NaN", "3..4 1"] {
+            assert_eq!(parse_point(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn line_equation_defines_run_endpoints() {
+        let mut app = GraphicsLabApp::default();
+        app.params.line_input = LineInput::SlopeIntercept;
+        (app.params.line_k, app.params.line_b, app.params.line_from, app.params.line_to) = (0.5, 5.0, -10.0, 30.0);
+        // P1/P2 устарели (например, после отмены), но запуск берёт концы из уравнения
+        app.params.p1 = Pos2::new(100.0, 100.0);
+        let params = app.run_params();
+        assert_eq!((params.p1, params.p2), (Pos2::new(-10.0, 0.0), Pos2::new(30.0, 20.0)));
+        let pixels: Vec<Pos2> = app.execute_algorithm(Algorithm::BresenhamLine, &mut NoOpLogger).records().map(|r| r.pos()).collect();
+        assert_eq!(pixels.first(), Some(&Pos2::new(-10.0, 0.0)));
+        assert_eq!(pixels.last(), Some(&Pos2::new(30.0, 20.0)));
+
+        app.params.line_input = LineInput::Vertical;
+        app.params.line_c = -7.0;
+        let params = app.run_params();
+        assert_eq!((params.p1, params.p2), (Pos2::new(-7.0, -10.0), Pos2::new(-7.0, 30.0)));
+
+        app.params.line_input = LineInput::TwoPoints;
+        assert_eq!(app.run_params().p1, Pos2::new(100.0, 100.0));
+    }
+
+    #[test]
+    fn adaptive_steps_grow_with_zoom() {
+        let mut app = GraphicsLabApp::default();
+        app.params.castle_adaptive = true;
+        app.params.castle_points = vec![Pos2::new(0.0, 0.0), Pos2::new(30.0, 40.0), Pos2::new(60.0, 0.0)];
+        let manual = app.params.castle_steps;
+        let mut previous = 0;
+        for zoom in [0.5, 1.0, 4.0, 20.0] {
+            app.viewports[app.view].zoom_x = zoom;
+            app.viewports[app.view].zoom_y = zoom;
+            let steps = app.run_params().castle_steps;
+            assert!(steps > previous, "масштаб {}: {} шагов", zoom, steps);
+            previous = steps;
+        }
+        // Масштаб не меняет параметры, поэтому не попадает в историю
+        assert_eq!(app.params.castle_steps, manual);
+        app.commit_history();
+        assert_eq!(app.history.len(), 1);
+        app.viewports[app.view].zoom_x = 2.0;
+        app.commit_history();
+        assert_eq!(app.history.len(), 1);
+    }
+
+    #[test]
+    fn clip_window_fits_view_with_margin() {
+        let mut app = GraphicsLabApp::default();
+        app.viewports[app.view].size = Vec2::new(800.0, 600.0);
+        app.params.clip_mode = ClipMode::Rectangle;
+        for window in [
+            Rect::from_min_max(Pos2::new(-40.0, -30.0), Pos2::new(40.0, 30.0)),
+            Rect::from_min_max(Pos2::new(100.0, 5.0), Pos2::new(300.0, 20.0)),
+            Rect::from_min_max(Pos2::new(-7.0, -90.0), Pos2::new(-2.0, -10.0)),
+        ] {
+            app.params.clip_window = window;
+            app.fit_view_to_clip_window();
+            let view = app.viewport().bounds();
+            assert!(view.contains_rect(window), "{:?} вне {:?}", window, view);
+            assert!((view.center() - window.center()).length() < 1e-3);
+            // Окно занимает FIT_MARGIN холста по ограничивающей оси
+            let fill = (window.width() / view.width()).max(window.height() / view.height());
+            assert!((fill - FIT_MARGIN).abs() < 1e-4, "{:?}: {}", window, fill);
+        }
+    }
+
+    // Чётность числа пересечений луча из точки вправо с рёбрами многоугольника
+    fn inside_polygon(p: Pos2, vertices: &[Pos2]) -> bool {
+        let mut inside = false;
+        for (i, &a) in vertices.iter().enumerate() {
+            let b = vertices[(i + 1) % vertices.len()];
+            if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    #[test]
+    fn random_star_polygon_contains_center() {
+        let area = Rect::from_center_size(Pos2::new(13.0, -8.0), Vec2::new(120.0, 80.0));
+        for seed in 0..300 {
+            let mut rng = Rng(seed);
+            for count in 3..=8 {
+                let polygon = random_star_polygon(&mut rng, area, count);
+                let center = area.center().round();
+                assert_eq!(polygon.len(), count);
+                assert!(inside_polygon(center, &polygon), "зерно {}: {:?}", seed, polygon);
+                let mut closed = polygon.clone();
+                closed.push(polygon[0]);
+                assert!(!algo::polyline(&closed, &mut NoOpLogger).contains(&center), "зерно {}: {:?}", seed, polygon);
+            }
+        }
+    }
+
+    // Ждёт окончания фонового расчёта и забирает его результат
+    fn finish_run(ctx: &egui::Context, app: &mut GraphicsLabApp) {
+        while app.pending_run.as_ref().is_some_and(|run| !run.handle.is_finished()) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        app.poll_pending_run(ctx);
+    }
+
+    #[test]
+    fn drag_rerun_waits_for_pending_run() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp::default();
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        app.run_algorithm();
+        assert_eq!(app.onion_skins.len(), 0);
+
+        // Точка сдвинута, пока идёт обычный запуск: перезапуск откладывается, а не теряется
+        app.params.p2 = Pos2::new(3.0, 1.0);
+        app.drag_rerun();
+        assert!(app.drag_rerun_queued);
+        finish_run(&ctx, &mut app);
+        assert_eq!(app.onion_skins.len(), 1);
+        assert!(app.pending_run.as_ref().is_some_and(|run| run.drag_rerun));
+
+        // Результат перезапуска - с последним положением точки и без новой "луковой кожи"
+        finish_run(&ctx, &mut app);
+        assert!(app.pending_run.is_none() && !app.drag_rerun_queued);
+        assert_eq!(app.onion_skins.len(), 1);
+        assert_eq!(app.render_result.records().last().map(|r| r.pos()), Some(Pos2::new(3.0, 1.0)));
+    }
+
+    #[test]
+    fn rounding_mode_logged_only_for_rounding_algorithms() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp { selected_algorithm: Algorithm::DDA, log_enabled: true, ..Default::default() };
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        assert!(app.last_log.contains("Режим округления: HalfUp"), "{}", app.last_log);
+
+        // Брезенхем работает в целых числах и режим округления не использует
+        app.selected_algorithm = Algorithm::BresenhamLine;
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        assert!(!app.last_log.contains("Режим округления"), "{}", app.last_log);
+    }
+
+    #[test]
+    fn axis_zoom_respects_aspect_lock() {
+        let mut view = Viewport::default();
+        view.set_axis_zoom(Vec2::new(30.0, view.zoom_y), true);
+        assert_eq!((view.zoom_x, view.zoom_y), (30.0, 30.0));
+        view.set_axis_zoom(Vec2::new(view.zoom_x, 12.0), true);
+        assert_eq!((view.zoom_x, view.zoom_y), (12.0, 12.0));
+
+        // Без сохранения пропорций оси масштабируются независимо
+        view.set_axis_zoom(Vec2::new(40.0, view.zoom_y), false);
+        assert_eq!((view.zoom_x, view.zoom_y), (40.0, 12.0));
+        view.set_axis_zoom(Vec2::new(view.zoom_x, 5.0), false);
+        assert_eq!((view.zoom_x, view.zoom_y), (40.0, 5.0));
+    }
+
+    #[test]
+    fn pixel_count_table_covers_menu_lines() {
+        let app = GraphicsLabApp::default();
+        let table = app.pixel_count_table();
+        let lines: Vec<String> = ALGORITHM_MENU
+            .iter()
+            .flat_map(|group| group.iter().map(|&(algorithm, _)| algorithm))
+            .filter(Algorithm::is_line)
+            .map(|algorithm| algorithm.to_string())
+            .collect();
+        let methods: Vec<String> = table.rows.iter().map(|row| row[0].clone()).collect();
+        assert_eq!(methods, lines);
+        assert!(methods.contains(&Algorithm::BrushLine.to_string()));
+    }
+
+    #[test]
+    fn pixel_log_replays_real_run() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp { selected_algorithm: Algorithm::BresenhamCircle, ..Default::default() };
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        let records: Vec<PixelRecord> = app.render_result.records().collect();
+        // Метки октантов и значения переменной решения должны пережить сохранение
+        assert!(records.iter().any(|r| r.tag != 0) && records.iter().any(|r| r.value != 0.0));
+
+        let path = std::env::temp_dir().join(format!("pixel_log_replay_{}.json", std::process::id()));
+        std::fs::write(&path, report::pixel_log_json("BresenhamCircle", records.iter().copied())).unwrap();
+        app.pixel_log_path = path.to_string_lossy().into_owned();
+        app.render_result = RenderResult::None;
+        app.replay_pixel_log();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(app.render_result.records().collect::<Vec<_>>(), records);
+    }
+
+    #[test]
+    fn circle_coverage_follows_last_run() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp {
+            selected_algorithm: Algorithm::BresenhamCircle,
+            show_circle_coverage: true,
+            ..Default::default()
+        };
+        app.params.circle_radius = 12.0;
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+
+        // Радиус изменён после запуска: эталон по-прежнему строится для нарисованной окружности
+        app.params.circle_radius = 30.0;
+        app.update_circle_coverage();
+        let (coverage, error) = app.circle_coverage.as_ref().unwrap();
+        assert_eq!(coverage, &algo::circle_coverage(app.params.circle_center, 12.0, 4));
+        assert!(*error < 0.5, "{}", error);
+    }
+
+    #[test]
+    fn onion_skins_keep_newest_runs() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp { onion_depth: 2, ..Default::default() };
+        for x in 1..=4 {
+            app.params.p2 = Pos2::new(x as f32 * 10.0, 0.0);
+            app.run_algorithm();
+            finish_run(&ctx, &mut app);
+        }
+        // Каждая "кожа" хранит параметры своего запуска, а не текущие поля ввода
+        app.params.p2 = Pos2::new(-5.0, -5.0);
+        let ends: Vec<Pos2> = app.onion_skins.iter().map(|past| past.params.p2).collect();
+        assert_eq!(ends, [Pos2::new(30.0, 0.0), Pos2::new(20.0, 0.0)]);
+    }
+
+    #[test]
+    fn mirrored_result_is_cached_per_run() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp { mirror_quadrants: true, ..Default::default() };
+        app.params.p1 = Pos2::new(1.0, 1.0);
+        app.params.p2 = Pos2::new(4.0, 2.0);
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        app.update_mirrored();
+        let (reflections, unique) = app.mirrored.as_ref().unwrap();
+        assert_eq!(reflections.len(), 3 * app.render_result.len());
+        assert_eq!(*unique, 4 * app.render_result.len());
+        assert!(reflections.records().any(|r| r.pos() == Pos2::new(-4.0, -2.0)));
+
+        // Новый запуск сбрасывает отражения прошлого результата
+        app.params.p2 = Pos2::new(9.0, 2.0);
+        app.run_algorithm();
+        finish_run(&ctx, &mut app);
+        assert!(app.mirrored.is_none());
+        app.update_mirrored();
+        assert!(app.mirrored.as_ref().unwrap().0.records().any(|r| r.pos() == Pos2::new(-9.0, -2.0)));
+    }
+
+    #[test]
+    fn window_positions_are_keyed_by_kind() {
+        let mut closed = TextWindow::new("Экспорт".to_string(), String::new());
+        closed.is_open = false;
+        let windows = [
+            TextWindow::new("Логи: ЦДА".to_string(), String::new()).kind("Логи"),
+            TextWindow::new("Экспорт".to_string(), String::new()),
+            closed,
+            TextWindow::new("Логи: Брезенхем".to_string(), String::new()).kind("Логи"),
+            TextWindow::new("Экспорт".to_string(), String::new()),
+        ];
+        let keys = GraphicsLabApp::window_position_keys(&windows);
+        let expected = [Some("Логи#0"), Some("Экспорт#0"), None, Some("Логи#1"), Some("Экспорт#1")];
+        assert_eq!(keys, expected.map(|k| k.map(String::from)));
+    }
+
+    #[test]
+    fn closed_windows_are_trimmed_oldest_first() {
+        let mut app = GraphicsLabApp::default();
+        for i in 0..2 * MAX_CLOSED_WINDOWS + 10 {
+            let mut window = TextWindow::new(format!("Логи: {}", i), String::new()).kind("Логи");
+            window.is_open = i % 2 == 0;
+            app.text_windows.push(window);
+        }
+        let open_before = app.text_windows.iter().filter(|w| w.is_open).count();
+        app.trim_closed_windows();
+        assert!(app.text_windows.iter().all(|w| w.kind == "Логи"));
+        assert_eq!(app.text_windows.iter().filter(|w| w.is_open).count(), open_before);
+        let closed: Vec<&str> = app.text_windows.iter().filter(|w| !w.is_open).map(|w| w.title.as_str()).collect();
+        assert_eq!(closed.len(), MAX_CLOSED_WINDOWS);
+        // Закрыты нечётные окна 1..49; забыты пять самых старых
+        assert_eq!((closed[0], closed[closed.len() - 1]), ("Логи: 11", "Логи: 49"));
+    }
+
+    #[test]
+    fn integer_input_snaps_finished_edit() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp { integer_input: true, ..Default::default() };
+        app.params.p1 = Pos2::new(2.4, -3.6);
+        app.params.circle_radius = 7.5;
+        app.params.castle_points[0] = Pos2::new(0.49, 10.51);
+
+        // Пока правка идёт, значение не трогается
+        frame(&ctx, &mut app, Some(true));
+        assert_eq!(app.params.p1, Pos2::new(2.4, -3.6));
+        frame(&ctx, &mut app, Some(false));
+        assert_eq!(app.params.p1, Pos2::new(2.0, -4.0));
+        assert_eq!(app.params.circle_radius, 8.0);
+        assert_eq!(app.params.castle_points[0], Pos2::new(0.0, 11.0));
+        // Правка с округлением - одно действие, отмена возвращает исходные значения
+        assert_eq!(app.history.len(), 1);
+        app.undo();
+        assert_eq!(app.params.p1, AppParameters::default().p1);
+        // Точки на холсте привязываются к сетке
+        assert_eq!(app.snap(Pos2::new(1.6, -0.2)), Pos2::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn drag_is_one_history_entry() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp::default();
+        let start = app.params.p1;
+
+        frame(&ctx, &mut app, Some(true));
+        for _ in 0..5 {
+            app.params.p1 += Vec2::new(1.0, 2.0);
+            frame(&ctx, &mut app, None);
+        }
+        assert_eq!(app.history.len(), 0);
+        frame(&ctx, &mut app, Some(false));
+        assert_eq!(app.history.len(), 1);
+
+        app.undo();
+        assert_eq!(app.params.p1, start);
+    }
+
+    #[test]
+    fn drag_without_movement_is_not_recorded() {
+        let ctx = egui::Context::default();
+        let mut app = GraphicsLabApp::default();
+        frame(&ctx, &mut app, Some(true));
+        frame(&ctx, &mut app, None);
+        frame(&ctx, &mut app, Some(false));
+        assert_eq!(app.history.len(), 0);
+    }
+}
@@ -0,0 +1,163 @@
+// src/clip.rs
+
+use crate::logger::Logger;
+use egui::{Pos2, Rect};
+
+// Биты кода области Коэна-Сазерленда: положение точки относительно границ окна
+const LEFT: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const BOTTOM: u8 = 0b0100;
+const TOP: u8 = 0b1000;
+
+/// Код области точки: по одному биту на каждую границу окна, за которой лежит точка
+pub fn outcode(p: Pos2, window: Rect) -> u8 {
+    let mut code = 0;
+    if p.x < window.min.x {
+        code |= LEFT;
+    } else if p.x > window.max.x {
+        code |= RIGHT;
+    }
+    if p.y < window.min.y {
+        code |= BOTTOM;
+    } else if p.y > window.max.y {
+        code |= TOP;
+    }
+    code
+}
+
+/// Отсечение отрезка прямоугольным окном по Коэну-Сазерленду.
+/// Если оба кода нулевые, отрезок целиком внутри; если их побитовое И не ноль, оба конца лежат
+/// по одну сторону от границы и отрезок целиком снаружи (None). Иначе внешний конец переносится
+/// в точку пересечения с одной из границ, и проверка повторяется
+pub fn cohen_sutherland<L: Logger>(p1: Pos2, p2: Pos2, window: Rect, logger: &mut L) -> Option<(Pos2, Pos2)> {
+    let (mut a, mut b) = (p1, p2);
+    let mut code_a = outcode(a, window);
+    let mut code_b = outcode(b, window);
+    let mut iteration = 0;
+    loop {
+        if logger.can_write() {
+            logger.log(format!(
+                "Итерация {}: ({:.2}, {:.2}) код {:04b}, ({:.2}, {:.2}) код {:04b}",
+                iteration, a.x, a.y, code_a, b.x, b.y, code_b
+            ));
+        }
+        if code_a | code_b == 0 {
+            if logger.can_write() {
+                logger.log("  оба кода нулевые - отрезок внутри окна".to_string());
+            }
+            return Some((a, b));
+        }
+        if code_a & code_b != 0 {
+            if logger.can_write() {
+                logger.log(format!("  {:04b} & {:04b} != 0 - отрезок вне окна", code_a, code_b));
+            }
+            return None;
+        }
+
+        // Переносим конец, лежащий снаружи, на границу, соответствующую старшему его биту
+        let outside = if code_a != 0 { code_a } else { code_b };
+        let (border, p) = if outside & TOP != 0 {
+            (format!("верхней границей y = {:.2}", window.max.y), Pos2::new(a.x + (b.x - a.x) * (window.max.y - a.y) / (b.y - a.y), window.max.y))
+        } else if outside & BOTTOM != 0 {
+            (format!("нижней границей y = {:.2}", window.min.y), Pos2::new(a.x + (b.x - a.x) * (window.min.y - a.y) / (b.y - a.y), window.min.y))
+        } else if outside & RIGHT != 0 {
+            (format!("правой границей x = {:.2}", window.max.x), Pos2::new(window.max.x, a.y + (b.y - a.y) * (window.max.x - a.x) / (b.x - a.x)))
+        } else {
+            (format!("левой границей x = {:.2}", window.min.x), Pos2::new(window.min.x, a.y + (b.y - a.y) * (window.min.x - a.x) / (b.x - a.x)))
+        };
+        if logger.can_write() {
+            logger.log(format!("  пересечение с {}: ({:.2}, {:.2})", border, p.x, p.y));
+        }
+        if outside == code_a {
+            a = p;
+            code_a = outcode(a, window);
+        } else {
+            b = p;
+            code_b = outcode(b, window);
+        }
+        iteration += 1;
+    }
+}
+
+// Удвоенная ориентированная площадь многоугольника: знак задаёт направление обхода
+fn signed_area2(polygon: &[Pos2]) -> f32 {
+    (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum()
+}
+
+/// Проверка выпуклости: при обходе все повороты в вершинах одного знака
+pub fn is_convex(polygon: &[Pos2]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    let n = polygon.len();
+    let mut sign = 0.0;
+    for i in 0..n {
+        let (a, b, c) = (polygon[i], polygon[(i + 1) % n], polygon[(i + 2) % n]);
+        let cross = (b - a).x * (c - b).y - (b - a).y * (c - b).x;
+        if cross == 0.0 {
+            continue;
+        }
+        if sign == 0.0 {
+            sign = cross.signum();
+        } else if cross.signum() != sign {
+            return false;
+        }
+    }
+    sign != 0.0
+}
+
+/// Отсечение отрезка выпуклым многоугольником по Сайрусу-Беку.
+/// Отрезок задаётся параметрически P(t) = P1 + t (P2 - P1), t ∈ [0, 1]. Для каждого ребра с внутренней
+/// нормалью n и точкой f на нём t = -n·(P1 - f) / n·D: при n·D > 0 отрезок входит в полуплоскость
+/// ребра (t уточняет нижнюю границу), при n·D < 0 выходит (верхнюю). Если границы пересеклись,
+/// отрезок снаружи (None). Окно должно быть выпуклым
+pub fn cyrus_beck<L: Logger>(p1: Pos2, p2: Pos2, polygon: &[Pos2], logger: &mut L) -> Option<(Pos2, Pos2)> {
+    let d = p2 - p1;
+    // Внутренняя нормаль - ребро, повёрнутое в сторону внутренности (зависит от направления обхода)
+    let orientation = signed_area2(polygon).signum();
+    let (mut t_enter, mut t_leave) = (0.0_f32, 1.0_f32);
+    for i in 0..polygon.len() {
+        let (f, g) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let edge = g - f;
+        let normal = egui::Vec2::new(-edge.y, edge.x) * orientation;
+        let numerator = -normal.dot(p1 - f);
+        let denominator = normal.dot(d);
+        if denominator == 0.0 {
+            // Отрезок параллелен ребру: он либо целиком внутри полуплоскости, либо целиком снаружи
+            if logger.can_write() {
+                logger.log(format!("Ребро {}: n = ({:.2}, {:.2}), отрезок параллелен ребру", i, normal.x, normal.y));
+            }
+            if numerator > 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = numerator / denominator;
+        let entering = denominator > 0.0;
+        if entering {
+            t_enter = t_enter.max(t);
+        } else {
+            t_leave = t_leave.min(t);
+        }
+        if logger.can_write() {
+            logger.log(format!(
+                "Ребро {}: n = ({:.2}, {:.2}), n·D = {:.2}, t = {:.4} ({}), tвх = {:.4}, tвых = {:.4}",
+                i, normal.x, normal.y, denominator, t,
+                if entering { "входящее" } else { "выходящее" },
+                t_enter, t_leave
+            ));
+        }
+        if t_enter > t_leave {
+            if logger.can_write() {
+                logger.log("  tвх > tвых - отрезок вне окна".to_string());
+            }
+            return None;
+        }
+    }
+    Some((p1 + d * t_enter, p1 + d * t_leave))
+}
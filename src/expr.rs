@@ -0,0 +1,252 @@
+// src/expr.rs
+
+// Простой вычислитель выражений от одной переменной для пользовательских кривых (t) и графиков функций (x).
+// Грамматика: сумма слагаемых, произведение множителей, степень (правоассоциативна),
+// унарный минус, числа, t, константы pi и e, функции от одного аргумента.
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(f32),
+    Var,
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+type Function = fn(f32) -> f32;
+
+// Предел вложенности скобок, унарных минусов и степеней: разбор рекурсивный, и без предела
+// длинная цепочка "((((..." переполнила бы стек
+const MAX_NESTING: usize = 200;
+
+const FUNCTIONS: [(&str, Function); 10] = [
+    ("sin", f32::sin),
+    ("cos", f32::cos),
+    ("tan", f32::tan),
+    ("sqrt", f32::sqrt),
+    ("abs", f32::abs),
+    ("exp", f32::exp),
+    ("ln", f32::ln),
+    ("log", f32::log10),
+    ("floor", f32::floor),
+    ("ceil", f32::ceil),
+];
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        Self::parse_in(text, "t")
+    }
+
+    // Разбор выражения, в котором переменная называется variable
+    pub fn parse_in(text: &str, variable: &str) -> Result<Expr, String> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0, variable, depth: 0 };
+        let expr = parser.sum()?;
+        parser.skip_spaces();
+        if parser.pos < parser.chars.len() {
+            return Err(format!("Лишний символ '{}' в позиции {}", parser.chars[parser.pos], parser.pos + 1));
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, t: f32) -> f32 {
+        match self {
+            Expr::Number(v) => *v,
+            Expr::Var => t,
+            Expr::Neg(e) => -e.eval(t),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(t), b.eval(t));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(f, e) => f(e.eval(t)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    variable: &'a str,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    // Через unary проходит любая рекурсия разбора, поэтому вложенность считается здесь
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err("Слишком глубокая вложенность".to_string());
+        }
+        let result = if self.peek() == Some('-') {
+            self.pos += 1;
+            self.unary().map(|e| Expr::Neg(Box::new(e)))
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.pos += 1;
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            None => Err("Неожиданный конец выражения".to_string()),
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.sum()?;
+                if self.peek() != Some(')') {
+                    return Err(format!("Ожидалась ')' в позиции {}", self.pos + 1));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(Expr::Number).map_err(|_| format!("Некорректное число '{}'", text))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if name == self.variable {
+                    return Ok(Expr::Var);
+                }
+                match name.as_str() {
+                    "pi" => Ok(Expr::Number(std::f32::consts::PI)),
+                    "e" => Ok(Expr::Number(std::f32::consts::E)),
+                    _ => {
+                        let Some(&(_, f)) = FUNCTIONS.iter().find(|(n, _)| *n == name) else {
+                            return Err(format!("Неизвестное имя '{}'", name));
+                        };
+                        if self.peek() != Some('(') {
+                            return Err(format!("После {} ожидалась '('", name));
+                        }
+                        Ok(Expr::Call(f, Box::new(self.atom()?)))
+                    }
+                }
+            }
+            Some(c) => Err(format!("Неожиданный символ '{}' в позиции {}", c, self.pos + 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str, t: f32) -> f32 {
+        Expr::parse(text).unwrap().eval(t)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(eval("8 - 3 - 2", 0.0), 3.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0), 1.0);
+        assert_eq!(eval("2 * 3 ^ 2", 0.0), 18.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3", 0.0), -3.0);
+        assert_eq!(eval("--3", 0.0), 3.0);
+        assert_eq!(eval("2 * -3", 0.0), -6.0);
+        assert_eq!(eval("-2 ^ 2", 0.0), -4.0);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.0), 512.0);
+        assert_eq!(eval("2 ^ -1", 0.0), 0.5);
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert_eq!(eval("sin(0)", 0.0), 0.0);
+        assert_eq!(eval("sqrt(16)", 0.0), 4.0);
+        assert_eq!(eval("abs(-2.5)", 0.0), 2.5);
+        assert!((eval("cos(pi)", 0.0) + 1.0).abs() < 1e-6);
+        assert!((eval("ln(e)", 0.0) - 1.0).abs() < 1e-6);
+        assert_eq!(eval("floor(sqrt(10)) + ceil(0.2)", 0.0), 4.0);
+    }
+
+    #[test]
+    fn variable() {
+        assert_eq!(eval("t * t + 1", 3.0), 10.0);
+        assert_eq!(Expr::parse_in("x / 2", "x").unwrap().eval(4.0), 2.0);
+        assert!(Expr::parse_in("t", "x").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        for text in ["", "(1 + 2", "1 + 2)", "foo(1)", "1 2", "2 * t abc", "sin 1", "1..2", "*3", "3 +", "#"] {
+            assert!(Expr::parse(text).is_err(), "{:?} должно быть ошибкой", text);
+        }
+    }
+
+    #[test]
+    fn no_input_panics() {
+        let deep = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(Expr::parse(&deep).is_err());
+        assert!(Expr::parse(&"-".repeat(10_000)).is_err());
+        assert!(Expr::parse(&"sin(".repeat(10_000)).is_err());
+        let samples = ["sin(t) * 2 ^ -(t + 1)", "((t))", "ln(-1) / 0", "1e5", "π", "t^t^t"];
+        for sample in samples {
+            for end in 0..=sample.chars().count() {
+                let prefix: String = sample.chars().take(end).collect();
+                if let Ok(expr) = Expr::parse(&prefix) {
+                    expr.eval(1.5);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// src/history.rs
+
+// Ограничение глубины истории (и отмены, и повтора), чтобы память не росла бесконечно
+const MAX_HISTORY: usize = 100;
+
+// Кладёт снимок на вершину стека, вытесняя самый старый сверх MAX_HISTORY
+fn push_limited<T>(stack: &mut Vec<T>, snapshot: T) {
+    stack.push(snapshot);
+    if stack.len() > MAX_HISTORY {
+        stack.remove(0);
+    }
+}
+
+// История состояний для отмены и повтора действий: хранит снимки, сделанные перед каждым действием,
+// и снимки, от которых ушли отменой
+pub struct History<T> {
+    undo_stack: Vec<T>,
+    redo_stack: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self { undo_stack: Vec::new(), redo_stack: Vec::new() }
+    }
+
+    // Запоминает состояние перед началом действия; новое действие делает отменённые недоступными для повтора
+    pub fn push(&mut self, snapshot: T) {
+        push_limited(&mut self.undo_stack, snapshot);
+        self.redo_stack.clear();
+    }
+
+    // Возвращает состояние, предшествовавшее последнему действию; current можно будет вернуть повтором
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo_stack.pop()?;
+        push_limited(&mut self.redo_stack, current);
+        Some(previous)
+    }
+
+    // Возвращает состояние, от которого ушли последней отменой
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo_stack.pop()?;
+        push_limited(&mut self.undo_stack, current);
+        Some(next)
+    }
+
+    pub fn len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_stacks_are_limited() {
+        let mut history = History::new();
+        for i in 0..MAX_HISTORY + 20 {
+            history.push(i);
+        }
+        assert_eq!(history.len(), MAX_HISTORY);
+
+        let mut current = MAX_HISTORY + 20;
+        while let Some(previous) = history.undo(current) {
+            current = previous;
+        }
+        // Самые старые снимки вытеснены: дальше отмена не идёт
+        assert_eq!(current, 20);
+        assert_eq!((history.len(), history.redo_len()), (0, MAX_HISTORY));
+
+        while let Some(next) = history.redo(current) {
+            current = next;
+        }
+        assert_eq!(current, MAX_HISTORY + 20);
+        assert_eq!((history.len(), history.redo_len()), (MAX_HISTORY, 0));
+    }
+
+    #[test]
+    fn new_action_clears_redo() {
+        let mut history = History::new();
+        history.push(1);
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.redo_len(), 1);
+        history.push(1);
+        assert_eq!(history.redo_len(), 0);
+        assert_eq!(history.redo(1), None);
+    }
+}
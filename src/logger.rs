@@ -1,5 +1,26 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
 pub trait Logger {
     fn log(&mut self, message: String);
+    // Запись, относящаяся к конкретному пикселю: обёртки могут фильтровать её по координатам
+    fn log_pixel(&mut self, _x: i32, _y: i32, message: String) {
+        self.log(message);
+    }
+    // Запись о пикселе в виде именованных полей; по умолчанию - строка "имя: значение, ..."
+    fn log_fields(&mut self, x: i32, y: i32, fields: &[(&str, String)]) {
+        let line: Vec<String> = fields.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+        self.log_pixel(x, y, line.join(", "));
+    }
+    // Шаг алгоритма с готовым текстом и теми же данными в виде полей: текстовые логгеры
+    // выводят сообщение как есть, табличный - поля
+    fn log_step(&mut self, x: i32, y: i32, message: String, _fields: &[(&str, String)]) {
+        self.log_pixel(x, y, message);
+    }
+    // Доля выполненной работы от 0 до 1 для долгих алгоритмов
+    fn progress(&mut self, _fraction: f32) {}
+    // Отметка об очередной итерации основного цикла алгоритма
+    fn iteration(&mut self) {}
     fn can_write(&self) -> bool;
     fn results(self) -> String;
 }
@@ -21,15 +42,22 @@ impl Logger for NoOpLogger {
     }
 }
 
+// Строка-итог с числом итераций основного цикла, которую текстовые логгеры дописывают в конец
+fn iterations_line(iterations: usize) -> Option<String> {
+    (iterations > 0).then(|| format!("Итераций основного цикла: {}", iterations))
+}
+
 // Реализация, которая собирает логи в строку
 pub struct StringLogger {
     buffer: String,
+    iterations: usize,
 }
 
 impl StringLogger {
     pub fn new() -> Self {
         Self {
             buffer: String::new(),
+            iterations: 0,
         }
     }
 }
@@ -40,15 +68,339 @@ impl Logger for StringLogger {
         self.buffer.push('\n');
     }
 
+    fn iteration(&mut self) {
+        self.iterations += 1;
+    }
+
     fn can_write(&self) -> bool {
         true
     }
 
-    fn results(self) -> String {
+    fn results(mut self) -> String {
         if self.buffer.is_empty() {
-            "Алгоритм не произвел никаких логов.".to_string()
-        } else {
-            self.buffer
+            return "Алгоритм не произвел никаких логов.".to_string();
+        }
+        if let Some(line) = iterations_line(self.iterations) {
+            self.log(line);
+        }
+        self.buffer
+    }
+}
+
+// Реализация, которая выводит поля записей таблицей с выровненными столбцами.
+// Свободные сообщения идут отдельными строками; новый набор полей начинает новую таблицу
+pub struct TableLogger {
+    // Блоки вывода: либо сообщение, либо таблица (заголовок, строки)
+    blocks: Vec<TableBlock>,
+    iterations: usize,
+}
+
+enum TableBlock {
+    Message(String),
+    Table(Vec<String>, Vec<Vec<String>>),
+}
+
+impl TableLogger {
+    pub fn new() -> Self {
+        Self { blocks: Vec::new(), iterations: 0 }
+    }
+}
+
+impl Logger for TableLogger {
+    fn log(&mut self, message: String) {
+        self.blocks.push(TableBlock::Message(message));
+    }
+
+    fn log_step(&mut self, x: i32, y: i32, _message: String, fields: &[(&str, String)]) {
+        self.log_fields(x, y, fields);
+    }
+
+    fn log_fields(&mut self, _x: i32, _y: i32, fields: &[(&str, String)]) {
+        let row: Vec<String> = fields.iter().map(|(_, v)| v.clone()).collect();
+        if let Some(TableBlock::Table(header, rows)) = self.blocks.last_mut() {
+            if header.iter().map(String::as_str).eq(fields.iter().map(|(name, _)| *name)) {
+                rows.push(row);
+                return;
+            }
+        }
+        let header = fields.iter().map(|(name, _)| name.to_string()).collect();
+        self.blocks.push(TableBlock::Table(header, vec![row]));
+    }
+
+    fn iteration(&mut self) {
+        self.iterations += 1;
+    }
+
+    fn can_write(&self) -> bool {
+        true
+    }
+
+    fn results(self) -> String {
+        if self.blocks.is_empty() {
+            return "Алгоритм не произвел никаких логов.".to_string();
+        }
+        let mut out = String::new();
+        let summary = iterations_line(self.iterations).map(TableBlock::Message);
+        for block in self.blocks.into_iter().chain(summary) {
+            match block {
+                TableBlock::Message(message) => {
+                    out.push_str(&message);
+                    out.push('\n');
+                }
+                TableBlock::Table(header, rows) => {
+                    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+                    for row in &rows {
+                        for (w, cell) in widths.iter_mut().zip(row) {
+                            *w = (*w).max(cell.chars().count());
+                        }
+                    }
+                    let line = |cells: &[String]| {
+                        let padded: Vec<String> = cells.iter().zip(&widths).map(|(c, &w)| format!("{:>w$}", c, w = w)).collect();
+                        padded.join(" | ")
+                    };
+                    out.push_str(&line(&header));
+                    out.push('\n');
+                    let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+                    out.push_str(&rule.join("-+-"));
+                    out.push('\n');
+                    for row in &rows {
+                        out.push_str(&line(row));
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+// Обёртка, пропускающая записи о пикселях только внутри заданного прямоугольника.
+// Обычная строка лога считается заголовком следующих за ней записей о пикселях (например, "x=3, y=9, d=-5"
+// перед восемью отражениями): она придерживается и выводится, только если хотя бы один из этих пикселей
+// попал в прямоугольник. Строки, за которыми записей о пикселях нет, проходят без изменений
+pub struct RangeFilterLogger<L: Logger> {
+    inner: L,
+    min: (i32, i32),
+    max: (i32, i32),
+    // Придержанная строка и признак того, что после неё уже были отброшенные пиксели
+    held: Option<(String, bool)>,
+}
+
+impl<L: Logger> RangeFilterLogger<L> {
+    pub fn new(inner: L, min: (i32, i32), max: (i32, i32)) -> Self {
+        Self { inner, min, max, held: None }
+    }
+
+    // Решает судьбу записи о пикселе (x, y) и придержанного заголовка перед ней
+    fn accept(&mut self, x: i32, y: i32) -> bool {
+        let inside = x >= self.min.0 && x <= self.max.0 && y >= self.min.1 && y <= self.max.1;
+        if inside {
+            if let Some((header, _)) = self.held.take() {
+                self.inner.log(header);
+            }
+        } else if let Some((_, dropped)) = &mut self.held {
+            *dropped = true;
+        }
+        inside
+    }
+
+    // Придержанная строка без отброшенных пикселей после неё - обычное сообщение
+    fn release_held(&mut self) {
+        if let Some((message, false)) = self.held.take() {
+            self.inner.log(message);
+        }
+    }
+}
+
+impl<L: Logger> Logger for RangeFilterLogger<L> {
+    fn log(&mut self, message: String) {
+        self.release_held();
+        self.held = Some((message, false));
+    }
+
+    fn log_pixel(&mut self, x: i32, y: i32, message: String) {
+        if self.accept(x, y) {
+            self.inner.log_pixel(x, y, message);
+        }
+    }
+
+    fn log_fields(&mut self, x: i32, y: i32, fields: &[(&str, String)]) {
+        if self.accept(x, y) {
+            self.inner.log_fields(x, y, fields);
+        }
+    }
+
+    fn log_step(&mut self, x: i32, y: i32, message: String, fields: &[(&str, String)]) {
+        if self.accept(x, y) {
+            self.inner.log_step(x, y, message, fields);
+        }
+    }
+
+    fn progress(&mut self, fraction: f32) {
+        self.inner.progress(fraction);
+    }
+
+    fn iteration(&mut self) {
+        self.inner.iteration();
+    }
+
+    fn can_write(&self) -> bool {
+        self.inner.can_write()
+    }
+
+    fn results(mut self) -> String {
+        self.release_held();
+        self.inner.results()
+    }
+}
+
+// Обёртка, публикующая прогресс в общую ячейку, которую читает поток интерфейса,
+// и считающая итерации основного цикла для строки состояния (отметки передаются и внутреннему логгеру)
+pub struct ProgressLogger<L: Logger> {
+    inner: L,
+    progress: Arc<AtomicU32>,
+    iterations: Option<usize>,
+}
+
+impl<L: Logger> ProgressLogger<L> {
+    pub fn new(inner: L, progress: Arc<AtomicU32>) -> Self {
+        Self { inner, progress, iterations: None }
+    }
+
+    // Число итераций; None, если алгоритм о них не сообщает
+    pub fn iterations(&self) -> Option<usize> {
+        self.iterations
+    }
+}
+
+impl<L: Logger> Logger for ProgressLogger<L> {
+    fn log(&mut self, message: String) {
+        self.inner.log(message);
+    }
+
+    fn log_pixel(&mut self, x: i32, y: i32, message: String) {
+        self.inner.log_pixel(x, y, message);
+    }
+
+    fn log_fields(&mut self, x: i32, y: i32, fields: &[(&str, String)]) {
+        self.inner.log_fields(x, y, fields);
+    }
+
+    fn log_step(&mut self, x: i32, y: i32, message: String, fields: &[(&str, String)]) {
+        self.inner.log_step(x, y, message, fields);
+    }
+
+    fn progress(&mut self, fraction: f32) {
+        self.progress.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    fn iteration(&mut self) {
+        *self.iterations.get_or_insert(0) += 1;
+        self.inner.iteration();
+    }
+
+    fn can_write(&self) -> bool {
+        self.inner.can_write()
+    }
+
+    fn results(self) -> String {
+        self.inner.results()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo;
+    use eframe::egui::Pos2;
+
+    #[test]
+    fn table_logger_aligns_bresenham_columns() {
+        let mut logger = TableLogger::new();
+        algo::bresenham_line(Pos2::new(-12.0, 3.0), Pos2::new(9.0, -4.0), &mut logger);
+        let out = logger.results();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2 + 22);
+        assert_eq!(lines[0].split(" | ").map(str::trim).collect::<Vec<_>>(), ["шаг", "x", "y", "ошибка"]);
+        assert!(lines[1].chars().all(|c| c == '-' || c == '+'));
+
+        // Все строки одной ширины, разделители столбцов стоят на одних и тех же позициях
+        let separators = |line: &str| -> Vec<usize> {
+            line.chars().enumerate().filter(|&(_, c)| c == '|' || c == '+').map(|(i, _)| i).collect()
+        };
+        let width = lines[0].chars().count();
+        for line in &lines {
+            assert_eq!(line.chars().count(), width, "{}", line);
+            assert_eq!(separators(line), separators(lines[0]), "{}", line);
         }
+        let first: Vec<&str> = lines[2].split(" | ").map(str::trim).collect();
+        assert_eq!(first[..3], ["0", "-12", "3"]);
+        assert_eq!(lines.last().unwrap().split(" | ").map(str::trim).nth(1), Some("9"));
+    }
+
+    // Окружность Брезенхема проходит один октант: около R/√2 итераций, по 8 пикселей на итерацию.
+    // Число итераций видно и в строке состояния (ProgressLogger), и в тексте лога
+    #[test]
+    fn circle_iterations_reach_every_logger() {
+        for r in [5.0_f32, 10.0, 40.0, 100.0] {
+            let progress = Arc::new(AtomicU32::new(0));
+            let inner = RangeFilterLogger::new(StringLogger::new(), (-1000, -1000), (1000, 1000));
+            let mut logger = ProgressLogger::new(inner, progress);
+            let pixels = algo::bresenham_circle(Pos2::new(0.0, 0.0), r, algo::ALL_OCTANTS, &mut logger);
+            let iterations = logger.iterations().unwrap();
+            let expected = (r / 2.0_f32.sqrt()).ceil() as usize;
+            assert!(iterations.abs_diff(expected) <= 1, "r = {}: {} итераций", r, iterations);
+            assert!(pixels.len() <= 8 * iterations);
+            let text = logger.results();
+            assert_eq!(text.lines().last(), Some(format!("Итераций основного цикла: {}", iterations).as_str()));
+        }
+
+        let mut table = TableLogger::new();
+        algo::bresenham_circle(Pos2::new(0.0, 0.0), 10.0, algo::ALL_OCTANTS, &mut table);
+        assert_eq!(table.results().lines().last(), Some("Итераций основного цикла: 7"));
+    }
+
+    #[test]
+    fn range_filter_drops_outside_events() {
+        let mut logger = RangeFilterLogger::new(StringLogger::new(), (0, 0), (5, 5));
+        logger.log("начало".to_string());
+        logger.log("заголовок вне".to_string());
+        logger.log_pixel(10, 0, "(10, 0)".to_string());
+        logger.log_pixel(0, -1, "(0, -1)".to_string());
+        logger.log("заголовок внутри".to_string());
+        logger.log_pixel(9, 9, "(9, 9)".to_string());
+        logger.log_pixel(5, 5, "(5, 5)".to_string());
+        logger.log_fields(6, 0, &[("x", "6".to_string())]);
+        logger.log_step(1, 2, "(1, 2)".to_string(), &[]);
+        logger.log("итог".to_string());
+        assert_eq!(logger.results(), "начало\nзаголовок внутри\n(5, 5)\n(1, 2)\nитог\n");
+    }
+
+    // Верхний октант окружности R = 10: остаются только итерации, отражения которых попали в прямоугольник
+    #[test]
+    fn range_filter_keeps_only_top_octant_of_circle() {
+        let mut logger = RangeFilterLogger::new(StringLogger::new(), (0, 8), (7, 10));
+        algo::bresenham_circle(Pos2::new(0.0, 0.0), 10.0, algo::ALL_OCTANTS, &mut logger);
+        let text = logger.results();
+        let lines: Vec<&str> = text.lines().collect();
+        let headers = lines.iter().filter(|l| l.starts_with("x=")).count();
+        let pixels: Vec<&str> = lines.iter().copied().filter(|l| l.contains("октант")).collect();
+        // Итерации x = 0..5; на последней (x = 6, y = 7) пиксели лежат ниже прямоугольника.
+        // При x = 0 в прямоугольник попадает и отражение октанта 2 - та же точка (0, 10)
+        assert_eq!(headers, 6);
+        assert_eq!(pixels[..2], ["  октант 1: (0, 10)", "  октант 2: (0, 10)"]);
+        assert_eq!(pixels.len(), 7);
+        assert!(pixels[2..].iter().all(|l| l.starts_with("  октант 1:")), "{:?}", pixels);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn string_logger_keeps_step_text() {
+        let mut logger = StringLogger::new();
+        algo::bresenham_line(Pos2::new(0.0, 0.0), Pos2::new(2.0, 1.0), &mut logger);
+        assert_eq!(logger.results().lines().next(), Some("Пиксель: (0, 0), Ошибка: 1"));
+
+        let mut logger = StringLogger::new();
+        algo::dda(Pos2::new(0.0, 0.0), Pos2::new(2.0, 1.0), algo::RoundingMode::HalfUp, &mut logger);
+        assert_eq!(logger.results().lines().nth(1), Some("Шаг 1: x = 1.00, y = 0.50 -> Пиксель (1, 1)"));
+    }
+}
@@ -1,12 +1,17 @@
 mod app;
 mod algo;
+mod clip;
+mod expr;
+mod history;
 mod logger;
+mod report;
+mod shortcuts;
 
 use app::GraphicsLabApp;
 
 fn main() {
     let native_options = eframe::NativeOptions::default();
-    eframe::run_native(
+    let _ = eframe::run_native(
         "Лабораторная работа №3: Растровые алгоритмы",
         native_options,
         Box::new(|_cc| Box::new(GraphicsLabApp::default())),
@@ -0,0 +1,663 @@
+// src/report.rs
+
+use crate::algo::PixelRecord;
+
+// Формат отчёта для сдачи лабораторной работы
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Содержимое отчёта: всё, что нужно приложить к лабораторной
+pub struct Report {
+    pub algorithm: String,
+    pub parameters: Vec<(String, String)>,
+    pub metrics: Vec<(String, String)>,
+    pub log: String,
+    pub svg: String,
+}
+
+impl Report {
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Html => self.render_html(),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut out = format!("# Отчёт: {}\n\n", self.algorithm);
+        out.push_str("## Параметры\n\n| Параметр | Значение |\n|---|---|\n");
+        for (name, value) in &self.parameters {
+            out.push_str(&format!("| {} | {} |\n", name, value));
+        }
+        out.push_str("\n## Метрики\n\n| Метрика | Значение |\n|---|---|\n");
+        for (name, value) in &self.metrics {
+            out.push_str(&format!("| {} | {} |\n", name, value));
+        }
+        out.push_str("\n## Результат\n\n");
+        out.push_str(&self.svg);
+        out.push_str("\n\n## Лог вычислений\n\n```\n");
+        out.push_str(&self.log);
+        out.push_str("\n```\n");
+        out
+    }
+
+    fn render_html(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Отчёт: {0}</title></head>\n<body>\n<h1>Отчёт: {0}</h1>\n",
+            escape_html(&self.algorithm)
+        );
+        out.push_str("<h2>Параметры</h2>\n<table border=\"1\">\n");
+        for (name, value) in &self.parameters {
+            out.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", escape_html(name), escape_html(value)));
+        }
+        out.push_str("</table>\n<h2>Метрики</h2>\n<table border=\"1\">\n");
+        for (name, value) in &self.metrics {
+            out.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", escape_html(name), escape_html(value)));
+        }
+        out.push_str("</table>\n<h2>Результат</h2>\n");
+        out.push_str(&self.svg);
+        out.push_str("\n<h2>Лог вычислений</h2>\n<pre>");
+        out.push_str(&escape_html(&self.log));
+        out.push_str("</pre>\n</body>\n</html>\n");
+        out
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// SVG-изображение результата: один квадрат на пиксель, прозрачность равна интенсивности
+pub fn pixels_to_svg(records: impl Iterator<Item = PixelRecord>) -> String {
+    let records: Vec<PixelRecord> = records.collect();
+    if records.is_empty() {
+        return "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\"></svg>".to_string();
+    }
+    let min_x = records.iter().map(|r| r.x).min().unwrap();
+    let max_x = records.iter().map(|r| r.x).max().unwrap();
+    let min_y = records.iter().map(|r| r.y).min().unwrap();
+    let max_y = records.iter().map(|r| r.y).max().unwrap();
+    let width = max_x - min_x + 1;
+    let height = max_y - min_y + 1;
+
+    // Масштабируем так, чтобы большая сторона была не меньше 400 точек
+    let scale = (400 / width.max(height)).max(1);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\" shape-rendering=\"crispEdges\">",
+        width * scale, height * scale, min_x, min_y, width, height
+    );
+    svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>", min_x, min_y, width, height));
+    for r in records {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"black\" fill-opacity=\"{:.3}\"/>",
+            r.x, r.y, r.intensity.clamp(0.0, 1.0)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+// Простая таблица с экспортом в Markdown и CSV
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub name: String,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(name: &str, header: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            header: header.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("| {} |\n", self.header.join(" | "));
+        out.push_str(&format!("|{}\n", "---|".repeat(self.header.len())));
+        for row in &self.rows {
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let line = |cells: &[String]| {
+            cells.iter().map(|c| csv_escape(c)).collect::<Vec<_>>().join(",")
+        };
+        let mut out = line(&self.header);
+        out.push('\n');
+        for row in &self.rows {
+            out.push_str(&line(row));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn csv_escape(cell: &str) -> String {
+    if cell.contains(',') || cell.contains('"') || cell.contains('\n') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+// Версия формата лога пикселей; лог другой версии не загружается
+pub const PIXEL_LOG_VERSION: u32 = 1;
+const PIXEL_LOG_FORMAT: &str = "graphics_lab pixel log";
+
+// Лог пикселей в JSON: формат и версия, алгоритм, затем по объекту на пиксель.
+// Нечисловые значения (NaN, бесконечность) записываются как null
+pub fn pixel_log_json(algorithm: &str, records: impl Iterator<Item = PixelRecord>) -> String {
+    let number = |v: f32| if v.is_finite() { format!("{}", v) } else { "null".to_string() };
+    let mut out = format!(
+        "{{\n  \"format\": {},\n  \"version\": {},\n  \"algorithm\": {},\n  \"pixels\": [",
+        json_string(PIXEL_LOG_FORMAT),
+        PIXEL_LOG_VERSION,
+        json_string(algorithm)
+    );
+    for (i, r) in records.enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        out.push_str(&format!(
+            "    {{\"x\": {}, \"y\": {}, \"intensity\": {}, \"step\": {}, \"tag\": {}, \"value\": {}}}",
+            r.x, r.y, number(r.intensity), r.step_index, r.tag, number(r.value)
+        ));
+    }
+    out.push_str("\n  ]\n}\n");
+    out
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Восстановление пикселей из лога. Обязательны только поля x и y;
+// без intensity пиксель считается полным, без step - номер шага равен номеру в массиве
+pub fn parse_pixel_log(text: &str) -> Result<Vec<PixelRecord>, String> {
+    let root = Json::parse(text)?;
+    if root.get("format").and_then(Json::as_str) != Some(PIXEL_LOG_FORMAT) {
+        return Err("Файл не является логом пикселей".to_string());
+    }
+    let version = root.get("version").and_then(Json::as_number).ok_or("В логе нет версии формата")?;
+    if version.parse::<u32>() != Ok(PIXEL_LOG_VERSION) {
+        return Err(format!("Версия лога {} не поддерживается (ожидается {})", version, PIXEL_LOG_VERSION));
+    }
+    let Some(Json::Array(pixels)) = root.get("pixels") else {
+        return Err("В логе нет массива pixels".to_string());
+    };
+
+    let mut records = Vec::new();
+    for (i, pixel) in pixels.iter().enumerate() {
+        let bad = |name: &str| format!("Пиксель {}: некорректное поле {}", i + 1, name);
+        let x = field(pixel, "x").ok().flatten().ok_or_else(|| bad("x"))?;
+        let y = field(pixel, "y").ok().flatten().ok_or_else(|| bad("y"))?;
+        let intensity = field(pixel, "intensity").map_err(|_| bad("intensity"))?.unwrap_or(1.0);
+        let step_index = field(pixel, "step").map_err(|_| bad("step"))?.unwrap_or(i);
+        let tag = field(pixel, "tag").map_err(|_| bad("tag"))?.unwrap_or(0);
+        let value = field(pixel, "value").map_err(|_| bad("value"))?.unwrap_or(0.0);
+        records.push(PixelRecord { x, y, intensity, step_index, tag, value });
+    }
+    Ok(records)
+}
+
+// Числовое поле объекта: отсутствующее или null - None, поле не того типа - ошибка
+fn field<T: std::str::FromStr>(object: &Json, name: &str) -> Result<Option<T>, ()> {
+    match object.get(name) {
+        None | Some(Json::Null) => Ok(None),
+        Some(value) => value.as_number().and_then(|s| s.parse().ok()).map(Some).ok_or(()),
+    }
+}
+
+// Разобранное значение JSON. Числа хранятся текстом, чтобы читать их сразу в нужный тип без потери точности
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// Ограничение вложенности массивов и объектов, чтобы разбор не переполнил стек
+const MAX_JSON_DEPTH: usize = 64;
+
+impl Json {
+    fn parse(text: &str) -> Result<Json, String> {
+        let mut chars = text.chars().peekable();
+        let value = Self::parse_value(&mut chars, 0)?;
+        Self::skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("JSON: лишний символ '{}' после значения", c)),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<&str> {
+        match self {
+            Json::Number(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+        while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn expect(chars: &mut std::iter::Peekable<std::str::Chars>, word: &str) -> Result<(), String> {
+        if word.chars().all(|w| chars.next() == Some(w)) {
+            Ok(())
+        } else {
+            Err(format!("JSON: ожидалось {}", word))
+        }
+    }
+
+    fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>, depth: usize) -> Result<Json, String> {
+        if depth > MAX_JSON_DEPTH {
+            return Err("JSON: слишком глубокая вложенность".to_string());
+        }
+        Self::skip_whitespace(chars);
+        match chars.peek() {
+            None => Err("JSON: неожиданный конец текста".to_string()),
+            Some('n') => Self::expect(chars, "null").map(|_| Json::Null),
+            Some('t') => Self::expect(chars, "true").map(|_| Json::Bool(true)),
+            Some('f') => Self::expect(chars, "false").map(|_| Json::Bool(false)),
+            Some('"') => Self::parse_string(chars).map(Json::String),
+            Some('[') => {
+                chars.next();
+                let mut items = Vec::new();
+                Self::skip_whitespace(chars);
+                if chars.next_if_eq(&']').is_some() {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(Self::parse_value(chars, depth + 1)?);
+                    Self::skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Json::Array(items)),
+                        _ => return Err("JSON: ожидалось ',' или ']'".to_string()),
+                    }
+                }
+            }
+            Some('{') => {
+                chars.next();
+                let mut fields = Vec::new();
+                Self::skip_whitespace(chars);
+                if chars.next_if_eq(&'}').is_some() {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    Self::skip_whitespace(chars);
+                    if chars.peek() != Some(&'"') {
+                        return Err("JSON: ожидалось имя поля".to_string());
+                    }
+                    let key = Self::parse_string(chars)?;
+                    Self::skip_whitespace(chars);
+                    Self::expect(chars, ":")?;
+                    fields.push((key, Self::parse_value(chars, depth + 1)?));
+                    Self::skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Json::Object(fields)),
+                        _ => return Err("JSON: ожидалось ',' или '}'".to_string()),
+                    }
+                }
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                    number.push(c);
+                }
+                Ok(Json::Number(number))
+            }
+            Some(c) => Err(format!("JSON: неожиданный символ '{}'", c)),
+        }
+    }
+
+    fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+        chars.next();
+        let mut out = String::new();
+        loop {
+            match chars.next() {
+                None => return Err("JSON: незакрытая строка".to_string()),
+                Some('"') => return Ok(out),
+                Some('\\') => match chars.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                            return Err("JSON: некорректный \\u".to_string());
+                        }
+                        let code = u32::from_str_radix(&hex, 16).unwrap_or(0);
+                        // Суррогатные пары в логах не встречаются, вместо них - знак замены
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    _ => return Err("JSON: некорректная escape-последовательность".to_string()),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+}
+
+// Наибольшее число ячеек матрицы: далёкие друг от друга пиксели дали бы огромный почти пустой прямоугольник
+pub const MAX_GRID_CELLS: usize = 4_000_000;
+
+// Плотная матрица интенсивностей прямоугольной области растра, строка матрицы - строка y.
+// Интенсивности пикселей, выведенных несколько раз, складываются до 1
+pub struct PixelGrid {
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<f32>,
+}
+
+impl PixelGrid {
+    // Матрица по ограничивающему прямоугольнику пикселей
+    pub fn from_records(records: impl Iterator<Item = PixelRecord>) -> Result<Self, String> {
+        let records: Vec<PixelRecord> = records.collect();
+        let min = (records.iter().map(|r| r.x).min().unwrap_or(0), records.iter().map(|r| r.y).min().unwrap_or(0));
+        let max = (records.iter().map(|r| r.x).max().unwrap_or(-1), records.iter().map(|r| r.y).max().unwrap_or(-1));
+        Self::cropped(records.into_iter(), min, max)
+    }
+
+    // Матрица области от min до max включительно; пиксели вне области отбрасываются.
+    // Область больше MAX_GRID_CELLS ячеек не выделяется
+    pub fn cropped(records: impl Iterator<Item = PixelRecord>, min: (i32, i32), max: (i32, i32)) -> Result<Self, String> {
+        let width = (max.0 as i64 - min.0 as i64 + 1).max(0) as usize;
+        let height = (max.1 as i64 - min.1 as i64 + 1).max(0) as usize;
+        if width.saturating_mul(height) > MAX_GRID_CELLS {
+            return Err(format!("Матрица {} x {} слишком велика: больше {} ячеек", width, height, MAX_GRID_CELLS));
+        }
+        let mut grid = Self { origin_x: min.0, origin_y: min.1, width, height, cells: vec![0.0; width * height] };
+        for r in records {
+            let (col, row) = (r.x as i64 - min.0 as i64, r.y as i64 - min.1 as i64);
+            if (0..width as i64).contains(&col) && (0..height as i64).contains(&row) {
+                let cell = &mut grid.cells[row as usize * width + col as usize];
+                *cell = (*cell + r.intensity).min(1.0);
+            }
+        }
+        Ok(grid)
+    }
+
+    // Интенсивность в столбце col и строке row матрицы
+    pub fn get(&self, col: usize, row: usize) -> f32 {
+        self.cells[row * self.width + col]
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        self.cells.chunks(self.width.max(1))
+    }
+}
+
+// Матрица в тексте, который читается numpy.loadtxt: строки с '#' - комментарии, в первой - начало координат и размер
+pub fn pixels_to_grid_text(records: impl Iterator<Item = PixelRecord>) -> Result<String, String> {
+    PixelGrid::from_records(records).map(|grid| grid_to_text(&grid))
+}
+
+pub fn grid_to_text(grid: &PixelGrid) -> String {
+    let mut out = format!("# origin_x={} origin_y={} width={} height={}\n", grid.origin_x, grid.origin_y, grid.width, grid.height);
+    if grid.width == 0 || grid.height == 0 {
+        return out;
+    }
+    out.push_str(&format!("# grid[i][j] - пиксель ({} + j, {} + i)\n", grid.origin_x, grid.origin_y));
+    for row in grid.rows() {
+        let cells: Vec<String> = row.iter().map(|v| if v.fract() == 0.0 { format!("{}", v) } else { format!("{:.3}", v) }).collect();
+        out.push_str(&cells.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+// PNG в оттенках серого: белый фон, пиксель тем темнее, чем больше интенсивность. Как и у SVG,
+// большая сторона увеличивается не меньше чем до 400 точек. Данные сжимаются несжатыми блоками deflate
+pub fn grid_to_png(grid: &PixelGrid) -> Vec<u8> {
+    let scale = (400 / grid.width.max(grid.height).max(1)).max(1);
+    let (width, height) = (grid.width.max(1) * scale, grid.height.max(1) * scale);
+    let mut raw = Vec::with_capacity((width + 1) * height);
+    for y in 0..height {
+        // Фильтр строки 0 - байты без преобразования
+        raw.push(0);
+        for x in 0..width {
+            let intensity = if grid.width == 0 || grid.height == 0 { 0.0 } else { grid.get(x / scale, y / scale) };
+            raw.push(255 - (intensity.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = raw.chunks(u16::MAX as usize).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i + 1 == blocks.len()) as u8);
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in &raw {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 бит на пиксель, оттенки серого, стандартные сжатие и фильтрация, без чересстрочности
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    for (kind, data) in [(b"IHDR", &header), (b"IDAT", &zlib), (b"IEND", &Vec::new())] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    png
+}
+
+// CRC-32 (многочлен 0xEDB88320), которым PNG проверяет блоки
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_records() -> Vec<PixelRecord> {
+        vec![
+            PixelRecord { x: -3, y: 7, intensity: 1.0, step_index: 0, tag: 0, value: -12.0 },
+            PixelRecord { x: 0, y: 0, intensity: 0.123_456_79, step_index: 1, tag: 3, value: 0.1 },
+            PixelRecord { x: 2_000_000, y: -5, intensity: 1e-7, step_index: 42, tag: 255, value: 3.5e12 },
+        ]
+    }
+
+    #[test]
+    fn report_has_all_sections() {
+        let report = Report {
+            algorithm: "ЦДА <P1, P2>".to_string(),
+            parameters: vec![("P1".to_string(), "(0, 0)".to_string())],
+            metrics: vec![("Пикселей".to_string(), "5".to_string())],
+            log: "Шаг 0: x < 1 & y > 0".to_string(),
+            svg: pixels_to_svg(sample_records().into_iter()),
+        };
+        let sections = ["Параметры", "Метрики", "Результат", "Лог вычислений"];
+
+        let markdown = report.render(ReportFormat::Markdown);
+        assert!(markdown.starts_with("# Отчёт: ЦДА <P1, P2>\n"));
+        for section in sections {
+            assert!(markdown.contains(&format!("\n## {}\n", section)), "{}", section);
+        }
+
+        let html = report.render(ReportFormat::Html);
+        assert!(html.contains("<h1>Отчёт: ЦДА &lt;P1, P2&gt;</h1>"));
+        for section in sections {
+            assert!(html.contains(&format!("<h2>{}</h2>", section)), "{}", section);
+        }
+        assert!(html.contains("Шаг 0: x &lt; 1 &amp; y &gt; 0"));
+    }
+
+    #[test]
+    fn grid_text_dimensions_and_cells() {
+        let records = [
+            PixelRecord::from_antialiased(0, (-2, 3, 1.0)),
+            PixelRecord::from_antialiased(1, (1, 3, 0.25)),
+            PixelRecord::from_antialiased(2, (1, 3, 0.5)),
+            PixelRecord::from_antialiased(3, (0, 5, 0.8)),
+            PixelRecord::from_antialiased(4, (0, 5, 0.8)),
+        ];
+        let text = pixels_to_grid_text(records.into_iter()).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("# origin_x=-2 origin_y=3 width=4 height=3"));
+        let rows: Vec<&str> = lines.filter(|l| !l.starts_with('#')).collect();
+        // Повторно выведенные пиксели складываются, но не больше 1
+        assert_eq!(rows, ["1 0 0 0.750", "0 0 0 0", "0 0 1 0"]);
+        assert_eq!(pixels_to_grid_text(std::iter::empty()).unwrap(), "# origin_x=0 origin_y=0 width=0 height=0\n");
+    }
+
+    #[test]
+    fn grid_refuses_huge_area() {
+        let far = [PixelRecord::from_antialiased(0, (0, 0, 1.0)), PixelRecord::from_antialiased(1, (100_000, 100_000, 1.0))];
+        assert!(pixels_to_grid_text(far.into_iter()).is_err());
+        let extreme = [PixelRecord::from_antialiased(0, (i32::MIN, 0, 1.0)), PixelRecord::from_antialiased(1, (i32::MAX, 0, 1.0))];
+        assert!(PixelGrid::from_records(extreme.into_iter()).is_err());
+        assert!(PixelGrid::cropped(std::iter::empty(), (0, 0), (1999, 1999)).is_ok());
+    }
+
+    #[test]
+    fn cropped_png_has_selection_size() {
+        let grid = PixelGrid::cropped(sample_records().into_iter(), (-5, 0), (4, 9)).unwrap();
+        assert_eq!((grid.width, grid.height), (10, 10));
+        assert_eq!(grid.get(2, 7), 1.0);
+        let png = grid_to_png(&grid);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        // Сторона 10 увеличивается в 40 раз, до 400 точек
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 400);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 400);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(&png[png.len() - 8..], &[b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn pixel_log_round_trip() {
+        let records = sample_records();
+        let text = pixel_log_json("Брезенхем \"отрезок\"\\", records.iter().copied());
+        assert_eq!(parse_pixel_log(&text), Ok(records));
+        assert_eq!(parse_pixel_log(&pixel_log_json("пусто", std::iter::empty())), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn pixel_log_non_finite_values() {
+        let record = PixelRecord { x: 1, y: 2, intensity: 1.0, step_index: 0, tag: 0, value: f32::NAN };
+        let parsed = parse_pixel_log(&pixel_log_json("", std::iter::once(record))).unwrap();
+        assert_eq!(parsed[0].value, 0.0);
+    }
+
+    #[test]
+    fn pixel_log_optional_fields() {
+        let text = r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [{"x": 4, "y": 5}, {"y": 1, "x": 2, "step": 9}]}"#;
+        let records = parse_pixel_log(text).unwrap();
+        assert_eq!((records[0].x, records[0].y, records[0].intensity, records[0].step_index), (4, 5, 1.0, 0));
+        assert_eq!((records[1].x, records[1].y, records[1].step_index), (2, 1, 9));
+    }
+
+    #[test]
+    fn pixel_log_rejects_unknown_version() {
+        let text = pixel_log_json("", sample_records().into_iter());
+        let future = text.replace(&format!("\"version\": {}", PIXEL_LOG_VERSION), "\"version\": 2");
+        assert!(parse_pixel_log(&future).unwrap_err().contains("Версия лога 2"));
+        let missing = text.replace(&format!("\"version\": {},", PIXEL_LOG_VERSION), "");
+        assert!(parse_pixel_log(&missing).is_err());
+    }
+
+    #[test]
+    fn pixel_log_rejects_malformed_input() {
+        let inputs = [
+            "",
+            "x,y\n1,2",
+            "[]",
+            r#"{"format": "other", "version": 1, "pixels": []}"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1}"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [{"x": 1}]}"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [{"x": 1.5, "y": 2}]}"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [{"x": 1, "y": 2, "tag": "a"}]}"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [] } trailing"#,
+            r#"{"format": "graphics_lab pixel log", "version": 1, "pixels": [{"x": 1, "y": 2}"#,
+            "\"\\u12",
+            &"[".repeat(10_000),
+        ];
+        for text in inputs {
+            assert!(parse_pixel_log(text).is_err(), "{:?}", text);
+        }
+    }
+}
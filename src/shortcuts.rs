@@ -0,0 +1,180 @@
+// src/shortcuts.rs
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+// Файл, в котором назначения клавиш сохраняются между запусками
+pub const SHORTCUTS_PATH: &str = "shortcuts.txt";
+
+// Клавиши, которые можно назначить действию (стрелки и +/- заняты навигацией по холсту)
+const BINDABLE_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Enter, Key::Space, Key::Tab, Key::Backspace, Key::Insert, Key::Delete, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+];
+
+// Действия, которым можно назначить сочетание клавиш
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Run,
+    ToggleLog,
+    Help,
+    NextAlgorithm,
+    PreviousAlgorithm,
+    ResetView,
+    FitView,
+    Goto,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Run,
+        Action::ToggleLog,
+        Action::Help,
+        Action::NextAlgorithm,
+        Action::PreviousAlgorithm,
+        Action::ResetView,
+        Action::FitView,
+        Action::Goto,
+    ];
+
+    // Имя действия в файле настроек
+    fn id(self) -> &'static str {
+        match self {
+            Action::Run => "run",
+            Action::ToggleLog => "toggle_log",
+            Action::Help => "help",
+            Action::NextAlgorithm => "next_algorithm",
+            Action::PreviousAlgorithm => "previous_algorithm",
+            Action::ResetView => "reset_view",
+            Action::FitView => "fit_view",
+            Action::Goto => "goto",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Run => "Запуск",
+            Action::ToggleLog => "Вкл/выкл лог вычислений",
+            Action::Help => "Справка",
+            Action::NextAlgorithm => "Следующий алгоритм",
+            Action::PreviousAlgorithm => "Предыдущий алгоритм",
+            Action::ResetView => "Вид по умолчанию",
+            Action::FitView => "Вписать результат в окно",
+            Action::Goto => "Перейти к точке",
+        }
+    }
+
+    fn default_shortcut(self) -> KeyboardShortcut {
+        let key = match self {
+            Action::Run => Key::F5,
+            Action::ToggleLog => Key::L,
+            Action::Help => Key::F1,
+            Action::NextAlgorithm => Key::PageDown,
+            Action::PreviousAlgorithm => Key::PageUp,
+            Action::ResetView => Key::Home,
+            Action::FitView => Key::F,
+            Action::Goto => Key::G,
+        };
+        KeyboardShortcut::new(Modifiers::NONE, key)
+    }
+}
+
+// Назначения клавиш: по одному сочетанию на каждое действие, в порядке Action::ALL
+pub struct Shortcuts {
+    bindings: [KeyboardShortcut; Action::ALL.len()],
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self { bindings: Action::ALL.map(Action::default_shortcut) }
+    }
+}
+
+impl Shortcuts {
+    pub fn get(&self, action: Action) -> KeyboardShortcut {
+        self.bindings[action as usize]
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: KeyboardShortcut) {
+        self.bindings[action as usize] = shortcut;
+    }
+
+    // Другое действие, которому уже назначено это сочетание
+    pub fn conflict(&self, shortcut: KeyboardShortcut, action: Action) -> Option<Action> {
+        Action::ALL.into_iter().find(|&other| other != action && self.get(other) == shortcut)
+    }
+
+    // Строки вида "run = Ctrl+F5"
+    pub fn to_text(&self) -> String {
+        Action::ALL
+            .iter()
+            .map(|&action| format!("{} = {}\n", action.id(), format_shortcut(self.get(action))))
+            .collect()
+    }
+
+    // Неизвестные и испорченные строки пропускаются, для них остаётся назначение по умолчанию
+    pub fn parse(text: &str) -> Self {
+        let mut shortcuts = Self::default();
+        for line in text.lines() {
+            let Some((id, value)) = line.split_once('=') else { continue };
+            let action = Action::ALL.into_iter().find(|a| a.id() == id.trim());
+            if let (Some(action), Some(shortcut)) = (action, parse_shortcut(value.trim())) {
+                shortcuts.set(action, shortcut);
+            }
+        }
+        shortcuts
+    }
+
+    // Без файла настроек - назначения по умолчанию
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path).map(|text| Self::parse(&text)).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text()).map_err(|e| format!("Не удалось сохранить {}: {}", path, e))
+    }
+}
+
+// Ctrl и Cmd не различаются: сочетание с Ctrl, назначенное на одной системе, работает и на другой
+pub fn capture_shortcut(modifiers: Modifiers, key: Key) -> Option<KeyboardShortcut> {
+    if !BINDABLE_KEYS.contains(&key) {
+        return None;
+    }
+    let mut pattern = if modifiers.command || modifiers.ctrl { Modifiers::COMMAND } else { Modifiers::NONE };
+    pattern.alt = modifiers.alt;
+    pattern.shift = modifiers.shift;
+    Some(KeyboardShortcut::new(pattern, key))
+}
+
+pub fn format_shortcut(shortcut: KeyboardShortcut) -> String {
+    let mut text = String::new();
+    if shortcut.modifiers.command {
+        text.push_str("Ctrl+");
+    }
+    if shortcut.modifiers.alt {
+        text.push_str("Alt+");
+    }
+    if shortcut.modifiers.shift {
+        text.push_str("Shift+");
+    }
+    text.push_str(shortcut.key.name());
+    text
+}
+
+fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key_name = parts.pop()?;
+    let key = BINDABLE_KEYS.iter().copied().find(|k| k.name().eq_ignore_ascii_case(key_name))?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" => modifiers = Modifiers { alt: modifiers.alt, shift: modifiers.shift, ..Modifiers::COMMAND },
+            "alt" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            _ => return None,
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}